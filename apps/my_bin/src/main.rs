@@ -5,7 +5,8 @@ use my_lib::debug_print;
 use my_lib::utils::{time, file};
 // use my_lib::utils::file::read_text_from_file;
 use neural_network::neural_network::NeuralNetwork;
-use neural_network::layer::mean_squared_error;
+use neural_network::optimizer::Sgd;
+use neural_network::trainer::{Callback, EpochStats, Trainer};
 
 use std::io;
 
@@ -51,18 +52,25 @@ fn test_neural_network() {
         (vec![1.0, 1.0], vec![0.0]),
     ];
 
-    let learning_rate = 0.1;
-    for epoch in 0..5000 {
-        let mut loss = 0.0;
-        for (input, target) in &data {
-            let prediction = network.forward(input);
-            loss += mean_squared_error(&prediction, target);
-            network.backward(input, target, learning_rate);
-        }
+    let result = Trainer::new(Sgd::new(0.5))
+        .with_batch_size(1)
+        .with_epochs(5000)
+        .with_shuffle_seed(42)
+        .with_callback(PrintLoss)
+        .fit(&mut network, &data);
+    if let Err(e) = result {
+        println!("训练失败: {}", e);
+    }
+}
+
+struct PrintLoss;
 
-        if epoch % 1000 == 0 {
-            println!("Epoch {}: Loss = {}", epoch, loss / data.len() as f64);
+impl Callback for PrintLoss {
+    fn on_epoch_end(&mut self, stats: &EpochStats) -> bool {
+        if stats.epoch % 1000 == 0 {
+            println!("Epoch {}: Loss = {}", stats.epoch, stats.train_loss);
         }
+        true
     }
 }
 
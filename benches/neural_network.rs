@@ -0,0 +1,113 @@
+// 对比神经网络旧的 Vec<Vec<f64>> 逐样本实现与新的矩阵批量实现
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use neural_network::activation::Activation;
+use neural_network::matrix::Matrix;
+use neural_network::neural_network::NeuralNetwork;
+use rand::Rng;
+
+const LAYER_SIZES: [usize; 4] = [784, 128, 64, 10];
+const BATCH_SIZE: usize = 64;
+
+fn random_vec(len: usize) -> Vec<f64> {
+    let mut rng = rand::rng();
+    (0..len).map(|_| rng.random_range(-1.0..1.0)).collect()
+}
+
+// 旧实现：权重按神经元分行存储，一次处理一个样本
+struct NaiveLayer {
+    weights: Vec<Vec<f64>>,
+    biases: Vec<f64>,
+    input: Vec<f64>,
+    output: Vec<f64>,
+}
+
+impl NaiveLayer {
+    fn new(input_size: usize, output_size: usize) -> Self {
+        NaiveLayer {
+            weights: (0..output_size).map(|_| random_vec(input_size)).collect(),
+            biases: random_vec(output_size),
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    fn forward(&mut self, input: &[f64]) -> Vec<f64> {
+        self.input = input.to_vec();
+        self.output = self.weights.iter().zip(self.biases.iter()).map(|(neuron_weights, bias)| {
+            let sum: f64 = neuron_weights.iter().zip(input.iter()).map(|(w, i)| w * i).sum();
+            1.0 / (1.0 + (-(sum + bias)).exp())
+        }).collect();
+        self.output.clone()
+    }
+
+    fn backward(&mut self, output_grad: &[f64], learning_rate: f64) -> Vec<f64> {
+        let mut input_grad = vec![0.0; self.input.len()];
+        for (i, neuron_weights) in self.weights.iter_mut().enumerate() {
+            let delta = output_grad[i] * self.output[i] * (1.0 - self.output[i]);
+            for (j, weight) in neuron_weights.iter_mut().enumerate() {
+                input_grad[j] += *weight * delta;
+                *weight -= learning_rate * delta * self.input[j];
+            }
+            self.biases[i] -= learning_rate * delta;
+        }
+        input_grad
+    }
+}
+
+fn naive_network() -> Vec<NaiveLayer> {
+    LAYER_SIZES.windows(2).map(|w| NaiveLayer::new(w[0], w[1])).collect()
+}
+
+fn bench_forward(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Neural Network Forward");
+    let samples: Vec<Vec<f64>> = (0..BATCH_SIZE).map(|_| random_vec(LAYER_SIZES[0])).collect();
+    let batch = Matrix::from_rows(&samples);
+
+    group.bench_function(BenchmarkId::new("Vec<Vec> per sample", BATCH_SIZE), |b| {
+        let mut layers = naive_network();
+        b.iter(|| {
+            for sample in &samples {
+                let output = layers.iter_mut().fold(sample.clone(), |acc, layer| layer.forward(&acc));
+                black_box(output);
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("Matrix forward_batch", BATCH_SIZE), |b| {
+        let mut network = NeuralNetwork::new(&LAYER_SIZES);
+        b.iter(|| black_box(network.forward_batch(black_box(&batch)).unwrap()))
+    });
+    group.finish();
+}
+
+fn bench_forward_backward(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Neural Network Forward + Backward");
+    let samples: Vec<Vec<f64>> = (0..BATCH_SIZE).map(|_| random_vec(LAYER_SIZES[0])).collect();
+    let targets: Vec<Vec<f64>> = (0..BATCH_SIZE).map(|_| random_vec(LAYER_SIZES[3])).collect();
+    let (inputs, target_matrix) = (Matrix::from_rows(&samples), Matrix::from_rows(&targets));
+
+    group.bench_function(BenchmarkId::new("Vec<Vec> per sample", BATCH_SIZE), |b| {
+        let mut layers = naive_network();
+        b.iter(|| {
+            for (sample, target) in samples.iter().zip(targets.iter()) {
+                let output = layers.iter_mut().fold(sample.clone(), |acc, layer| layer.forward(&acc));
+                let grad: Vec<f64> = output.iter().zip(target).map(|(p, t)| 2.0 * (p - t) / output.len() as f64).collect();
+                layers.iter_mut().rev().fold(grad, |acc, layer| layer.backward(&acc, 0.01));
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("Matrix batch", BATCH_SIZE), |b| {
+        let mut network = NeuralNetwork::with_activations(&LAYER_SIZES, &[Activation::Sigmoid; 3]);
+        b.iter(|| {
+            network.zero_grad();
+            network.forward_batch(black_box(&inputs)).unwrap();
+            black_box(network.compute_gradients_batch(&target_matrix).unwrap());
+            network.apply_gradients(0.01 / BATCH_SIZE as f64);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_forward, bench_forward_backward);
+criterion_main!(benches);
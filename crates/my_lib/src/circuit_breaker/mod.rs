@@ -0,0 +1,488 @@
+//! 熔断器：依赖（Redis、后端服务等）异常时快速失败，而不是继续堆积重试。
+//!
+//! 状态机：
+//!
+//! - `Closed`：正常放行，统计窗口内慢调用比例 / 异常比例 / 异常数超过阈值时转为 `Open`；
+//! - `Open`：直接拒绝，经过 `retry_timeout_ms` 后下一个请求把状态切到 `HalfOpen`；
+//! - `HalfOpen`：放行 `probe_num` 个探测请求，全部成功则 `Closed`，任一失败则重新 `Open`。
+//!   探测请求没有回报结果就被丢弃（future 被取消、闭包 panic）时按失败处理。
+//!
+//! ```no_run
+//! use my_lib::circuit_breaker::{CircuitBreaker, Rule, Strategy};
+//!
+//! let breaker = CircuitBreaker::new(Rule::new("redis", Strategy::ErrorRatio { threshold: 0.5 })).unwrap();
+//! breaker.add_state_change_listener(|from, to, rule: &Rule| {
+//!     println!("{}: {:?} -> {:?}", rule.resource, from, to);
+//! });
+//! let result = breaker.call(|| "127.0.0.1:6379".parse::<std::net::SocketAddr>());
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+
+use thiserror::Error;
+
+use crate::stat::{LeapArray, MetricEvent};
+use crate::utils::time::{self, Clock};
+
+pub use self::rule::{Rule, Strategy};
+
+mod rule;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BreakerError {
+    #[error("invalid circuit breaker rule for resource '{resource}': {reason}")]
+    InvalidRule { resource: String, reason: String },
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl State {
+    fn from_u8(value: u8) -> State {
+        match value {
+            0 => State::Closed,
+            1 => State::Open,
+            _ => State::HalfOpen,
+        }
+    }
+}
+
+/// 状态变化监听器，闭包 `Fn(State, State, &Rule)` 可以直接当作监听器使用
+pub trait StateChangeListener: Send + Sync {
+    fn on_state_change(&self, from: State, to: State, rule: &Rule);
+}
+
+impl<F> StateChangeListener for F
+where
+    F: Fn(State, State, &Rule) + Send + Sync,
+{
+    fn on_state_change(&self, from: State, to: State, rule: &Rule) {
+        self(from, to, rule)
+    }
+}
+
+/// 经熔断器包装后的调用结果
+#[derive(Debug, PartialEq)]
+pub enum CallError<E> {
+    /// 熔断器打开，请求未执行
+    Rejected { resource: String },
+    /// 请求执行了，但返回了错误
+    Inner(E),
+}
+
+impl<E: fmt::Display> fmt::Display for CallError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallError::Rejected { resource } => write!(f, "circuit breaker of '{}' is open", resource),
+            CallError::Inner(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for CallError<E> {}
+
+pub struct CircuitBreaker {
+    rule: Rule,
+    state: AtomicU8,
+    /// 处于 `Open` 时，到这个时间点（毫秒）之后才允许探测
+    next_retry_time: AtomicU64,
+    probes_admitted: AtomicU64,
+    probes_succeeded: AtomicU64,
+    stat: LeapArray,
+    listeners: RwLock<Vec<Arc<dyn StateChangeListener>>>,
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("rule", &self.rule)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(rule: Rule) -> Result<Self, BreakerError> {
+        Self::with_clock(rule, time::clock())
+    }
+
+    pub fn with_clock(rule: Rule, clock: Arc<dyn Clock>) -> Result<Self, BreakerError> {
+        rule.validate()?;
+        let stat = LeapArray::new(rule.stat_sample_count, rule.stat_interval_ms, clock);
+        Ok(CircuitBreaker {
+            rule,
+            state: AtomicU8::new(State::Closed as u8),
+            next_retry_time: AtomicU64::new(0),
+            probes_admitted: AtomicU64::new(0),
+            probes_succeeded: AtomicU64::new(0),
+            stat,
+            listeners: RwLock::new(Vec::new()),
+        })
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn state(&self) -> State {
+        State::from_u8(self.state.load(Ordering::SeqCst))
+    }
+
+    pub fn add_state_change_listener<L>(&self, listener: L)
+    where
+        L: StateChangeListener + 'static,
+    {
+        self.listeners.write().unwrap().push(Arc::new(listener));
+    }
+
+    /// 切换状态，只有当前状态确实是 `from` 时才会成功并通知监听器
+    fn transform(&self, from: State, to: State) -> bool {
+        if self
+            .state
+            .compare_exchange(from as u8, to as u8, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+        match to {
+            State::Open => {
+                let now = self.stat.clock().now_millis();
+                self.next_retry_time.store(now + self.rule.retry_timeout_ms, Ordering::SeqCst);
+            }
+            State::HalfOpen => {
+                self.probes_admitted.store(0, Ordering::SeqCst);
+                self.probes_succeeded.store(0, Ordering::SeqCst);
+            }
+            State::Closed => self.stat.reset(),
+        }
+        let listeners = self.listeners.read().unwrap().clone();
+        for listener in listeners {
+            listener.on_state_change(from, to, &self.rule);
+        }
+        true
+    }
+
+    /// 请求是否可以放行；放行后调用方必须通过 [`on_request_complete`](Self::on_request_complete) 回报结果。
+    /// 请求可能被取消时用 [`try_acquire`](Self::try_acquire)，否则半开状态下丢失的探测会让熔断器一直拒绝
+    pub fn try_pass(&self) -> bool {
+        self.admit().is_some()
+    }
+
+    /// 同 [`try_pass`](Self::try_pass)，放行时返回许可。
+    /// 许可没有调用 [`Permit::complete`] 就被丢弃时，如果它是半开状态下的探测，熔断器重新打开
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let probe = self.admit()?;
+        Some(Permit { breaker: self, start: self.stat.clock().now_millis(), probe, completed: false })
+    }
+
+    // 放行时返回这次请求是否是半开状态下的探测
+    fn admit(&self) -> Option<bool> {
+        match self.state() {
+            State::Closed => Some(false),
+            State::Open => {
+                let now = self.stat.clock().now_millis();
+                if now < self.next_retry_time.load(Ordering::SeqCst) {
+                    return None;
+                }
+                // 只有抢到状态切换的那个请求作为第一个探测
+                if self.transform(State::Open, State::HalfOpen) {
+                    self.probes_admitted.fetch_add(1, Ordering::SeqCst);
+                    Some(true)
+                } else {
+                    self.admit()
+                }
+            }
+            State::HalfOpen => {
+                (self.probes_admitted.fetch_add(1, Ordering::SeqCst) < self.rule.probe_num).then_some(true)
+            }
+        }
+    }
+
+    /// 回报一次已放行请求的结果
+    pub fn on_request_complete(&self, rt_ms: u64, is_error: bool) {
+        let is_slow = match self.rule.strategy {
+            Strategy::SlowRequestRatio { max_allowed_rt_ms, .. } => rt_ms > max_allowed_rt_ms,
+            _ => false,
+        };
+        if let Some(bucket) = self.stat.current_bucket() {
+            bucket.add(MetricEvent::Complete, 1);
+            bucket.add_rt(rt_ms);
+            if is_error {
+                bucket.add(MetricEvent::Error, 1);
+            }
+            if is_slow {
+                bucket.add(MetricEvent::Slow, 1);
+            }
+        }
+
+        match self.state() {
+            State::Closed => {
+                if self.exceeds_threshold() {
+                    self.transform(State::Closed, State::Open);
+                }
+            }
+            State::HalfOpen => {
+                let failed = match self.rule.strategy {
+                    Strategy::SlowRequestRatio { .. } => is_slow,
+                    Strategy::ErrorRatio { .. } | Strategy::ErrorCount { .. } => is_error,
+                };
+                if failed {
+                    self.transform(State::HalfOpen, State::Open);
+                } else if self.probes_succeeded.fetch_add(1, Ordering::SeqCst) + 1 >= self.rule.probe_num {
+                    self.transform(State::HalfOpen, State::Closed);
+                }
+            }
+            State::Open => {}
+        }
+    }
+
+    fn exceeds_threshold(&self) -> bool {
+        let total = self.stat.sum(MetricEvent::Complete);
+        if total < self.rule.min_request_amount || total == 0 {
+            return false;
+        }
+        match self.rule.strategy {
+            Strategy::SlowRequestRatio { threshold, .. } => {
+                self.stat.sum(MetricEvent::Slow) as f64 / total as f64 > threshold
+            }
+            Strategy::ErrorRatio { threshold } => {
+                self.stat.sum(MetricEvent::Error) as f64 / total as f64 > threshold
+            }
+            Strategy::ErrorCount { threshold } => self.stat.sum(MetricEvent::Error) >= threshold,
+        }
+    }
+
+    /// 通过熔断器执行同步调用
+    pub fn call<T, E, F>(&self, f: F) -> Result<T, CallError<E>>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        let Some(permit) = self.try_acquire() else {
+            return Err(CallError::Rejected { resource: self.rule.resource.clone() });
+        };
+        let result = f();
+        permit.finish(result.is_err());
+        result.map_err(CallError::Inner)
+    }
+
+    /// 通过熔断器执行异步调用
+    pub async fn call_async<T, E, Fut>(&self, fut: Fut) -> Result<T, CallError<E>>
+    where
+        Fut: Future<Output = Result<T, E>>,
+    {
+        // 调用方丢弃这个 future 时，许可随之丢弃
+        let Some(permit) = self.try_acquire() else {
+            return Err(CallError::Rejected { resource: self.rule.resource.clone() });
+        };
+        let result = fut.await;
+        permit.finish(result.is_err());
+        result.map_err(CallError::Inner)
+    }
+}
+
+/// [`CircuitBreaker::try_acquire`] 放行的一次请求，结果通过 [`complete`](Self::complete) 回报
+#[derive(Debug)]
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    start: u64,
+    probe: bool,
+    completed: bool,
+}
+
+impl Permit<'_> {
+    pub fn complete(mut self, rt_ms: u64, is_error: bool) {
+        self.completed = true;
+        self.breaker.on_request_complete(rt_ms, is_error);
+    }
+
+    /// 响应时间按放行到现在的时间计算
+    fn finish(self, is_error: bool) {
+        let rt = self.breaker.stat.clock().now_millis().saturating_sub(self.start);
+        self.complete(rt, is_error);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        // 丢失的探测按失败处理，否则半开状态永远等不到结果
+        if !self.completed && self.probe {
+            self.breaker.transform(State::HalfOpen, State::Open);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::MockClock;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn breaker(rule: Rule) -> (Arc<MockClock>, CircuitBreaker) {
+        let clock = Arc::new(MockClock::new(1_000_000));
+        let breaker = CircuitBreaker::with_clock(rule, clock.clone()).unwrap();
+        (clock, breaker)
+    }
+
+    fn record_transitions(breaker: &CircuitBreaker) -> Arc<Mutex<Vec<(State, State)>>> {
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&transitions);
+        breaker.add_state_change_listener(move |from, to, _: &Rule| {
+            recorder.lock().unwrap().push((from, to));
+        });
+        transitions
+    }
+
+    #[test]
+    fn test_error_ratio() {
+        let rule = Rule::new("error_ratio", Strategy::ErrorRatio { threshold: 0.5 })
+            .with_min_request_amount(4)
+            .with_retry_timeout_ms(1_000)
+            .with_probe_num(2);
+        let (clock, breaker) = breaker(rule);
+        let transitions = record_transitions(&breaker);
+
+        // 请求数不足时不熔断
+        for _ in 0..3 {
+            assert_eq!(breaker.call(|| Err::<(), _>("boom")), Err(CallError::Inner("boom")));
+        }
+        assert_eq!(breaker.state(), State::Closed);
+
+        assert!(breaker.call(|| Err::<(), _>("boom")).is_err());
+        assert_eq!(breaker.state(), State::Open);
+        assert_eq!(
+            breaker.call(|| Ok::<_, ()>(1)),
+            Err(CallError::Rejected { resource: "error_ratio".to_string() })
+        );
+
+        // 超时后进入半开，放行 probe_num 个探测
+        clock.advance(Duration::from_millis(1_000));
+        assert!(breaker.try_pass());
+        assert_eq!(breaker.state(), State::HalfOpen);
+        assert!(breaker.try_pass());
+        assert!(!breaker.try_pass());
+        breaker.on_request_complete(1, false);
+        assert_eq!(breaker.state(), State::HalfOpen);
+        breaker.on_request_complete(1, false);
+        assert_eq!(breaker.state(), State::Closed);
+
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![
+                (State::Closed, State::Open),
+                (State::Open, State::HalfOpen),
+                (State::HalfOpen, State::Closed),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let rule = Rule::new("probe", Strategy::ErrorCount { threshold: 2 })
+            .with_min_request_amount(1)
+            .with_retry_timeout_ms(500);
+        let (clock, breaker) = breaker(rule);
+
+        assert!(breaker.call(|| Err::<(), _>(())).is_err());
+        assert_eq!(breaker.state(), State::Closed);
+        assert!(breaker.call(|| Err::<(), _>(())).is_err());
+        assert_eq!(breaker.state(), State::Open);
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(breaker.call(|| Err::<(), _>(())), Err(CallError::Inner(())));
+        assert_eq!(breaker.state(), State::Open);
+
+        // 重新计时
+        clock.advance(Duration::from_millis(499));
+        assert!(!breaker.try_pass());
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(breaker.call(|| Ok::<_, ()>(7)), Ok(7));
+        assert_eq!(breaker.state(), State::Closed);
+    }
+
+    #[test]
+    fn test_slow_request_ratio() {
+        let rule = Rule::new(
+            "slow",
+            Strategy::SlowRequestRatio { max_allowed_rt_ms: 100, threshold: 0.4 },
+        )
+        .with_min_request_amount(5);
+        let (_clock, breaker) = breaker(rule);
+
+        for rt in [10, 200, 20, 300, 30] {
+            assert!(breaker.try_pass());
+            breaker.on_request_complete(rt, false);
+        }
+        // 2/5 = 0.4，未超过阈值
+        assert_eq!(breaker.state(), State::Closed);
+        breaker.on_request_complete(150, false);
+        assert_eq!(breaker.state(), State::Open);
+    }
+
+    #[test]
+    fn test_stat_window_expires() {
+        let rule = Rule::new("window", Strategy::ErrorCount { threshold: 2 })
+            .with_min_request_amount(1)
+            .with_stat_window(1_000, 2);
+        let (clock, breaker) = breaker(rule);
+
+        assert!(breaker.call(|| Err::<(), _>(())).is_err());
+        clock.advance(Duration::from_millis(1_000));
+        assert!(breaker.call(|| Err::<(), _>(())).is_err());
+        assert_eq!(breaker.state(), State::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_call_async() {
+        let rule = Rule::new("async", Strategy::ErrorCount { threshold: 1 }).with_min_request_amount(1);
+        let (_clock, breaker) = breaker(rule);
+
+        assert_eq!(breaker.call_async(async { Ok::<_, String>(1) }).await, Ok(1));
+        let err = breaker.call_async(async { Err::<i32, _>("timeout".to_string()) }).await;
+        assert_eq!(err, Err(CallError::Inner("timeout".to_string())));
+        let rejected = breaker.call_async(async { Ok::<_, String>(2) }).await;
+        assert_eq!(rejected.unwrap_err().to_string(), "circuit breaker of 'async' is open");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_probe_reopens() {
+        let rule = Rule::new("dropped", Strategy::ErrorCount { threshold: 1 })
+            .with_min_request_amount(1)
+            .with_retry_timeout_ms(500);
+        let (clock, breaker) = breaker(rule);
+        let transitions = record_transitions(&breaker);
+        assert!(breaker.call(|| Err::<(), _>(())).is_err());
+
+        // 探测还没完成就被超时取消
+        clock.advance(Duration::from_millis(500));
+        let probe = breaker.call_async(std::future::pending::<Result<(), ()>>());
+        assert!(tokio::time::timeout(Duration::from_millis(10), probe).await.is_err());
+        assert_eq!(breaker.state(), State::Open);
+        assert!(!breaker.try_pass());
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(breaker.call_async(async { Ok::<_, ()>(3) }).await, Ok(3));
+        assert_eq!(breaker.state(), State::Closed);
+        assert_eq!(transitions.lock().unwrap()[2], (State::HalfOpen, State::Open));
+
+        // 关闭状态下丢弃的许可不影响状态
+        drop(breaker.try_acquire().unwrap());
+        assert_eq!(breaker.state(), State::Closed);
+    }
+
+    #[test]
+    fn test_invalid_rule() {
+        let rule = Rule::new("invalid", Strategy::ErrorRatio { threshold: 1.5 });
+        assert!(CircuitBreaker::new(rule).is_err());
+        let rule = Rule::new("invalid", Strategy::ErrorCount { threshold: 1 }).with_stat_window(1_000, 3);
+        assert!(CircuitBreaker::new(rule).is_err());
+    }
+}
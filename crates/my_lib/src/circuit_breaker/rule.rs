@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use super::BreakerError;
+
+/// 熔断策略
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Strategy {
+    /// 慢调用比例：RT 超过 `max_allowed_rt_ms` 的调用占比超过 `threshold`（0~1）时熔断
+    SlowRequestRatio { max_allowed_rt_ms: u64, threshold: f64 },
+    /// 异常比例超过 `threshold`（0~1）时熔断
+    ErrorRatio { threshold: f64 },
+    /// 统计窗口内异常数达到 `threshold` 时熔断
+    ErrorCount { threshold: u64 },
+}
+
+/// 熔断规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub resource: String,
+    pub strategy: Strategy,
+    /// 熔断后多久进入半开状态尝试恢复（毫秒）
+    pub retry_timeout_ms: u64,
+    /// 统计窗口内请求数少于该值时不触发熔断
+    pub min_request_amount: u64,
+    /// 统计窗口长度（毫秒）
+    pub stat_interval_ms: u64,
+    /// 统计窗口的桶数，`stat_interval_ms` 必须能被它整除
+    pub stat_sample_count: usize,
+    /// 半开状态下放行的探测请求数，全部成功后才恢复
+    pub probe_num: u64,
+}
+
+impl Rule {
+    pub fn new(resource: impl Into<String>, strategy: Strategy) -> Self {
+        Rule {
+            resource: resource.into(),
+            strategy,
+            retry_timeout_ms: 5_000,
+            min_request_amount: 5,
+            stat_interval_ms: 1_000,
+            stat_sample_count: 1,
+            probe_num: 1,
+        }
+    }
+
+    pub fn with_retry_timeout_ms(mut self, retry_timeout_ms: u64) -> Self {
+        self.retry_timeout_ms = retry_timeout_ms;
+        self
+    }
+
+    pub fn with_min_request_amount(mut self, min_request_amount: u64) -> Self {
+        self.min_request_amount = min_request_amount;
+        self
+    }
+
+    pub fn with_stat_window(mut self, interval_ms: u64, sample_count: usize) -> Self {
+        self.stat_interval_ms = interval_ms;
+        self.stat_sample_count = sample_count;
+        self
+    }
+
+    pub fn with_probe_num(mut self, probe_num: u64) -> Self {
+        self.probe_num = probe_num;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), BreakerError> {
+        let invalid = |reason: &str| Err(BreakerError::InvalidRule {
+            resource: self.resource.clone(),
+            reason: reason.to_string(),
+        });
+        if self.resource.trim().is_empty() {
+            return invalid("empty resource name");
+        }
+        if self.stat_sample_count == 0
+            || self.stat_interval_ms == 0
+            || !self.stat_interval_ms.is_multiple_of(self.stat_sample_count as u64)
+        {
+            return invalid("stat_interval_ms must be a positive multiple of stat_sample_count");
+        }
+        if self.probe_num == 0 {
+            return invalid("probe_num must be positive");
+        }
+        match self.strategy {
+            Strategy::SlowRequestRatio { threshold, .. } | Strategy::ErrorRatio { threshold } => {
+                if !(0.0..=1.0).contains(&threshold) {
+                    return invalid("ratio threshold must be within [0, 1]");
+                }
+            }
+            Strategy::ErrorCount { threshold } => {
+                if threshold == 0 {
+                    return invalid("error count threshold must be positive");
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::stat::ResourceNode;
+
+use super::rule::Rule;
+
+/// 被流控拦截的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockReason {
+    /// QPS 超过阈值（含预热期间的动态阈值）
+    FlowQps { threshold: f64 },
+    /// 匀速排队模式下预计等待时间超过上限
+    QueueTimeout { max_queueing_time_ms: u32 },
+}
+
+/// `entry` 被拦截时返回的错误，带上触发的规则
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockError {
+    pub resource: String,
+    pub reason: BlockReason,
+    pub rule: Rule,
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.reason {
+            BlockReason::FlowQps { threshold } => {
+                write!(f, "resource '{}' blocked by flow rule: qps threshold {}", self.resource, threshold)
+            }
+            BlockReason::QueueTimeout { max_queueing_time_ms } => {
+                write!(
+                    f,
+                    "resource '{}' blocked by flow rule: queueing time exceeds {}ms",
+                    self.resource, max_queueing_time_ms
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+/// 通过流控检查后拿到的凭证，作用域结束（drop）时视为调用完成
+#[derive(Debug)]
+pub struct Entry {
+    resource: String,
+    batch_count: u32,
+    start_ms: u64,
+    stat: Arc<ResourceNode>,
+}
+
+impl Entry {
+    pub(crate) fn new(resource: String, batch_count: u32, stat: Arc<ResourceNode>) -> Self {
+        Entry {
+            resource,
+            batch_count,
+            start_ms: stat.clock().now_millis(),
+            stat,
+        }
+    }
+
+    pub fn resource(&self) -> &str {
+        &self.resource
+    }
+
+    pub fn batch_count(&self) -> u32 {
+        self.batch_count
+    }
+
+    /// 记录一次业务异常，熔断和监控据此统计错误数
+    pub fn trace_error(&self) {
+        self.stat.add_error(self.batch_count);
+    }
+
+    /// 显式结束本次调用，等价于 drop
+    pub fn exit(self) {}
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        let rt = self.stat.clock().now_millis().saturating_sub(self.start_ms);
+        self.stat.add_complete(self.batch_count, rt);
+    }
+}
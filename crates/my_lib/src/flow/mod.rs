@@ -0,0 +1,286 @@
+//! Sentinel 风格的流量控制。
+//!
+//! 规则按资源名生效，每个资源可以配置多条规则，调用方通过 [`entry`] 申请通过：
+//!
+//! ```no_run
+//! use my_lib::flow::{self, Rule};
+//!
+//! flow::load_rules(vec![Rule::new("GET:/users", 100.0)]).unwrap();
+//! match flow::entry("GET:/users") {
+//!     Ok(_entry) => { /* 执行业务逻辑，_entry 离开作用域即视为调用完成 */ }
+//!     Err(blocked) => println!("{}", blocked),
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub use self::entry::{BlockError, BlockReason, Entry};
+pub use self::rule::{ControlBehavior, Rule};
+pub use crate::stat::MetricSnapshot;
+
+mod entry;
+mod reject;
+mod rule;
+mod throttling;
+mod warm_up;
+
+use self::reject::RejectController;
+use crate::stat::ResourceNode;
+use self::throttling::ThrottlingController;
+use self::warm_up::WarmUpCalculator;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FlowError {
+    #[error("invalid flow rule for resource '{resource}': {reason}")]
+    InvalidRule { resource: String, reason: String },
+}
+
+/// 单个流控器的检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenResult {
+    Pass,
+    Blocked,
+    /// 需要排队等待指定时长后通过
+    Wait(Duration),
+}
+
+pub(crate) trait TrafficShapingController: Send + Sync {
+    fn rule(&self) -> &Rule;
+
+    fn check(&self, stat: &ResourceNode, batch_count: u32) -> TokenResult;
+}
+
+type ControllerMap = HashMap<String, Vec<Arc<dyn TrafficShapingController>>>;
+
+fn controllers() -> &'static RwLock<ControllerMap> {
+    static CONTROLLERS: OnceLock<RwLock<ControllerMap>> = OnceLock::new();
+    CONTROLLERS.get_or_init(Default::default)
+}
+
+fn stats() -> &'static RwLock<HashMap<String, Arc<ResourceNode>>> {
+    static STATS: OnceLock<RwLock<HashMap<String, Arc<ResourceNode>>>> = OnceLock::new();
+    STATS.get_or_init(Default::default)
+}
+
+fn stat_of(resource: &str) -> Arc<ResourceNode> {
+    if let Some(stat) = stats().read().unwrap().get(resource) {
+        return Arc::clone(stat);
+    }
+    let mut stats = stats().write().unwrap();
+    Arc::clone(stats.entry(resource.to_string()).or_default())
+}
+
+fn build_controller(rule: Rule) -> Arc<dyn TrafficShapingController> {
+    match rule.control_behavior {
+        ControlBehavior::Reject => Arc::new(RejectController::new(rule)),
+        ControlBehavior::WarmUp => Arc::new(WarmUpCalculator::new(rule)),
+        ControlBehavior::Throttling => Arc::new(ThrottlingController::new(rule)),
+    }
+}
+
+fn build_controllers(rules: Vec<Rule>) -> Result<ControllerMap, FlowError> {
+    let mut map = ControllerMap::new();
+    for rule in rules {
+        rule.validate()?;
+        map.entry(rule.resource.clone())
+            .or_default()
+            .push(build_controller(rule));
+    }
+    Ok(map)
+}
+
+/// 全量加载规则，替换掉所有已有规则；任一规则不合法时不做任何修改
+pub fn load_rules(rules: Vec<Rule>) -> Result<(), FlowError> {
+    let map = build_controllers(rules)?;
+    *controllers().write().unwrap() = map;
+    Ok(())
+}
+
+/// 只替换指定资源的规则，`rules` 为空时等同于清除该资源的规则
+pub fn load_rules_of_resource(resource: &str, rules: Vec<Rule>) -> Result<(), FlowError> {
+    if let Some(rule) = rules.iter().find(|r| r.resource != resource) {
+        return Err(FlowError::InvalidRule {
+            resource: rule.resource.clone(),
+            reason: format!("rule does not belong to resource '{}'", resource),
+        });
+    }
+    let mut map = build_controllers(rules)?;
+    let mut controllers = controllers().write().unwrap();
+    match map.remove(resource) {
+        Some(list) => controllers.insert(resource.to_string(), list),
+        None => controllers.remove(resource),
+    };
+    Ok(())
+}
+
+/// 当前生效的全部规则
+pub fn get_rules() -> Vec<Rule> {
+    controllers()
+        .read()
+        .unwrap()
+        .values()
+        .flatten()
+        .map(|c| c.rule().clone())
+        .collect()
+}
+
+pub fn get_rules_of_resource(resource: &str) -> Vec<Rule> {
+    controllers()
+        .read()
+        .unwrap()
+        .get(resource)
+        .map(|list| list.iter().map(|c| c.rule().clone()).collect())
+        .unwrap_or_default()
+}
+
+pub fn clear_rules() {
+    controllers().write().unwrap().clear();
+}
+
+/// 资源当前的统计数据，资源从未被访问过时返回 `None`
+pub fn metrics(resource: &str) -> Option<MetricSnapshot> {
+    stats().read().unwrap().get(resource).map(|stat| stat.snapshot())
+}
+
+/// 申请访问资源，等价于 `entry_with_count(resource, 1)`
+pub fn entry(resource: &str) -> Result<Entry, BlockError> {
+    entry_with_count(resource, 1)
+}
+
+/// 申请一次性通过 `batch_count` 个请求。
+///
+/// 匀速排队模式下可能阻塞当前线程直到轮到本次请求。
+/// 规则按加载顺序依次检查，排队规则放行时已经占用了队列中的位置；
+/// 之后的规则拒绝时这个位置不会退还，后面的请求照样要为它等待，与 Sentinel 的行为一致。
+pub fn entry_with_count(resource: &str, batch_count: u32) -> Result<Entry, BlockError> {
+    let stat = stat_of(resource);
+    let list = controllers()
+        .read()
+        .unwrap()
+        .get(resource)
+        .cloned()
+        .unwrap_or_default();
+
+    for controller in &list {
+        match controller.check(&stat, batch_count) {
+            TokenResult::Pass => {}
+            TokenResult::Wait(wait) => std::thread::sleep(wait),
+            TokenResult::Blocked => {
+                stat.add_block(batch_count);
+                let rule = controller.rule().clone();
+                let reason = match rule.control_behavior {
+                    ControlBehavior::Throttling => BlockReason::QueueTimeout {
+                        max_queueing_time_ms: rule.max_queueing_time_ms,
+                    },
+                    _ => BlockReason::FlowQps { threshold: rule.threshold },
+                };
+                return Err(BlockError {
+                    resource: resource.to_string(),
+                    reason,
+                    rule,
+                });
+            }
+        }
+    }
+
+    stat.add_pass(batch_count);
+    Ok(Entry::new(resource.to_string(), batch_count, stat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::MockClock;
+    use std::time::Instant;
+
+    /// 给资源换上模拟时钟，结果不受真实时间和统计窗口边界的影响；要在第一次 `entry` 之前调用
+    fn mock_clock(resource: &str) -> Arc<MockClock> {
+        let clock = Arc::new(MockClock::new(1_000_000));
+        stats().write().unwrap().insert(resource.to_string(), Arc::new(ResourceNode::new(clock.clone())));
+        clock
+    }
+
+    #[test]
+    fn test_reject() {
+        let resource = "test_reject";
+        mock_clock(resource);
+        load_rules_of_resource(resource, vec![Rule::new(resource, 2.0)]).unwrap();
+
+        assert!(entry(resource).is_ok());
+        assert!(entry(resource).is_ok());
+        let err = entry(resource).unwrap_err();
+        assert_eq!(err.reason, BlockReason::FlowQps { threshold: 2.0 });
+
+        let snapshot = metrics(resource).unwrap();
+        assert_eq!(snapshot.pass_qps, 2.0);
+        assert_eq!(snapshot.block_qps, 1.0);
+        assert_eq!(snapshot.complete_qps, 2.0);
+        assert_eq!(snapshot.error_qps, 0.0);
+    }
+
+    #[test]
+    fn test_trace_error() {
+        let resource = "test_trace_error";
+        let entry = entry(resource).unwrap();
+        entry.trace_error();
+        entry.exit();
+        assert_eq!(metrics(resource).unwrap().error_qps, 1.0);
+    }
+
+    #[test]
+    fn test_no_rule_always_pass() {
+        for _ in 0..100 {
+            assert!(entry("test_no_rule").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_warm_up_starts_cold() {
+        let resource = "test_warm_up";
+        mock_clock(resource);
+        let rule = Rule::new(resource, 30.0).with_warm_up(10, 3);
+        load_rules_of_resource(resource, vec![rule]).unwrap();
+
+        // 冷启动阶段只允许 30 / 3 = 10 QPS
+        let passed = (0..30).filter(|_| entry(resource).is_ok()).count();
+        assert_eq!(passed, 10);
+    }
+
+    #[test]
+    fn test_throttling() {
+        let resource = "test_throttling";
+        let clock = mock_clock(resource);
+        let rule = Rule::new(resource, 20.0).with_throttling(120);
+        load_rules_of_resource(resource, vec![rule]).unwrap();
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            entry(resource).unwrap();
+        }
+        // 20 QPS 即间隔 50ms，第二、三个请求分别真实地睡眠 50ms、100ms
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        // 模拟时钟追上队列后并发涌入，最多排队 120ms，即只能再放行 2 个
+        clock.advance(Duration::from_millis(100));
+        let handles: Vec<_> = (0..8)
+            .map(|_| std::thread::spawn(move || entry(resource).map(|_| ()).map_err(|e| e.reason)))
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        let blocked: Vec<_> = results.iter().filter_map(|r| r.clone().err()).collect();
+        assert_eq!(blocked.len(), 6);
+        assert!(blocked
+            .iter()
+            .all(|r| *r == BlockReason::QueueTimeout { max_queueing_time_ms: 120 }));
+    }
+
+    #[test]
+    fn test_load_rules_of_resource_rejects_foreign_rule() {
+        let result = load_rules_of_resource("a", vec![Rule::new("b", 1.0)]);
+        assert!(result.is_err());
+        assert!(get_rules_of_resource("a").is_empty());
+    }
+}
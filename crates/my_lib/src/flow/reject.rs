@@ -0,0 +1,30 @@
+use crate::stat::ResourceNode;
+
+use super::rule::Rule;
+use super::{TokenResult, TrafficShapingController};
+
+/// 直接拒绝：当前 QPS 加上本次请求数超过阈值即拒绝
+#[derive(Debug)]
+pub(crate) struct RejectController {
+    rule: Rule,
+}
+
+impl RejectController {
+    pub(crate) fn new(rule: Rule) -> Self {
+        RejectController { rule }
+    }
+}
+
+impl TrafficShapingController for RejectController {
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn check(&self, stat: &ResourceNode, batch_count: u32) -> TokenResult {
+        if stat.pass_qps() + batch_count as f64 > self.rule.threshold {
+            TokenResult::Blocked
+        } else {
+            TokenResult::Pass
+        }
+    }
+}
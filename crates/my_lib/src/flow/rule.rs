@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use super::FlowError;
+
+/// 达到阈值后的流控效果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ControlBehavior {
+    /// 超过阈值直接拒绝
+    #[default]
+    Reject,
+    /// 预热：令牌桶 + 冷启动斜率，阈值在 `warm_up_period_sec` 内逐步升到 `threshold`
+    WarmUp,
+    /// 匀速排队：漏桶，请求按固定间隔通过，排队超过 `max_queueing_time_ms` 则拒绝
+    Throttling,
+}
+
+/// 流控规则，按资源名生效
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// 资源名
+    pub resource: String,
+    /// QPS 阈值
+    pub threshold: f64,
+    pub control_behavior: ControlBehavior,
+    /// 预热时长（秒），仅 `WarmUp` 使用
+    pub warm_up_period_sec: u32,
+    /// 冷启动因子，冷启动时的阈值为 `threshold / warm_up_cold_factor`，仅 `WarmUp` 使用
+    pub warm_up_cold_factor: u32,
+    /// 最大排队等待时间（毫秒），仅 `Throttling` 使用
+    pub max_queueing_time_ms: u32,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            resource: String::new(),
+            threshold: 0.0,
+            control_behavior: ControlBehavior::Reject,
+            warm_up_period_sec: 10,
+            warm_up_cold_factor: 3,
+            max_queueing_time_ms: 500,
+        }
+    }
+}
+
+impl Rule {
+    pub fn new(resource: impl Into<String>, threshold: f64) -> Self {
+        Rule {
+            resource: resource.into(),
+            threshold,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_warm_up(mut self, period_sec: u32, cold_factor: u32) -> Self {
+        self.control_behavior = ControlBehavior::WarmUp;
+        self.warm_up_period_sec = period_sec;
+        self.warm_up_cold_factor = cold_factor;
+        self
+    }
+
+    pub fn with_throttling(mut self, max_queueing_time_ms: u32) -> Self {
+        self.control_behavior = ControlBehavior::Throttling;
+        self.max_queueing_time_ms = max_queueing_time_ms;
+        self
+    }
+
+    /// 校验规则参数是否合理
+    pub fn validate(&self) -> Result<(), FlowError> {
+        let invalid = |reason: &str| Err(FlowError::InvalidRule {
+            resource: self.resource.clone(),
+            reason: reason.to_string(),
+        });
+        if self.resource.trim().is_empty() {
+            return invalid("empty resource name");
+        }
+        if !self.threshold.is_finite() || self.threshold < 0.0 {
+            return invalid("threshold must be a non-negative finite number");
+        }
+        match self.control_behavior {
+            ControlBehavior::WarmUp => {
+                if self.warm_up_period_sec == 0 {
+                    return invalid("warm_up_period_sec must be positive");
+                }
+                if self.warm_up_cold_factor <= 1 {
+                    return invalid("warm_up_cold_factor must be greater than 1");
+                }
+            }
+            ControlBehavior::Throttling => {
+                if self.threshold == 0.0 {
+                    return invalid("throttling requires a positive threshold");
+                }
+            }
+            ControlBehavior::Reject => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(Rule::new("abc", 10.0).validate().is_ok());
+        assert!(Rule::new("", 10.0).validate().is_err());
+        assert!(Rule::new("abc", -1.0).validate().is_err());
+        assert!(Rule::new("abc", f64::NAN).validate().is_err());
+        assert!(Rule::new("abc", 10.0).with_warm_up(10, 1).validate().is_err());
+        assert!(Rule::new("abc", 0.0).with_throttling(100).validate().is_err());
+    }
+}
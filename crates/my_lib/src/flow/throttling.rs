@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::stat::ResourceNode;
+
+use super::rule::Rule;
+use super::{TokenResult, TrafficShapingController};
+
+/// 匀速排队（漏桶）：相邻请求至少间隔 `1s / threshold`，
+/// 需要排队时返回等待时长，排队时间超过 `max_queueing_time_ms` 则拒绝。
+#[derive(Debug)]
+pub(crate) struct ThrottlingController {
+    rule: Rule,
+    max_queueing_time_ns: u64,
+    /// 最近一个请求预计通过的时间点（纳秒）
+    last_passed_time: AtomicU64,
+}
+
+impl ThrottlingController {
+    pub(crate) fn new(rule: Rule) -> Self {
+        let max_queueing_time_ns = rule.max_queueing_time_ms as u64 * 1_000_000;
+        ThrottlingController {
+            rule,
+            max_queueing_time_ns,
+            last_passed_time: AtomicU64::new(0),
+        }
+    }
+
+    fn check_at(&self, now: u64, batch_count: u32) -> TokenResult {
+        // 每个请求占用的时间片，阈值极小时饱和到 u64::MAX
+        let cost = (batch_count as f64 * 1e9 / self.rule.threshold).round() as u64;
+
+        let last_passed = self.last_passed_time.load(Ordering::SeqCst);
+        let expected = last_passed.saturating_add(cost);
+        if expected <= now
+            && self
+                .last_passed_time
+                .compare_exchange(last_passed, now, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        {
+            return TokenResult::Pass;
+        }
+
+        let estimated_wait = expected.saturating_sub(now);
+        if estimated_wait > self.max_queueing_time_ns {
+            return TokenResult::Blocked;
+        }
+
+        // 先占位再复查，避免并发请求同时挤进队列；时间点溢出时拒绝
+        let Ok(old_time) = self
+            .last_passed_time
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |time| time.checked_add(cost))
+        else {
+            return TokenResult::Blocked;
+        };
+        let wait = (old_time + cost).saturating_sub(now);
+        if wait > self.max_queueing_time_ns {
+            self.last_passed_time.fetch_sub(cost, Ordering::SeqCst);
+            return TokenResult::Blocked;
+        }
+        if wait > 0 {
+            TokenResult::Wait(Duration::from_nanos(wait))
+        } else {
+            TokenResult::Pass
+        }
+    }
+}
+
+impl TrafficShapingController for ThrottlingController {
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn check(&self, stat: &ResourceNode, batch_count: u32) -> TokenResult {
+        self.check_at(stat.clock().now_nanos(), batch_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queueing() {
+        // 10 QPS => 每 100ms 放行一个，最多排队 250ms
+        let controller = ThrottlingController::new(Rule::new("abc", 10.0).with_throttling(250));
+        let now = 1_000_000_000_000;
+
+        assert_eq!(controller.check_at(now, 1), TokenResult::Pass);
+        assert_eq!(controller.check_at(now, 1), TokenResult::Wait(Duration::from_millis(100)));
+        assert_eq!(controller.check_at(now, 1), TokenResult::Wait(Duration::from_millis(200)));
+        assert_eq!(controller.check_at(now, 1), TokenResult::Blocked);
+
+        // 被拒绝的请求不占用队列
+        assert_eq!(
+            controller.check_at(now + 100_000_000, 1),
+            TokenResult::Wait(Duration::from_millis(200))
+        );
+    }
+
+    #[test]
+    fn test_tiny_threshold_does_not_overflow() {
+        // 每个请求的时间片饱和到 u64::MAX
+        let controller = ThrottlingController::new(Rule::new("abc", 1e-300).with_throttling(u32::MAX));
+        let now = 1_000_000_000_000;
+        assert_eq!(controller.check_at(now, 1), TokenResult::Blocked);
+        assert_eq!(controller.check_at(now, u32::MAX), TokenResult::Blocked);
+
+        controller.last_passed_time.store(u64::MAX - 10, Ordering::SeqCst);
+        let controller = ThrottlingController { max_queueing_time_ns: u64::MAX, ..controller };
+        assert_eq!(controller.check_at(now, 1), TokenResult::Blocked);
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use crate::stat::ResourceNode;
+use crate::utils::next_after;
+
+use super::rule::Rule;
+use super::{TokenResult, TrafficShapingController};
+
+/// 预热（冷启动）阈值计算，算法同 Guava 的 `SmoothWarmingUp`。
+///
+/// 令牌桶满时系统处于“冷”状态，允许的 QPS 只有 `threshold / cold_factor`；
+/// 随着请求持续消耗令牌，允许的 QPS 沿斜率 `slope` 升至 `threshold`。
+#[derive(Debug)]
+pub(crate) struct WarmUpCalculator {
+    rule: Rule,
+    warning_token: i64,
+    max_token: i64,
+    slope: f64,
+    stored_tokens: AtomicI64,
+    last_filled_time: AtomicU64,
+}
+
+impl WarmUpCalculator {
+    pub(crate) fn new(rule: Rule) -> Self {
+        let threshold = rule.threshold;
+        let period = rule.warm_up_period_sec as f64;
+        let cold_factor = rule.warm_up_cold_factor as f64;
+
+        let warning_token = (period * threshold / (cold_factor - 1.0)) as i64;
+        let max_token = warning_token + (2.0 * period * threshold / (1.0 + cold_factor)) as i64;
+        let slope = if max_token > warning_token {
+            (cold_factor - 1.0) / threshold / (max_token - warning_token) as f64
+        } else {
+            0.0
+        };
+
+        WarmUpCalculator {
+            rule,
+            warning_token,
+            max_token,
+            slope,
+            stored_tokens: AtomicI64::new(0),
+            last_filled_time: AtomicU64::new(0),
+        }
+    }
+
+    /// 根据当前剩余令牌数算出此刻允许的 QPS
+    fn current_threshold(&self) -> f64 {
+        let rest_token = self.stored_tokens.load(Ordering::SeqCst);
+        if rest_token >= self.warning_token {
+            let above_token = (rest_token - self.warning_token) as f64;
+            next_after(1.0 / (above_token * self.slope + 1.0 / self.rule.threshold))
+        } else {
+            self.rule.threshold
+        }
+    }
+
+    /// 每秒补充一次令牌，并扣掉上一秒实际通过的请求数
+    fn sync_token(&self, now: u64, previous_qps: f64) {
+        let current_time = now - now % 1000;
+        let old_last_filled = self.last_filled_time.load(Ordering::SeqCst);
+        if current_time <= old_last_filled {
+            return;
+        }
+
+        let old_value = self.stored_tokens.load(Ordering::SeqCst);
+        let new_value = self.cool_down_tokens(current_time, previous_qps);
+        if self
+            .stored_tokens
+            .compare_exchange(old_value, new_value, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            let current = self.stored_tokens.fetch_sub(previous_qps as i64, Ordering::SeqCst)
+                - previous_qps as i64;
+            if current < 0 {
+                self.stored_tokens.store(0, Ordering::SeqCst);
+            }
+            self.last_filled_time.store(current_time, Ordering::SeqCst);
+        }
+    }
+
+    fn cool_down_tokens(&self, current_time: u64, previous_qps: f64) -> i64 {
+        let old_value = self.stored_tokens.load(Ordering::SeqCst);
+        let last_filled = self.last_filled_time.load(Ordering::SeqCst);
+        let refill = ((current_time - last_filled) as f64 * self.rule.threshold / 1000.0) as i64;
+
+        let mut new_value = old_value;
+        if old_value < self.warning_token {
+            new_value = old_value.saturating_add(refill);
+        } else if old_value > self.warning_token
+            && previous_qps < self.rule.threshold / self.rule.warm_up_cold_factor as f64
+        {
+            // 流量低于冷启动阈值时系统会重新“变冷”
+            new_value = old_value.saturating_add(refill);
+        }
+        new_value.min(self.max_token)
+    }
+}
+
+impl TrafficShapingController for WarmUpCalculator {
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn check(&self, stat: &ResourceNode, batch_count: u32) -> TokenResult {
+        self.sync_token(stat.clock().now_millis(), stat.previous_pass_qps());
+        if stat.pass_qps() + batch_count as f64 <= self.current_threshold() {
+            TokenResult::Pass
+        } else {
+            TokenResult::Blocked
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cold_start_threshold() {
+        let calculator = WarmUpCalculator::new(Rule::new("abc", 30.0).with_warm_up(10, 3));
+        assert_eq!(calculator.warning_token, 150);
+        assert_eq!(calculator.max_token, 300);
+
+        // 首次同步会把令牌桶填满，此时只允许 threshold / cold_factor
+        calculator.sync_token(1_000_000, 0.0);
+        assert_eq!(calculator.stored_tokens.load(Ordering::SeqCst), 300);
+        let threshold = calculator.current_threshold();
+        assert!((threshold - 10.0).abs() < 1e-6, "cold threshold {}", threshold);
+
+        // 令牌消耗到警戒线以下后恢复到完整阈值
+        calculator.stored_tokens.store(100, Ordering::SeqCst);
+        assert_eq!(calculator.current_threshold(), 30.0);
+    }
+}
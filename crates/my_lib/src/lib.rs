@@ -26,4 +26,8 @@ macro_rules! debug_print {
 pub mod utils;
 mod protocol;
 pub mod arithmetic;
+pub mod flow;
+pub mod stat;
+pub mod circuit_breaker;
+pub mod scheduler;
 mod other;
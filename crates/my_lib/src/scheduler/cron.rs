@@ -0,0 +1,238 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::time::{civil_from_days, days_from_civil, days_in_month};
+
+use super::SchedulerError;
+
+/// cron 表达式，时间一律按 UTC 计算。
+///
+/// 支持 5 段（分 时 日 月 周）和 6 段（秒 分 时 日 月 周）两种格式，每段可以是
+/// `*`、`?`、数字、范围 `1-5`、步长 `*/15` / `10-50/10` 以及用逗号分隔的列表；
+/// 月份和星期也可以写英文缩写（`JAN`、`MON`），星期中 0 和 7 都表示周日。
+/// 与 Vixie cron 一致，日和周同时受限时只要满足其一即可；以 `*` 开头的段（包括 `*/2`）不算受限。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    source: String,
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// 最多向后搜索的天数，避免 `0 0 30 2 *` 这种永远不会触发的表达式死循环
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, SchedulerError> {
+        let invalid = |reason: String| SchedulerError::InvalidCron {
+            expr: expr.to_string(),
+            reason,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let (second, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            n => return Err(invalid(format!("expected 5 or 6 fields, got {}", n))),
+        };
+
+        let parse = |field: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(field, min, max, names).map_err(&invalid)
+        };
+        let seconds = parse(second, 0, 59, &[])?;
+        let minutes = parse(rest[0], 0, 59, &[])?;
+        let hours = parse(rest[1], 0, 23, &[])?;
+        let days_of_month = parse(rest[2], 1, 31, &[])?;
+        let months = parse(rest[3], 1, 12, &MONTH_NAMES)?;
+        let mut days_of_week = parse(rest[4], 0, 7, &WEEKDAY_NAMES)?;
+        // 7 也是周日
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(CronExpr {
+            source: expr.trim().to_string(),
+            seconds,
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            dom_restricted: is_restricted(rest[2]),
+            dow_restricted: is_restricted(rest[4]),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    fn day_matches(&self, day: u32, weekday: u32) -> bool {
+        let dom = self.days_of_month & (1 << day) != 0;
+        let dow = self.days_of_week & (1 << weekday) != 0;
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            _ => dom && dow,
+        }
+    }
+
+    /// 严格晚于 `after_secs`（unix 秒）的下一次触发时间
+    pub fn next_after(&self, after_secs: i64) -> Option<i64> {
+        let mut t = after_secs + 1;
+        let limit = after_secs + MAX_SEARCH_DAYS * 86_400;
+        while t <= limit {
+            let days = t.div_euclid(86_400);
+            let secs_of_day = t.rem_euclid(86_400);
+            let (year, month, day) = civil_from_days(days);
+            let (hour, minute, second) = (secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60);
+
+            if self.months & (1 << month) == 0 {
+                // 跳到下个月 1 号零点
+                let (y, m) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+                t = days_from_civil(y, m, 1) * 86_400;
+                continue;
+            }
+            let weekday = (days + 4).rem_euclid(7) as u32;
+            if day > days_in_month(year, month) || !self.day_matches(day, weekday) {
+                t = (days + 1) * 86_400;
+                continue;
+            }
+            if self.hours & (1 << hour) == 0 {
+                t = days * 86_400 + (hour + 1) * 3600;
+                continue;
+            }
+            if self.minutes & (1 << minute) == 0 {
+                t = days * 86_400 + hour * 3600 + (minute + 1) * 60;
+                continue;
+            }
+            if self.seconds & (1 << second) == 0 {
+                t += 1;
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+}
+
+impl FromStr for CronExpr {
+    type Err = SchedulerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CronExpr::parse(s)
+    }
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn is_wildcard(field: &str) -> bool {
+    field == "*" || field == "?"
+}
+
+/// Vixie cron 只看开头是否是 `*`
+fn is_restricted(field: &str) -> bool {
+    !(field.starts_with('*') || field == "?")
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let upper = value.to_ascii_uppercase();
+    let parsed = match names.iter().position(|name| *name == upper) {
+        // 月份从 1 开始，星期从 0 开始
+        Some(index) => index as u32 + min,
+        None => value.parse().map_err(|_| format!("invalid value '{}'", value))?,
+    };
+    if parsed < min || parsed > max {
+        return Err(format!("value {} out of range {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+/// 解析单个字段，返回按位表示的取值集合
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be positive".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if is_wildcard(range) {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max, names)?, parse_value(end, min, max, names)?)
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // `5/10` 表示从 5 开始每 10 个
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start > end {
+            return Err(format!("invalid range '{}'", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-02-29T04:00:00Z，周四
+    const BASE: i64 = 1_709_179_200;
+
+    fn next(expr: &str, after: i64) -> Option<i64> {
+        CronExpr::parse(expr).unwrap().next_after(after)
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in ["* * * *", "* * * * * * *", "60 * * * *", "* 24 * * *", "*/0 * * * *", "5-1 * * * *", "* * * FOO *"] {
+            assert!(CronExpr::parse(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_next_after() {
+        // 每分钟
+        assert_eq!(next("* * * * *", BASE), Some(BASE + 60));
+        // 每 15 秒
+        assert_eq!(next("*/15 * * * * *", BASE + 1), Some(BASE + 15));
+        // 每天 03:30
+        assert_eq!(next("30 3 * * *", BASE), Some(BASE + 86_400 - 1800));
+        // 闰年 2 月 29 日之后的下一个 2 月 29 日
+        assert_eq!(next("0 0 29 FEB *", BASE), Some(BASE - 4 * 3600 + (365 * 3 + 366) * 86_400));
+        // 周一 9 点：2024-03-04
+        assert_eq!(next("0 9 * * MON", BASE), Some(BASE + 4 * 86_400 + 5 * 3600));
+        // 周日写成 7
+        assert_eq!(next("0 0 * * 7", BASE), Some(BASE - 4 * 3600 + 3 * 86_400));
+        // 工作日 8-18 点整点
+        assert_eq!(next("0 8-18/2 * * 1-5", BASE), Some(BASE + 4 * 3600));
+        // 日和周同时受限时满足其一即可：3 月 1 日（周五）先于周一
+        assert_eq!(next("0 0 1 * MON", BASE), Some(BASE - 4 * 3600 + 86_400));
+        // `*/2` 不算受限，要同时满足：单数日的周一是 3 月 11 日
+        assert_eq!(next("0 0 */2 * MON", BASE), Some(BASE - 4 * 3600 + 11 * 86_400));
+    }
+
+    #[test]
+    fn test_never_fires() {
+        assert_eq!(next("0 0 30 2 *", BASE), None);
+    }
+}
@@ -0,0 +1,633 @@
+//! 定时任务调度：cron 表达式、固定频率（fixed rate）和固定延迟（fixed delay）三种方式，
+//! 任务是跑在 tokio 上的异步闭包。
+//!
+//! 配置了 [`JobStore`] 时，任务定义、上次运行时间、下次触发时间和最近一次错误都会写进
+//! SQLite 的 `scheduled_jobs` 表；进程重启后如果发现记录的下次触发时间已经过去，
+//! 会按 [`MisfirePolicy`] 补跑。
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use my_lib::scheduler::{Job, OverlapPolicy, Schedule, Scheduler};
+//!
+//! # async fn run() -> Result<(), my_lib::scheduler::SchedulerError> {
+//! let mut scheduler = Scheduler::new();
+//! scheduler.add(Job::new("warmup", "cron:0 */5 * * * *".parse()?, || async {
+//!     // 预热缓存
+//!     Ok(())
+//! }))
+//! .await?;
+//! scheduler.add(
+//!     Job::new("cleanup", Schedule::FixedRate(Duration::from_secs(60)), || async { Ok(()) })
+//!         .with_overlap(OverlapPolicy::Skip),
+//! )
+//! .await?;
+//! # scheduler.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use thiserror::Error;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::utils::time::{current_time_millis, format_duration, parse_duration};
+
+pub use self::cron::CronExpr;
+pub use self::store::{JobRecord, JobStore};
+
+mod cron;
+mod store;
+
+/// 补跑时最多执行的次数，防止停机很久之后一次性补跑成千上万次
+const MAX_CATCH_UP_RUNS: u64 = 100;
+
+#[derive(Error, Debug)]
+pub enum SchedulerError {
+    #[error("invalid cron expression '{expr}': {reason}")]
+    InvalidCron { expr: String, reason: String },
+    #[error("invalid schedule '{0}'")]
+    InvalidSchedule(String),
+    #[error("job '{0}' already exists")]
+    DuplicateJob(String),
+    #[error("job store error: {0}")]
+    Store(#[from] rusqlite::Error),
+}
+
+/// 调度方式，字符串形式为 `cron:<表达式>`、`rate:<时长>`、`delay:<时长>`，例如 `rate:30s`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    Cron(CronExpr),
+    /// 按固定频率触发，不受任务耗时影响
+    FixedRate(Duration),
+    /// 上一次运行结束后再等待固定时长
+    FixedDelay(Duration),
+}
+
+impl Schedule {
+    /// `last` 之后、且晚于 `now` 的下一次触发时间（毫秒）；固定频率会保持原来的相位
+    fn next_fire(&self, last: i64, now: i64) -> Option<i64> {
+        match self {
+            Schedule::Cron(expr) => expr.next_after(last.max(now).div_euclid(1000)).map(|secs| secs * 1000),
+            Schedule::FixedRate(period) => {
+                let period = period.as_millis().max(1) as i64;
+                let next = last + period;
+                if next > now {
+                    Some(next)
+                } else {
+                    Some(next + ((now - next) / period + 1) * period)
+                }
+            }
+            Schedule::FixedDelay(delay) => Some(now + delay.as_millis() as i64),
+        }
+    }
+
+    /// 从 `due` 到 `now`（含）之间错过的触发次数
+    fn missed_fires(&self, due: i64, now: i64) -> u64 {
+        match self {
+            Schedule::Cron(expr) => {
+                let mut count = 1;
+                let mut at = due;
+                while count < MAX_CATCH_UP_RUNS {
+                    match expr.next_after(at.div_euclid(1000)) {
+                        Some(secs) if secs * 1000 <= now => {
+                            at = secs * 1000;
+                            count += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                count
+            }
+            Schedule::FixedRate(period) => {
+                let period = period.as_millis().max(1) as i64;
+                (((now - due) / period + 1) as u64).min(MAX_CATCH_UP_RUNS)
+            }
+            Schedule::FixedDelay(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Schedule::Cron(expr) => write!(f, "cron:{}", expr),
+            Schedule::FixedRate(period) => write!(f, "rate:{}", format_duration(*period)),
+            Schedule::FixedDelay(delay) => write!(f, "delay:{}", format_duration(*delay)),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = SchedulerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SchedulerError::InvalidSchedule(s.to_string());
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+        let duration = || match parse_duration(value) {
+            Ok(duration) if !duration.is_zero() => Ok(duration),
+            _ => Err(invalid()),
+        };
+        match kind.trim() {
+            "cron" => Ok(Schedule::Cron(value.parse()?)),
+            "rate" => Ok(Schedule::FixedRate(duration()?)),
+            "delay" => Ok(Schedule::FixedDelay(duration()?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// 上一次运行还没结束时又到了触发时间的处理方式（对 `FixedDelay` 不起作用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// 跳过本次触发
+    #[default]
+    Skip,
+    /// 允许并发运行
+    AllowConcurrent,
+    /// 等上一次结束后再运行
+    Wait,
+}
+
+impl OverlapPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlapPolicy::Skip => "skip",
+            OverlapPolicy::AllowConcurrent => "allow_concurrent",
+            OverlapPolicy::Wait => "wait",
+        }
+    }
+}
+
+/// 重启后发现错过了触发时间的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MisfirePolicy {
+    /// 不补跑，直接等下一次
+    Skip,
+    /// 不管错过几次，只补跑一次
+    #[default]
+    RunOnce,
+    /// 错过几次补跑几次（最多 100 次），依次串行执行
+    RunAll,
+}
+
+impl MisfirePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MisfirePolicy::Skip => "skip",
+            MisfirePolicy::RunOnce => "run_once",
+            MisfirePolicy::RunAll => "run_all",
+        }
+    }
+}
+
+type JobFn = Arc<dyn Fn() -> BoxFuture<'static, anyhow::Result<()>> + Send + Sync>;
+
+/// 定时任务定义
+pub struct Job {
+    name: String,
+    schedule: Schedule,
+    overlap: OverlapPolicy,
+    misfire: MisfirePolicy,
+    task: JobFn,
+}
+
+impl Job {
+    pub fn new<F, Fut>(name: impl Into<String>, schedule: Schedule, task: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Job {
+            name: name.into(),
+            schedule,
+            overlap: OverlapPolicy::default(),
+            misfire: MisfirePolicy::default(),
+            task: Arc::new(move || Box::pin(task())),
+        }
+    }
+
+    pub fn with_overlap(mut self, overlap: OverlapPolicy) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn with_misfire(mut self, misfire: MisfirePolicy) -> Self {
+        self.misfire = misfire;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+}
+
+/// 单个任务运行时共享的状态
+struct JobRunner {
+    name: String,
+    schedule: Schedule,
+    overlap: OverlapPolicy,
+    misfire: MisfirePolicy,
+    task: JobFn,
+    store: Option<JobStore>,
+    running: Arc<Mutex<()>>,
+}
+
+impl JobRunner {
+    /// 执行一次并记录结果；任务 panic 也按失败处理
+    async fn run(&self) {
+        let started_at = current_time_millis() as i64;
+        let error = match tokio::spawn((self.task)()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(format!("{:#}", e)),
+            Err(e) => Some(e.to_string()),
+        };
+        // 持久化失败不影响调度本身
+        let name = self.name.clone();
+        self.with_store(move |store| store.record_run(&name, started_at, error.as_deref())).await;
+    }
+
+    /// 在阻塞线程池里访问存储；没有存储时返回 `None`，访问失败不影响调度
+    async fn with_store<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&JobStore) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        blocking(self.store.clone()?, f).await.ok()
+    }
+
+    /// 独占地执行一次，等待正在运行的实例结束
+    async fn run_exclusive(&self) {
+        let _guard = self.running.lock().await;
+        self.run().await;
+    }
+
+    /// 按重叠策略触发一次，不等待任务结束
+    fn fire(self: &Arc<Self>) {
+        let runner = self.clone();
+        match self.overlap {
+            OverlapPolicy::Skip => {
+                let Ok(guard) = self.running.clone().try_lock_owned() else {
+                    return;
+                };
+                tokio::spawn(async move {
+                    runner.run().await;
+                    drop(guard);
+                });
+            }
+            OverlapPolicy::Wait => {
+                tokio::spawn(async move { runner.run_exclusive().await });
+            }
+            OverlapPolicy::AllowConcurrent => {
+                tokio::spawn(async move { runner.run().await });
+            }
+        }
+    }
+
+    async fn record_next_run(&self, next: Option<i64>) {
+        let name = self.name.clone();
+        self.with_store(move |store| store.record_next_run(&name, next)).await;
+    }
+
+    /// 计算第一次触发时间，必要时先补跑错过的运行
+    async fn first_fire(&self) -> Option<i64> {
+        let name = self.name.clone();
+        let stored = self
+            .with_store(move |store| store.get(&name))
+            .await
+            .and_then(|record| record.ok().flatten())
+            .and_then(|record| record.next_run_at);
+        let now = current_time_millis() as i64;
+        match stored {
+            Some(due) if due <= now => {
+                let runs = match self.misfire {
+                    MisfirePolicy::Skip => 0,
+                    MisfirePolicy::RunOnce => 1,
+                    MisfirePolicy::RunAll => self.schedule.missed_fires(due, now),
+                };
+                for _ in 0..runs {
+                    self.run_exclusive().await;
+                }
+                self.schedule.next_fire(due, current_time_millis() as i64)
+            }
+            Some(due) => Some(due),
+            None => self.schedule.next_fire(now, now),
+        }
+    }
+
+    async fn run_loop(self: Arc<Self>, mut shutdown: watch::Receiver<bool>) {
+        let mut next = self.first_fire().await;
+        while let Some(at) = next {
+            self.record_next_run(Some(at)).await;
+            let wait = at - current_time_millis() as i64;
+            if wait > 0 {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(wait as u64)) => {}
+                    _ = shutdown.changed() => return,
+                }
+            }
+            if *shutdown.borrow() {
+                return;
+            }
+            next = match self.schedule {
+                Schedule::FixedDelay(_) => {
+                    self.run_exclusive().await;
+                    let now = current_time_millis() as i64;
+                    self.schedule.next_fire(now, now)
+                }
+                _ => {
+                    self.fire();
+                    self.schedule.next_fire(at, current_time_millis() as i64)
+                }
+            };
+        }
+        // cron 表达式不会再触发
+        self.record_next_run(None).await;
+    }
+}
+
+/// 在阻塞线程池里访问存储，SQLite 的读写不占用运行时的工作线程
+async fn blocking<R, F>(store: JobStore, f: F) -> Result<R, tokio::task::JoinError>
+where
+    F: FnOnce(&JobStore) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&store)).await
+}
+
+/// 任务调度器，`add` 需要在 tokio 运行时内调用
+///
+/// `shutdown` 或 drop 之后不再触发新的运行，已经在跑的任务会继续执行完。
+pub struct Scheduler {
+    store: Option<JobStore>,
+    jobs: HashMap<String, JoinHandle<()>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    /// 不做持久化的调度器
+    pub fn new() -> Self {
+        Scheduler {
+            store: None,
+            jobs: HashMap::new(),
+            shutdown: watch::channel(false).0,
+        }
+    }
+
+    pub fn with_store(store: JobStore) -> Self {
+        let mut scheduler = Self::new();
+        scheduler.store = Some(store);
+        scheduler
+    }
+
+    pub fn store(&self) -> Option<&JobStore> {
+        self.store.as_ref()
+    }
+
+    /// 注册并立即开始调度一个任务
+    pub async fn add(&mut self, job: Job) -> Result<(), SchedulerError> {
+        if self.jobs.contains_key(&job.name) {
+            return Err(SchedulerError::DuplicateJob(job.name));
+        }
+        let (name, schedule, overlap, misfire) =
+            (job.name.clone(), job.schedule.to_string(), job.overlap.as_str(), job.misfire.as_str());
+        self.access_store(move |store| store.upsert_definition(&name, &schedule, overlap, misfire))
+            .await
+            .transpose()?;
+        let runner = Arc::new(JobRunner {
+            name: job.name.clone(),
+            schedule: job.schedule,
+            overlap: job.overlap,
+            misfire: job.misfire,
+            task: job.task,
+            store: self.store.clone(),
+            running: Arc::new(Mutex::new(())),
+        });
+        let handle = tokio::spawn(runner.run_loop(self.shutdown.subscribe()));
+        self.jobs.insert(job.name, handle);
+        Ok(())
+    }
+
+    /// 停止调度并删除持久化记录
+    pub async fn remove(&mut self, name: &str) -> Result<bool, SchedulerError> {
+        let Some(handle) = self.jobs.remove(name) else {
+            return Ok(false);
+        };
+        handle.abort();
+        let name = name.to_string();
+        self.access_store(move |store| store.remove(&name)).await.transpose()?;
+        Ok(true)
+    }
+
+    /// 与 `JobRunner` 一样在阻塞线程池里访问存储；没有存储时返回 `None`，闭包 panic 时在这里重新抛出
+    async fn access_store<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&JobStore) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        match blocking(self.store.clone()?, f).await {
+            Ok(result) => Some(result),
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    pub fn job_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.jobs.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 停止所有任务的调度，等待调度循环（包括正在进行的补跑）退出
+    pub async fn shutdown(mut self) {
+        let _ = self.shutdown.send(true);
+        for (_, handle) in self.jobs.drain() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::sqlite_util::SqliteDb;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::NamedTempFile;
+
+    fn counting_job(name: &str, schedule: Schedule, counter: Arc<AtomicUsize>) -> Job {
+        Job::new(name, schedule, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+    }
+
+    /// 记录最大并发数的慢任务
+    fn slow_job(name: &str, schedule: Schedule, current: Arc<AtomicUsize>, max: Arc<AtomicUsize>) -> Job {
+        Job::new(name, schedule, move || {
+            let (current, max) = (current.clone(), max.clone());
+            async move {
+                let running = current.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                current.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+    }
+
+    #[test]
+    fn test_schedule_round_trip() {
+        for text in ["cron:0 */5 * * * *", "rate:30s", "delay:1m30s"] {
+            assert_eq!(text.parse::<Schedule>().unwrap().to_string(), text);
+        }
+        for bad in ["30s", "rate:0s", "every:1s", "cron:* *"] {
+            assert!(bad.parse::<Schedule>().is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_next_fire_keeps_phase() {
+        let rate = Schedule::FixedRate(Duration::from_millis(100));
+        assert_eq!(rate.next_fire(1_000, 1_000), Some(1_100));
+        // 落后了好几个周期，跳到 now 之后的第一个周期点
+        assert_eq!(rate.next_fire(1_000, 1_350), Some(1_400));
+        assert_eq!(rate.missed_fires(1_000, 1_350), 4);
+    }
+
+    #[tokio::test]
+    async fn test_fixed_rate() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut scheduler = Scheduler::new();
+        scheduler
+            .add(counting_job("tick", Schedule::FixedRate(Duration::from_millis(50)), counter.clone())).await
+            .unwrap();
+        assert!(matches!(
+            scheduler.add(counting_job("tick", Schedule::FixedRate(Duration::from_secs(1)), counter.clone())).await,
+            Err(SchedulerError::DuplicateJob(_))
+        ));
+
+        tokio::time::sleep(Duration::from_millis(330)).await;
+        scheduler.shutdown().await;
+        let runs = counter.load(Ordering::SeqCst);
+        assert!((4..=7).contains(&runs), "runs = {}", runs);
+
+        // shutdown 之后不再触发
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), runs);
+    }
+
+    #[tokio::test]
+    async fn test_overlap_policy() {
+        let rate = Schedule::FixedRate(Duration::from_millis(20));
+        let (current, skip_max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let mut scheduler = Scheduler::new();
+        scheduler.add(slow_job("skip", rate.clone(), current, skip_max.clone())).await.unwrap();
+
+        let (current, allow_max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        scheduler
+            .add(slow_job("allow", rate, current, allow_max.clone()).with_overlap(OverlapPolicy::AllowConcurrent)).await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        scheduler.shutdown().await;
+        assert_eq!(skip_max.load(Ordering::SeqCst), 1);
+        assert!(allow_max.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn test_fixed_delay_never_overlaps() {
+        let (current, max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let mut scheduler = Scheduler::new();
+        scheduler
+            .add(slow_job("delay", Schedule::FixedDelay(Duration::from_millis(20)), current, max.clone())).await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        scheduler.shutdown().await;
+        assert_eq!(max.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_after_restart() {
+        let file = NamedTempFile::new().unwrap();
+        let rate = Schedule::FixedRate(Duration::from_secs(1));
+
+        for (misfire, expected) in [(MisfirePolicy::Skip, 0), (MisfirePolicy::RunOnce, 1), (MisfirePolicy::RunAll, 6)] {
+            // 模拟上次进程停机前记录的状态：5.5 秒前就该触发了
+            let store = JobStore::new(SqliteDb::connect(file.path()).unwrap()).unwrap();
+            let due = current_time_millis() as i64 - 5_500;
+            store.upsert_definition("report", &rate.to_string(), "skip", misfire.as_str()).unwrap();
+            store.record_next_run("report", Some(due)).unwrap();
+
+            let counter = Arc::new(AtomicUsize::new(0));
+            let mut scheduler = Scheduler::with_store(store.clone());
+            scheduler
+                .add(counting_job("report", rate.clone(), counter.clone()).with_misfire(misfire)).await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            scheduler.shutdown().await;
+
+            assert_eq!(counter.load(Ordering::SeqCst), expected, "{:?}", misfire);
+            let record = store.get("report").unwrap().unwrap();
+            // 补跑后保持原来的相位
+            assert_eq!(record.next_run_at, Some(due + 6_000));
+            assert_eq!(record.last_run_at.is_some(), expected > 0);
+            store.remove("report").unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failure_is_recorded() {
+        let store = JobStore::new(SqliteDb::connect(":memory:").unwrap()).unwrap();
+        let mut scheduler = Scheduler::with_store(store.clone());
+        scheduler
+            .add(Job::new("broken", Schedule::FixedRate(Duration::from_millis(20)), || async {
+                anyhow::bail!("connection refused")
+            })).await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        scheduler.shutdown().await;
+
+        let record = store.get("broken").unwrap().unwrap();
+        assert_eq!(record.last_error.as_deref(), Some("connection refused"));
+        assert_eq!(record.schedule, "rate:20ms");
+    }
+
+    #[tokio::test]
+    async fn test_remove_deletes_record() {
+        let store = JobStore::new(SqliteDb::connect(":memory:").unwrap()).unwrap();
+        let mut scheduler = Scheduler::with_store(store.clone());
+        let counter = Arc::new(AtomicUsize::new(0));
+        scheduler.add(counting_job("tick", Schedule::FixedRate(Duration::from_secs(1)), counter)).await.unwrap();
+        assert!(store.get("tick").unwrap().is_some());
+
+        assert!(scheduler.remove("tick").await.unwrap());
+        assert!(store.get("tick").unwrap().is_none());
+        assert!(!scheduler.remove("tick").await.unwrap());
+        assert!(scheduler.job_names().is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+
+use crate::utils::sqlite_util::SqliteDb;
+
+use super::SchedulerError;
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS scheduled_jobs (
+    name TEXT PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    overlap TEXT NOT NULL,
+    misfire TEXT NOT NULL,
+    last_run_at INTEGER,
+    next_run_at INTEGER,
+    last_error TEXT
+)";
+
+/// 持久化的任务定义和运行状态，时间均为 unix 毫秒
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRecord {
+    pub name: String,
+    pub schedule: String,
+    pub overlap: String,
+    pub misfire: String,
+    pub last_run_at: Option<i64>,
+    pub next_run_at: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+/// 基于 [`SqliteDb`] 的任务存储，数据保存在 `scheduled_jobs` 表中
+#[derive(Clone)]
+pub struct JobStore {
+    db: Arc<Mutex<SqliteDb>>,
+}
+
+impl JobStore {
+    pub fn new(db: SqliteDb) -> Result<Self, SchedulerError> {
+        Self::shared(Arc::new(Mutex::new(db)))
+    }
+
+    /// 与其他模块共用同一个连接
+    pub fn shared(db: Arc<Mutex<SqliteDb>>) -> Result<Self, SchedulerError> {
+        db.lock().unwrap().execute(CREATE_TABLE, &[])?;
+        Ok(JobStore { db })
+    }
+
+    /// 写入任务定义；调度方式变化时清空上次记录的下次触发时间
+    pub fn upsert_definition(&self, name: &str, schedule: &str, overlap: &str, misfire: &str) -> Result<(), SchedulerError> {
+        self.db.lock().unwrap().execute(
+            "INSERT INTO scheduled_jobs (name, schedule, overlap, misfire) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(name) DO UPDATE SET
+                next_run_at = CASE WHEN schedule = excluded.schedule THEN next_run_at ELSE NULL END,
+                schedule = excluded.schedule,
+                overlap = excluded.overlap,
+                misfire = excluded.misfire",
+            &[&name, &schedule, &overlap, &misfire],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_next_run(&self, name: &str, next_run_at: Option<i64>) -> Result<(), SchedulerError> {
+        self.db.lock().unwrap().execute(
+            "UPDATE scheduled_jobs SET next_run_at = ?2 WHERE name = ?1",
+            &[&name, &next_run_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_run(&self, name: &str, run_at: i64, error: Option<&str>) -> Result<(), SchedulerError> {
+        self.db.lock().unwrap().execute(
+            "UPDATE scheduled_jobs SET last_run_at = ?2, last_error = ?3 WHERE name = ?1",
+            &[&name, &run_at, &error],
+        )?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<JobRecord>, SchedulerError> {
+        let records = self.query("SELECT * FROM scheduled_jobs WHERE name = ?1", &[&name])?;
+        Ok(records.into_iter().next())
+    }
+
+    pub fn list(&self) -> Result<Vec<JobRecord>, SchedulerError> {
+        self.query("SELECT * FROM scheduled_jobs ORDER BY name", &[])
+    }
+
+    pub fn remove(&self, name: &str) -> Result<bool, SchedulerError> {
+        let removed = self.db.lock().unwrap().execute("DELETE FROM scheduled_jobs WHERE name = ?1", &[&name])?;
+        Ok(removed > 0)
+    }
+
+    fn query(&self, sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<JobRecord>, SchedulerError> {
+        let records = self.db.lock().unwrap().query_map(sql, params, |row| {
+            Ok(JobRecord {
+                name: row.get("name")?,
+                schedule: row.get("schedule")?,
+                overlap: row.get("overlap")?,
+                misfire: row.get("misfire")?,
+                last_run_at: row.get("last_run_at")?,
+                next_run_at: row.get("next_run_at")?,
+                last_error: row.get("last_error")?,
+            })
+        })?;
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_round_trip() {
+        let file = NamedTempFile::new().unwrap();
+        {
+            let store = JobStore::new(SqliteDb::connect(file.path()).unwrap()).unwrap();
+            store.upsert_definition("cleanup", "rate:30s", "skip", "run_once").unwrap();
+            store.record_next_run("cleanup", Some(1_000)).unwrap();
+            store.record_run("cleanup", 900, Some("boom")).unwrap();
+        }
+
+        // 重新打开后状态还在
+        let store = JobStore::new(SqliteDb::connect(file.path()).unwrap()).unwrap();
+        let record = store.get("cleanup").unwrap().unwrap();
+        assert_eq!(record.schedule, "rate:30s");
+        assert_eq!(record.next_run_at, Some(1_000));
+        assert_eq!(record.last_run_at, Some(900));
+        assert_eq!(record.last_error.as_deref(), Some("boom"));
+
+        // 定义不变时保留下次触发时间，变了则清空
+        store.upsert_definition("cleanup", "rate:30s", "wait", "skip").unwrap();
+        assert_eq!(store.get("cleanup").unwrap().unwrap().next_run_at, Some(1_000));
+        store.upsert_definition("cleanup", "rate:1m", "wait", "skip").unwrap();
+        let record = store.get("cleanup").unwrap().unwrap();
+        assert_eq!(record.next_run_at, None);
+        assert_eq!(record.overlap, "wait");
+
+        assert!(store.remove("cleanup").unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 统计事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetricEvent {
+    /// 通过规则检查
+    Pass,
+    /// 被规则拦截
+    Block,
+    /// 调用完成
+    Complete,
+    /// 业务异常
+    Error,
+    /// 响应时间累计（毫秒）
+    Rt,
+    /// 响应时间超过阈值的慢调用，由熔断器统计
+    Slow,
+}
+
+impl MetricEvent {
+    pub(crate) const ALL: [MetricEvent; 6] = [
+        MetricEvent::Pass,
+        MetricEvent::Block,
+        MetricEvent::Complete,
+        MetricEvent::Error,
+        MetricEvent::Rt,
+        MetricEvent::Slow,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 单个时间窗口内的计数，全部用原子量实现
+#[derive(Debug)]
+pub struct MetricBucket {
+    counters: [AtomicU64; MetricEvent::ALL.len()],
+    min_rt: AtomicU64,
+}
+
+impl Default for MetricBucket {
+    fn default() -> Self {
+        MetricBucket {
+            counters: Default::default(),
+            min_rt: AtomicU64::new(u64::MAX),
+        }
+    }
+}
+
+impl MetricBucket {
+    pub fn add(&self, event: MetricEvent, count: u64) {
+        self.counters[event.index()].fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, event: MetricEvent) -> u64 {
+        self.counters[event.index()].load(Ordering::Relaxed)
+    }
+
+    /// 累计一次响应时间，同时更新最小 RT
+    pub fn add_rt(&self, rt_ms: u64) {
+        self.add(MetricEvent::Rt, rt_ms);
+        self.min_rt.fetch_min(rt_ms, Ordering::Relaxed);
+    }
+
+    /// 窗口内的最小 RT，没有数据时为 `u64::MAX`
+    pub fn min_rt(&self) -> u64 {
+        self.min_rt.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reset(&self) {
+        for counter in &self.counters {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.min_rt.store(u64::MAX, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::utils::time::Clock;
+
+use super::bucket::{MetricBucket, MetricEvent};
+
+/// 桶正在被某个线程重置
+const RESETTING: u64 = u64::MAX;
+
+#[derive(Debug, Default)]
+struct BucketWrap {
+    /// 桶对应时间窗口的起点（毫秒）
+    start: AtomicU64,
+    bucket: MetricBucket,
+}
+
+/// 滑动窗口统计，由 `sample_count` 个等长的时间桶组成，总长 `interval_ms`。
+///
+/// 桶按 `(now / bucket_length) % sample_count` 循环复用，过期的桶由第一个
+/// 发现它过期的线程通过 CAS 抢到重置权，整个过程不加锁。
+#[derive(Debug)]
+pub struct LeapArray {
+    bucket_length_ms: u64,
+    interval_ms: u64,
+    buckets: Box<[BucketWrap]>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LeapArray {
+    /// # Panics
+    ///
+    /// `sample_count` 为 0 或 `interval_ms` 不能被 `sample_count` 整除时 panic。
+    pub fn new(sample_count: usize, interval_ms: u64, clock: Arc<dyn Clock>) -> Self {
+        assert!(sample_count > 0, "sample_count must be positive");
+        assert!(
+            interval_ms > 0 && interval_ms.is_multiple_of(sample_count as u64),
+            "interval_ms({}) must be a positive multiple of sample_count({})",
+            interval_ms,
+            sample_count
+        );
+        LeapArray {
+            bucket_length_ms: interval_ms / sample_count as u64,
+            interval_ms,
+            buckets: (0..sample_count).map(|_| BucketWrap::default()).collect(),
+            clock,
+        }
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    pub fn bucket_length_ms(&self) -> u64 {
+        self.bucket_length_ms
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// 取得 `now` 所在的桶，必要时先重置过期的桶；时钟回拨时返回 `None`
+    pub fn bucket_at(&self, now: u64) -> Option<&MetricBucket> {
+        let wrap = &self.buckets[((now / self.bucket_length_ms) % self.buckets.len() as u64) as usize];
+        let start = now - now % self.bucket_length_ms;
+        loop {
+            let old = wrap.start.load(Ordering::Acquire);
+            if old == start {
+                return Some(&wrap.bucket);
+            } else if old == RESETTING {
+                std::hint::spin_loop();
+            } else if old < start {
+                if wrap
+                    .start
+                    .compare_exchange(old, RESETTING, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    wrap.bucket.reset();
+                    wrap.start.store(start, Ordering::Release);
+                    return Some(&wrap.bucket);
+                }
+            } else {
+                return None;
+            }
+        }
+    }
+
+    pub fn current_bucket(&self) -> Option<&MetricBucket> {
+        self.bucket_at(self.clock.now_millis())
+    }
+
+    pub fn add(&self, event: MetricEvent, count: u64) {
+        if let Some(bucket) = self.current_bucket() {
+            bucket.add(event, count);
+        }
+    }
+
+    /// 记录一次完成调用的响应时间
+    pub fn add_rt(&self, rt_ms: u64) {
+        if let Some(bucket) = self.current_bucket() {
+            bucket.add_rt(rt_ms);
+        }
+    }
+
+    /// `now` 所在窗口内仍然有效的桶
+    fn valid_buckets(&self, now: u64) -> impl Iterator<Item = &MetricBucket> {
+        let interval = self.interval_ms;
+        self.buckets.iter().filter_map(move |wrap| {
+            let start = wrap.start.load(Ordering::Acquire);
+            (start != RESETTING && start <= now && now - start < interval).then_some(&wrap.bucket)
+        })
+    }
+
+    /// 当前窗口内某类事件的总数
+    pub fn sum(&self, event: MetricEvent) -> u64 {
+        let now = self.clock.now_millis();
+        self.valid_buckets(now).map(|b| b.get(event)).sum()
+    }
+
+    /// 每秒事件数
+    pub fn qps(&self, event: MetricEvent) -> f64 {
+        self.sum(event) as f64 * 1000.0 / self.interval_ms as f64
+    }
+
+    /// 平均响应时间（毫秒），窗口内没有完成的调用时为 0
+    pub fn avg_rt(&self) -> f64 {
+        let now = self.clock.now_millis();
+        let (rt, complete) = self
+            .valid_buckets(now)
+            .fold((0, 0), |(rt, complete), b| (rt + b.get(MetricEvent::Rt), complete + b.get(MetricEvent::Complete)));
+        if complete == 0 {
+            0.0
+        } else {
+            rt as f64 / complete as f64
+        }
+    }
+
+    /// 窗口内的最小响应时间（毫秒）
+    pub fn min_rt(&self) -> Option<u64> {
+        let now = self.clock.now_millis();
+        self.valid_buckets(now)
+            .map(|b| b.min_rt())
+            .min()
+            .filter(|rt| *rt != u64::MAX)
+    }
+
+    /// 起点恰好为 `time` 所在桶起点的那个桶的计数，桶已被复用时为 0
+    pub fn bucket_value_at(&self, event: MetricEvent, time: u64) -> u64 {
+        let wrap = &self.buckets[((time / self.bucket_length_ms) % self.buckets.len() as u64) as usize];
+        if wrap.start.load(Ordering::Acquire) == time - time % self.bucket_length_ms {
+            wrap.bucket.get(event)
+        } else {
+            0
+        }
+    }
+
+    /// 清空所有桶
+    pub fn reset(&self) {
+        for wrap in self.buckets.iter() {
+            wrap.start.store(0, Ordering::Release);
+            wrap.bucket.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::MockClock;
+    use std::time::Duration;
+
+    fn leap_array(start: u64) -> (Arc<MockClock>, LeapArray) {
+        let clock = Arc::new(MockClock::new(start));
+        let array = LeapArray::new(4, 1000, clock.clone());
+        (clock, array)
+    }
+
+    #[test]
+    fn test_bucket_rollover() {
+        let (clock, array) = leap_array(10_000);
+        array.add(MetricEvent::Pass, 1);
+        clock.advance(Duration::from_millis(250));
+        array.add(MetricEvent::Pass, 2);
+        clock.advance(Duration::from_millis(250));
+        array.add(MetricEvent::Pass, 3);
+        assert_eq!(array.sum(MetricEvent::Pass), 6);
+        assert_eq!(array.qps(MetricEvent::Pass), 6.0);
+
+        // 10_000 所在的桶滑出窗口
+        clock.set_millis(11_000);
+        assert_eq!(array.sum(MetricEvent::Pass), 5);
+
+        // 同一个槽位被新窗口复用时先清零
+        array.add(MetricEvent::Pass, 10);
+        assert_eq!(array.sum(MetricEvent::Pass), 15);
+        assert_eq!(array.bucket_value_at(MetricEvent::Pass, 10_000), 0);
+        assert_eq!(array.bucket_value_at(MetricEvent::Pass, 11_000), 10);
+
+        clock.set_millis(20_000);
+        assert_eq!(array.sum(MetricEvent::Pass), 0);
+    }
+
+    #[test]
+    fn test_rt() {
+        let (clock, array) = leap_array(0);
+        assert_eq!(array.min_rt(), None);
+        assert_eq!(array.avg_rt(), 0.0);
+
+        for rt in [30, 10, 20] {
+            array.add(MetricEvent::Complete, 1);
+            array.add_rt(rt);
+            clock.advance(Duration::from_millis(300));
+        }
+        assert_eq!(array.min_rt(), Some(10));
+        assert_eq!(array.avg_rt(), 20.0);
+
+        // 最小 RT 所在的桶过期后跟着更新
+        clock.set_millis(1_350);
+        assert_eq!(array.min_rt(), Some(20));
+    }
+
+    #[test]
+    fn test_clock_backwards() {
+        let (clock, array) = leap_array(10_000);
+        array.add(MetricEvent::Pass, 1);
+        clock.set_millis(9_000);
+        assert!(array.current_bucket().is_none());
+        array.add(MetricEvent::Pass, 1);
+        clock.set_millis(10_000);
+        assert_eq!(array.sum(MetricEvent::Pass), 1);
+    }
+
+    #[test]
+    fn test_concurrent_add() {
+        let (_clock, array) = leap_array(5_000);
+        let array = Arc::new(array);
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let array = Arc::clone(&array);
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        array.add(MetricEvent::Pass, 1);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(array.sum(MetricEvent::Pass), 8000);
+    }
+}
@@ -0,0 +1,9 @@
+//! 基于滑动窗口（LeapArray）的实时统计，供流控和熔断使用。
+
+pub use self::bucket::{MetricBucket, MetricEvent};
+pub use self::leap_array::LeapArray;
+pub use self::node::{MetricSnapshot, ResourceNode};
+
+mod bucket;
+mod leap_array;
+mod node;
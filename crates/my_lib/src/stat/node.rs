@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use crate::utils::time::{self, Clock};
+
+use super::bucket::MetricEvent;
+use super::leap_array::LeapArray;
+
+/// 资源的统计快照
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MetricSnapshot {
+    pub pass_qps: f64,
+    pub block_qps: f64,
+    pub complete_qps: f64,
+    pub error_qps: f64,
+    /// 平均响应时间（毫秒）
+    pub avg_rt_ms: f64,
+    /// 最小响应时间（毫秒），没有完成的调用时为 `None`
+    pub min_rt_ms: Option<u64>,
+}
+
+/// 单个资源的统计节点：秒级窗口用于实时 QPS/RT，分钟级窗口保留最近 60 秒的明细
+#[derive(Debug)]
+pub struct ResourceNode {
+    second: LeapArray,
+    minute: LeapArray,
+}
+
+impl Default for ResourceNode {
+    fn default() -> Self {
+        Self::new(time::clock())
+    }
+}
+
+impl ResourceNode {
+    /// 秒级窗口默认 2 个桶，每桶 500ms
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self::with_window(2, 1000, clock)
+    }
+
+    /// 自定义秒级窗口的桶数和总时长
+    pub fn with_window(sample_count: usize, interval_ms: u64, clock: Arc<dyn Clock>) -> Self {
+        ResourceNode {
+            second: LeapArray::new(sample_count, interval_ms, Arc::clone(&clock)),
+            minute: LeapArray::new(60, 60_000, clock),
+        }
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        self.second.clock()
+    }
+
+    pub fn add_pass(&self, count: u32) {
+        self.second.add(MetricEvent::Pass, count as u64);
+        self.minute.add(MetricEvent::Pass, count as u64);
+    }
+
+    pub fn add_block(&self, count: u32) {
+        self.second.add(MetricEvent::Block, count as u64);
+        self.minute.add(MetricEvent::Block, count as u64);
+    }
+
+    pub fn add_error(&self, count: u32) {
+        self.second.add(MetricEvent::Error, count as u64);
+        self.minute.add(MetricEvent::Error, count as u64);
+    }
+
+    /// 记录调用完成及其响应时间
+    pub fn add_complete(&self, count: u32, rt_ms: u64) {
+        for array in [&self.second, &self.minute] {
+            array.add(MetricEvent::Complete, count as u64);
+            array.add_rt(rt_ms);
+        }
+    }
+
+    pub fn pass_qps(&self) -> f64 {
+        self.second.qps(MetricEvent::Pass)
+    }
+
+    pub fn block_qps(&self) -> f64 {
+        self.second.qps(MetricEvent::Block)
+    }
+
+    pub fn complete_qps(&self) -> f64 {
+        self.second.qps(MetricEvent::Complete)
+    }
+
+    pub fn error_qps(&self) -> f64 {
+        self.second.qps(MetricEvent::Error)
+    }
+
+    pub fn avg_rt(&self) -> f64 {
+        self.second.avg_rt()
+    }
+
+    pub fn min_rt(&self) -> Option<u64> {
+        self.second.min_rt()
+    }
+
+    /// 上一整秒通过的请求数
+    pub fn previous_pass_qps(&self) -> f64 {
+        let now = self.clock().now_millis();
+        self.minute.bucket_value_at(MetricEvent::Pass, now.saturating_sub(1000)) as f64
+    }
+
+    /// 最近一分钟内某类事件的总数
+    pub fn total_in_minute(&self, event: MetricEvent) -> u64 {
+        self.minute.sum(event)
+    }
+
+    pub fn snapshot(&self) -> MetricSnapshot {
+        MetricSnapshot {
+            pass_qps: self.pass_qps(),
+            block_qps: self.block_qps(),
+            complete_qps: self.complete_qps(),
+            error_qps: self.error_qps(),
+            avg_rt_ms: self.avg_rt(),
+            min_rt_ms: self.min_rt(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::MockClock;
+    use std::time::Duration;
+
+    #[test]
+    fn test_node() {
+        let clock = Arc::new(MockClock::new(1_000_000));
+        let node = ResourceNode::new(clock.clone());
+
+        node.add_pass(3);
+        node.add_block(1);
+        node.add_complete(1, 12);
+        node.add_complete(1, 8);
+        node.add_error(1);
+        assert_eq!(
+            node.snapshot(),
+            MetricSnapshot {
+                pass_qps: 3.0,
+                block_qps: 1.0,
+                complete_qps: 2.0,
+                error_qps: 1.0,
+                avg_rt_ms: 10.0,
+                min_rt_ms: Some(8),
+            }
+        );
+
+        clock.advance(Duration::from_millis(1000));
+        assert_eq!(node.pass_qps(), 0.0);
+        assert_eq!(node.previous_pass_qps(), 3.0);
+        assert_eq!(node.total_in_minute(MetricEvent::Pass), 3);
+    }
+}
@@ -10,7 +10,7 @@ pub mod custom_error;
 #[allow(non_snake_case)]
 mod CustomError;
 pub mod redis_util;
-mod sqlite_util;
+pub mod sqlite_util;
 mod sqlite_tool_async;
 
 pub fn is_blank(path: &str) -> bool {
@@ -20,7 +20,6 @@ pub fn is_blank(path: &str) -> bool {
 /// not a general implememtation,
 /// only used in our `core::flow::WarmUpCalculator`,
 /// which won't overflow as long as parameter in rule is rational
-#[allow(unused)]
 pub(crate) fn next_after(x: f64) -> f64 {
     let x = x.to_bits();
     let x = if (x >> 63) == 0 { x + 1 } else { x - 1 };
@@ -27,6 +27,16 @@ impl SqliteDb {
         rows.collect()
     }
 
+    /// 查询数据并用闭包逐行映射，适合不方便实现 `From<Row>` 的场景
+    pub fn query_map<T, F>(&self, sql: &str, params: &[&dyn rusqlite::ToSql], f: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&Row<'_>) -> Result<T>,
+    {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(params, f)?;
+        rows.collect()
+    }
+
     /// 开启事务
     pub fn begin_transaction(&mut self) -> Result<usize> {
         self.conn.execute("BEGIN TRANSACTION;", [])
@@ -1,13 +1,464 @@
 //! Timer implementation for the Sentinel.
 //! It supports a cached timer and a real-time timer from `unix_timestamp_nanos`.
 
+use std::fmt::{Debug, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+pub use self::wheel::{TimerId, TimerService, TimingWheel};
+
+pub mod wheel;
+
 #[inline]
 pub fn sleep_for_ms(ms: u64) {
-    std::thread::sleep(std::time::Duration::from_millis(ms));
-    println!("sleep_for_ms!");
+    std::thread::sleep(Duration::from_millis(ms));
 }
 
 #[inline]
 pub fn sleep_for_ns(ns: u64) {
-    std::thread::sleep(std::time::Duration::from_nanos(ns));
+    std::thread::sleep(Duration::from_nanos(ns));
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TimeError {
+    #[error("invalid duration '{0}'")]
+    InvalidDuration(String),
+    #[error("invalid RFC 3339 timestamp '{0}'")]
+    InvalidTimestamp(String),
+}
+
+/// 可注入的时钟，统计和流控模块通过它取时间，测试时可换成 [`MockClock`]
+pub trait Clock: Debug + Send + Sync {
+    /// unix 时间戳（毫秒）
+    fn now_millis(&self) -> u64 {
+        self.now_nanos() / 1_000_000
+    }
+
+    /// unix 时间戳（纳秒）
+    fn now_nanos(&self) -> u64;
+}
+
+/// 每次调用都读取系统时间
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_nanos(&self) -> u64 {
+        unix_nanos(SystemTime::now())
+    }
+}
+
+/// 毫秒精度的缓存时钟：后台线程每隔 `tick` 刷新一次，读时间只是一次原子读。
+///
+/// 高频调用（每个请求都要取时间的流控、统计）时比直接读系统时间开销低得多，
+/// 时钟被 drop 后后台线程自动退出。
+#[derive(Debug)]
+pub struct CachedClock {
+    millis: Arc<AtomicU64>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl CachedClock {
+    /// 以 1ms 的间隔启动后台刷新线程
+    pub fn start() -> Self {
+        Self::with_tick(Duration::from_millis(1))
+    }
+
+    pub fn with_tick(tick: Duration) -> Self {
+        let millis = Arc::new(AtomicU64::new(SystemClock.now_millis()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        {
+            let millis = Arc::clone(&millis);
+            let stopped = Arc::clone(&stopped);
+            std::thread::Builder::new()
+                .name("cached-clock-ticker".to_string())
+                .spawn(move || {
+                    while !stopped.load(Ordering::Relaxed) {
+                        std::thread::sleep(tick);
+                        millis.store(SystemClock.now_millis(), Ordering::Relaxed);
+                    }
+                })
+                .expect("failed to spawn cached clock ticker");
+        }
+        CachedClock { millis, stopped }
+    }
+}
+
+impl Drop for CachedClock {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Clock for CachedClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::Relaxed)
+    }
+
+    fn now_nanos(&self) -> u64 {
+        self.now_millis() * 1_000_000
+    }
+}
+
+/// 手动拨动的时钟，只在测试里使用
+#[derive(Debug, Default)]
+pub struct MockClock {
+    nanos: AtomicU64,
+}
+
+impl MockClock {
+    pub fn new(start_millis: u64) -> Self {
+        MockClock {
+            nanos: AtomicU64::new(start_millis * 1_000_000),
+        }
+    }
+
+    pub fn set_millis(&self, millis: u64) {
+        self.nanos.store(millis * 1_000_000, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_nanos(&self) -> u64 {
+        self.nanos.load(Ordering::SeqCst)
+    }
+}
+
+fn global_clock() -> &'static RwLock<Arc<dyn Clock>> {
+    static CLOCK: OnceLock<RwLock<Arc<dyn Clock>>> = OnceLock::new();
+    CLOCK.get_or_init(|| RwLock::new(Arc::new(SystemClock)))
+}
+
+/// 全局时钟，默认是 [`SystemClock`]
+pub fn clock() -> Arc<dyn Clock> {
+    Arc::clone(&global_clock().read().unwrap())
+}
+
+/// 替换全局时钟，例如 `set_clock(Arc::new(CachedClock::start()))`；
+/// 只影响之后创建的统计节点、熔断器
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    *global_clock().write().unwrap() = clock;
+}
+
+/// 当前 unix 时间戳（毫秒），取自全局时钟
+#[inline]
+pub fn current_time_millis() -> u64 {
+    clock().now_millis()
+}
+
+/// 当前 unix 时间戳（纳秒），取自全局时钟
+#[inline]
+pub fn current_time_nanos() -> u64 {
+    clock().now_nanos()
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+const DURATION_UNITS: [(&str, u128); 7] = [
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// 解析 "1h30m"、"1.5s"、"250ms" 这类可读的时长，单位支持 d/h/m/s/ms/us(µs)/ns
+pub fn parse_duration(s: &str) -> Result<Duration, TimeError> {
+    let invalid = || TimeError::InvalidDuration(s.to_string());
+    let input = s.trim();
+    if input.is_empty() {
+        return Err(invalid());
+    }
+    if input == "0" {
+        return Ok(Duration::ZERO);
+    }
+
+    let mut rest = input;
+    let mut total_nanos: u128 = 0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(invalid)?;
+        let unit_len = rest[number_len..]
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len() - number_len);
+        let (number, unit) = (&rest[..number_len], &rest[number_len..number_len + unit_len]);
+        rest = &rest[number_len + unit_len..];
+
+        let unit = if unit == "µs" { "us" } else { unit };
+        let scale = DURATION_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, scale)| *scale)
+            .ok_or_else(invalid)?;
+
+        let (int_part, frac_part) = number.split_once('.').unwrap_or((number, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(invalid());
+        }
+        let int_value: u128 = if int_part.is_empty() { 0 } else { int_part.parse().map_err(|_| invalid())? };
+        let mut nanos = int_value.checked_mul(scale).ok_or_else(invalid)?;
+        if !frac_part.is_empty() {
+            // u128::from_str 接受前导的 '+'
+            if !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            let frac_value: u128 = frac_part.parse().map_err(|_| invalid())?;
+            let denominator = 10u128.checked_pow(frac_part.len() as u32).ok_or_else(invalid)?;
+            let frac_nanos = frac_value.checked_mul(scale).ok_or_else(invalid)? / denominator;
+            nanos = nanos.checked_add(frac_nanos).ok_or_else(invalid)?;
+        }
+        total_nanos = total_nanos.checked_add(nanos).ok_or_else(invalid)?;
+    }
+
+    let secs = u64::try_from(total_nanos / 1_000_000_000).map_err(|_| invalid())?;
+    Ok(Duration::new(secs, (total_nanos % 1_000_000_000) as u32))
+}
+
+/// 把时长格式化成 "1h30m"、"2m3s500ms" 这样的形式，是 [`parse_duration`] 的逆操作
+pub fn format_duration(duration: Duration) -> String {
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+    let mut out = String::new();
+    for (unit, scale) in DURATION_UNITS {
+        let value = nanos / scale;
+        if value > 0 {
+            let _ = write!(out, "{}{}", value, unit);
+            nanos %= scale;
+        }
+    }
+    out
+}
+
+/// 公历日期到 1970-01-01 的天数（Howard Hinnant 的 days_from_civil 算法）
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// [`days_from_civil`] 的逆运算，返回 (年, 月, 日)
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub(crate) fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// 格式化为 UTC 的 RFC 3339 时间，毫秒精度，如 `2024-02-29T04:00:00.000Z`
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let millis = unix_nanos(time) / 1_000_000;
+    let (days, ms_of_day) = ((millis / 86_400_000) as i64, millis % 86_400_000);
+    let (year, month, day) = civil_from_days(days);
+    let secs = ms_of_day / 1000;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        ms_of_day % 1000
+    )
+}
+
+/// 解析 RFC 3339 时间，如 `2024-02-29T12:00:00+08:00`、`2024-02-29T04:00:00.123Z`；
+/// 早于 1970-01-01 的时间不支持
+pub fn parse_rfc3339(s: &str) -> Result<SystemTime, TimeError> {
+    let invalid = || TimeError::InvalidTimestamp(s.to_string());
+    let bytes = s.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return Err(invalid());
+    }
+    let number = |range: std::ops::Range<usize>| -> Result<u32, TimeError> {
+        let part = s.get(range).ok_or_else(invalid)?;
+        if !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        part.parse().map_err(|_| invalid())
+    };
+
+    let year = number(0..4)? as i64;
+    let (month, day) = (number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+
+    // 小数秒
+    let mut pos = 19;
+    let mut nanos: u64 = 0;
+    if bytes[pos] == b'.' {
+        let digits = bytes[pos + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(invalid());
+        }
+        for (i, b) in bytes[pos + 1..pos + 1 + digits].iter().enumerate() {
+            if i < 9 {
+                nanos += (b - b'0') as u64 * 10u64.pow(8 - i as u32);
+            }
+        }
+        pos += 1 + digits;
+    }
+
+    // 时区偏移
+    let offset_secs: i64 = match &s[pos..] {
+        "Z" | "z" => 0,
+        offset if offset.len() == 6 && offset.as_bytes()[3] == b':' => {
+            let sign = match offset.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return Err(invalid()),
+            };
+            let (h, m) = (number(pos + 1..pos + 3)?, number(pos + 4..pos + 6)?);
+            if h > 23 || m > 59 {
+                return Err(invalid());
+            }
+            sign * (h as i64 * 3600 + m as i64 * 60)
+        }
+        _ => return Err(invalid()),
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400
+        + hour as i64 * 3600
+        + minute as i64 * 60
+        + second as i64
+        - offset_secs;
+    let secs = u64::try_from(secs).map_err(|_| invalid())?;
+    Ok(UNIX_EPOCH + Duration::new(secs, nanos as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::new(1_000);
+        assert_eq!(clock.now_millis(), 1_000);
+        clock.advance(Duration::from_micros(1_500));
+        assert_eq!(clock.now_millis(), 1_001);
+        assert_eq!(clock.now_nanos(), 1_001_500_000);
+        clock.set_millis(5);
+        assert_eq!(clock.now_millis(), 5);
+    }
+
+    #[test]
+    fn test_cached_clock() {
+        let clock = CachedClock::with_tick(Duration::from_millis(5));
+        let first = clock.now_millis();
+        assert!(first.abs_diff(SystemClock.now_millis()) < 50);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(clock.now_millis() > first);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(2 * 86_400)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1m0.5s10µs"), Ok(Duration::from_micros(60_500_010)));
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+        assert_eq!(parse_duration(" 42ns "), Ok(Duration::from_nanos(42)));
+        // 小数部分乘以单位后溢出 u128
+        let long_fraction = "0.99999999999999999999999999999999999999d";
+        for bad in ["", "10", "h", "1x", "1.2.3s", "-1s", "1h 30m", "1.+5s", long_fraction] {
+            assert!(parse_duration(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_duration(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration(Duration::from_millis(123_500)), "2m3s500ms");
+        let d = Duration::new(90_061, 1_001);
+        assert_eq!(parse_duration(&format_duration(d)), Ok(d));
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        let time = parse_rfc3339("2024-02-29T12:00:00+08:00").unwrap();
+        assert_eq!(unix_nanos(time) / 1_000_000, 1_709_179_200_000);
+        assert_eq!(format_rfc3339(time), "2024-02-29T04:00:00.000Z");
+
+        let time = parse_rfc3339("1970-01-01T00:00:01.123456789Z").unwrap();
+        assert_eq!(unix_nanos(time), 1_123_456_789);
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+
+        let time = parse_rfc3339("2000-12-31T23:59:59.5-01:30").unwrap();
+        assert_eq!(format_rfc3339(time), "2001-01-01T01:29:59.500Z");
+
+        for bad in [
+            "2023-02-29T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01 00:00:00",
+            "2024-01-01T00:00:00+0800",
+            "1969-12-31T23:59:59Z",
+            "2024-01-01T00:00:00.Z",
+        ] {
+            assert!(parse_rfc3339(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_civil_days_round_trip() {
+        for days in [-719_468, -1, 0, 365, 11_016, 19_782, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
 }
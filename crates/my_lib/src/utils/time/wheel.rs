@@ -0,0 +1,459 @@
+//! 分层哈希时间轮。
+//!
+//! 4 层、每层 64 个槽，第 `l` 层每个槽覆盖 `64^l` 个 tick。定时器按到期时间与当前时间的差值
+//! 放进合适的层，高层的槽到期时把其中的定时器下放（cascade）到低层，最终在第 0 层触发。
+//! 定时器存放在 slab 里，槽内用双向链表串起来，新增和取消都是 O(1)。
+//!
+//! [`TimingWheel`] 本身只按 tick 计数，不关心真实时间；[`TimerService`] 在 tokio 上按固定
+//! tick 驱动它，成千上万个连接的空闲超时可以共用一个时间轮，而不是每个连接一个 `timeout()`。
+
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Instant, MissedTickBehavior};
+
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 4;
+/// 时间轮能直接表示的最大跨度（tick），更远的定时器先挂在最高层，下放时再重新计算
+const MAX_SPAN: u64 = 1 << (SLOT_BITS * LEVELS as u32);
+
+/// 定时器句柄，用于取消；槽位复用后旧句柄自动失效
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct Timer<T> {
+    payload: T,
+    deadline: u64,
+    level: usize,
+    slot: usize,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    generation: u64,
+    timer: Option<Timer<T>>,
+}
+
+pub struct TimingWheel<T> {
+    current: u64,
+    /// 每个槽的链表头
+    slots: Vec<[Option<usize>; SLOTS]>,
+    nodes: Vec<Node<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<T> fmt::Debug for TimingWheel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimingWheel")
+            .field("current", &self.current)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<T> Default for TimingWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> TimingWheel<T> {
+    pub fn new() -> Self {
+        TimingWheel {
+            current: 0,
+            slots: vec![[None; SLOTS]; LEVELS],
+            nodes: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// 当前 tick
+    pub fn current_tick(&self) -> u64 {
+        self.current
+    }
+
+    /// 尚未触发的定时器数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `delay` 个 tick 后触发，`delay` 为 0 时在下一个 tick 触发
+    pub fn schedule(&mut self, delay: u64, payload: T) -> TimerId {
+        self.schedule_at(self.current.saturating_add(delay), payload)
+    }
+
+    /// 在第 `deadline` 个 tick 触发，已经过去的时间点在下一个 tick 触发
+    pub fn schedule_at(&mut self, deadline: u64, payload: T) -> TimerId {
+        let deadline = deadline.max(self.current + 1);
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.nodes.push(Node { generation: 0, timer: None });
+                self.nodes.len() - 1
+            }
+        };
+        let node = &mut self.nodes[index];
+        node.generation += 1;
+        node.timer = Some(Timer {
+            payload,
+            deadline,
+            level: 0,
+            slot: 0,
+            prev: None,
+            next: None,
+        });
+        let generation = node.generation;
+        self.link(index);
+        self.len += 1;
+        TimerId { index, generation }
+    }
+
+    /// 取消定时器，返回它携带的数据；已触发或已取消时返回 `None`
+    pub fn cancel(&mut self, id: TimerId) -> Option<T> {
+        let node = self.nodes.get(id.index)?;
+        if node.generation != id.generation || node.timer.is_none() {
+            return None;
+        }
+        self.unlink(id.index);
+        Some(self.release(id.index))
+    }
+
+    /// 定时器是否还在等待触发
+    pub fn contains(&self, id: TimerId) -> bool {
+        self.nodes
+            .get(id.index)
+            .is_some_and(|node| node.generation == id.generation && node.timer.is_some())
+    }
+
+    /// 前进一个 tick，返回到期的定时器
+    pub fn tick(&mut self) -> Vec<T> {
+        let mut expired = Vec::new();
+        self.tick_into(&mut expired);
+        expired
+    }
+
+    /// 前进到第 `target` 个 tick（不会后退），返回期间到期的定时器，按到期先后排列
+    pub fn advance_to(&mut self, target: u64) -> Vec<T> {
+        let mut expired = Vec::new();
+        while self.current < target {
+            if self.len == 0 {
+                // 没有定时器时直接跳过
+                self.current = target;
+                break;
+            }
+            self.tick_into(&mut expired);
+        }
+        expired
+    }
+
+    fn tick_into(&mut self, expired: &mut Vec<T>) {
+        self.current += 1;
+        let now = self.current;
+
+        // 先从高层往低层下放，再触发第 0 层
+        for level in (1..LEVELS).rev() {
+            if now & ((1 << (SLOT_BITS * level as u32)) - 1) == 0 {
+                let slot = ((now >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1);
+                let mut cursor = self.slots[level][slot].take();
+                while let Some(index) = cursor {
+                    let timer = self.nodes[index].timer.as_mut().unwrap();
+                    cursor = timer.next;
+                    timer.prev = None;
+                    timer.next = None;
+                    self.link(index);
+                }
+            }
+        }
+
+        let slot = (now as usize) & (SLOTS - 1);
+        let mut cursor = self.slots[0][slot].take();
+        while let Some(index) = cursor {
+            cursor = self.nodes[index].timer.as_ref().unwrap().next;
+            expired.push(self.release(index));
+        }
+    }
+
+    /// 根据到期时间把定时器挂到对应的槽上
+    fn link(&mut self, index: usize) {
+        let current = self.current;
+        let timer = self.nodes[index].timer.as_mut().unwrap();
+        let target = timer.deadline.min(current + MAX_SPAN - 1);
+        let diff = target - current;
+        let level = if diff == 0 {
+            0
+        } else {
+            (((u64::BITS - 1 - diff.leading_zeros()) / SLOT_BITS) as usize).min(LEVELS - 1)
+        };
+        let slot = ((target >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1);
+
+        let head = self.slots[level][slot].replace(index);
+        timer.level = level;
+        timer.slot = slot;
+        timer.prev = None;
+        timer.next = head;
+        if let Some(head) = head {
+            self.nodes[head].timer.as_mut().unwrap().prev = Some(index);
+        }
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (level, slot, prev, next) = {
+            let timer = self.nodes[index].timer.as_ref().unwrap();
+            (timer.level, timer.slot, timer.prev, timer.next)
+        };
+        match prev {
+            Some(prev) => self.nodes[prev].timer.as_mut().unwrap().next = next,
+            None => self.slots[level][slot] = next,
+        }
+        if let Some(next) = next {
+            self.nodes[next].timer.as_mut().unwrap().prev = prev;
+        }
+    }
+
+    fn release(&mut self, index: usize) -> T {
+        let timer = self.nodes[index].timer.take().unwrap();
+        self.free.push(index);
+        self.len -= 1;
+        timer.payload
+    }
+}
+
+type Callback = Box<dyn FnOnce() + Send>;
+
+/// 由 tokio 驱动的共享时间轮。
+///
+/// 回调在驱动任务里同步执行，必须足够轻量（通知 channel、设置标志位），
+/// 耗时的逻辑应该放到收到通知的任务里做。所有 `TimerService` 克隆都被 drop 后驱动任务退出。
+#[derive(Clone)]
+pub struct TimerService {
+    wheel: Arc<Mutex<TimingWheel<Callback>>>,
+    tick: Duration,
+    start: Instant,
+}
+
+impl fmt::Debug for TimerService {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimerService")
+            .field("tick", &self.tick)
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+impl TimerService {
+    /// 启动驱动任务，必须在 tokio 运行时内调用
+    ///
+    /// # Panics
+    ///
+    /// `tick` 为 0 时 panic。
+    pub fn start(tick: Duration) -> Self {
+        assert!(!tick.is_zero(), "tick must be positive");
+        let service = TimerService {
+            wheel: Arc::new(Mutex::new(TimingWheel::new())),
+            tick,
+            start: Instant::now(),
+        };
+        tokio::spawn(Self::drive(Arc::downgrade(&service.wheel), tick, service.start));
+        service
+    }
+
+    async fn drive(wheel: Weak<Mutex<TimingWheel<Callback>>>, tick: Duration, start: Instant) {
+        let mut interval = tokio::time::interval(tick);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            let Some(wheel) = wheel.upgrade() else {
+                break;
+            };
+            let target = (start.elapsed().as_nanos() / tick.as_nanos()) as u64;
+            let expired = wheel.lock().unwrap().advance_to(target);
+            drop(wheel);
+            for callback in expired {
+                callback();
+            }
+        }
+    }
+
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// 尚未触发的定时器数量
+    pub fn len(&self) -> usize {
+        self.wheel.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn deadline(&self, delay: Duration) -> u64 {
+        let tick = self.tick.as_nanos();
+        let now = self.start.elapsed().as_nanos() / tick;
+        (now + delay.as_nanos().div_ceil(tick)) as u64
+    }
+
+    /// `delay` 后执行回调
+    pub fn schedule<F>(&self, delay: Duration, callback: F) -> TimerId
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let deadline = self.deadline(delay);
+        self.wheel.lock().unwrap().schedule_at(deadline, Box::new(callback))
+    }
+
+    /// `delay` 后把 `value` 发到 channel 上，接收端已关闭时静默丢弃
+    pub fn schedule_send<T>(&self, delay: Duration, tx: mpsc::UnboundedSender<T>, value: T) -> TimerId
+    where
+        T: Send + 'static,
+    {
+        self.schedule(delay, move || {
+            let _ = tx.send(value);
+        })
+    }
+
+    /// `delay` 后完成的通知，取消定时器后接收端会收到 `RecvError`
+    pub fn notify_after(&self, delay: Duration) -> (TimerId, oneshot::Receiver<()>) {
+        let (tx, rx) = oneshot::channel();
+        let id = self.schedule(delay, move || {
+            let _ = tx.send(());
+        });
+        (id, rx)
+    }
+
+    /// 取消定时器，已触发或已取消时返回 `false`
+    pub fn cancel(&self, id: TimerId) -> bool {
+        self.wheel.lock().unwrap().cancel(id).is_some()
+    }
+
+    /// 把尚未触发的定时器推迟到 `delay` 之后，回调不变；适合连接每次收到数据时刷新空闲超时。
+    /// 定时器已触发或已取消时返回 `None`
+    pub fn reset(&self, id: TimerId, delay: Duration) -> Option<TimerId> {
+        let deadline = self.deadline(delay);
+        let mut wheel = self.wheel.lock().unwrap();
+        let callback = wheel.cancel(id)?;
+        Some(wheel.schedule_at(deadline, callback))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fire_in_order() {
+        let mut wheel = TimingWheel::new();
+        wheel.schedule(3, "c");
+        wheel.schedule(1, "a");
+        wheel.schedule(2, "b");
+        wheel.schedule(0, "now");
+        assert_eq!(wheel.len(), 4);
+
+        // 同一个 tick 内的触发顺序不做保证
+        let mut first = wheel.tick();
+        first.sort();
+        assert_eq!(first, vec!["a", "now"]);
+        assert_eq!(wheel.tick(), vec!["b"]);
+        assert_eq!(wheel.tick(), vec!["c"]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_cascade_across_levels() {
+        let mut wheel = TimingWheel::new();
+        let delays = [63, 64, 65, 4095, 4096, 4097, 300_000, MAX_SPAN + 10];
+        for delay in delays {
+            wheel.schedule(delay, delay);
+        }
+        let mut fired = Vec::new();
+        for _ in 0..MAX_SPAN + 10 {
+            for delay in wheel.tick() {
+                fired.push((delay, wheel.current_tick()));
+            }
+        }
+        assert_eq!(fired, delays.iter().map(|d| (*d, *d)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_schedule_from_offset() {
+        let mut wheel = TimingWheel::new();
+        assert!(wheel.advance_to(4_000).is_empty());
+        for delay in [1, 95, 96, 200, 5_000] {
+            wheel.schedule(delay, 4_000 + delay);
+        }
+        let mut fired = Vec::new();
+        while !wheel.is_empty() {
+            for deadline in wheel.tick() {
+                assert_eq!(deadline, wheel.current_tick());
+                fired.push(deadline);
+            }
+        }
+        assert_eq!(fired, vec![4_001, 4_095, 4_096, 4_200, 9_000]);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut wheel = TimingWheel::new();
+        let a = wheel.schedule(10, "a");
+        let b = wheel.schedule(10, "b");
+        let c = wheel.schedule(10, "c");
+        assert_eq!(wheel.cancel(b), Some("b"));
+        assert_eq!(wheel.cancel(b), None);
+        assert!(!wheel.contains(b));
+        assert!(wheel.contains(a));
+
+        // 复用的槽位不会被旧句柄误取消
+        let d = wheel.schedule(5, "d");
+        assert_eq!(wheel.cancel(b), None);
+
+        let mut fired = wheel.advance_to(10);
+        fired.sort();
+        assert_eq!(fired, vec!["a", "c", "d"]);
+        assert_eq!(wheel.cancel(a), None);
+        assert_eq!(wheel.cancel(c), None);
+        assert_eq!(wheel.cancel(d), None);
+    }
+
+    #[tokio::test]
+    async fn test_timer_service() {
+        let service = TimerService::start(Duration::from_millis(1));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        service.schedule_send(Duration::from_millis(30), tx.clone(), "late");
+        service.schedule_send(Duration::from_millis(10), tx.clone(), "early");
+        let cancelled = service.schedule_send(Duration::from_millis(20), tx, "cancelled");
+        assert!(service.cancel(cancelled));
+
+        assert_eq!(rx.recv().await, Some("early"));
+        assert_eq!(rx.recv().await, Some("late"));
+        assert!(service.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timer_service_reset() {
+        let service = TimerService::start(Duration::from_millis(1));
+        let start = Instant::now();
+        let (id, rx) = service.notify_after(Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        // 模拟收到数据后刷新空闲超时
+        let id = service.reset(id, Duration::from_millis(40)).unwrap();
+        rx.await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(!service.cancel(id));
+    }
+}
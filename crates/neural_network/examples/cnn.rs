@@ -0,0 +1,62 @@
+// 在合成的 12 x 12 图像上训练一个小卷积网络，区分横线、竖线和正方形边框
+//
+// cargo run --release -p neural_network --example cnn
+use neural_network::activation::Activation;
+use neural_network::conv::Conv2DSpec;
+use neural_network::data::{shapes_image_shape, synthetic_shapes, Dataset};
+use neural_network::loss::CategoricalCrossEntropy;
+use neural_network::metrics::Metric;
+use neural_network::optimizer::Adam;
+use neural_network::schedule::{CosineAnnealing, LinearWarmup};
+use neural_network::sequential::Sequential;
+use neural_network::trainer::{Callback, EpochStats, Trainer};
+
+struct PrintProgress;
+
+impl Callback for PrintProgress {
+    fn on_epoch_end(&mut self, stats: &EpochStats) -> bool {
+        println!(
+            "epoch {:>2}: train loss {:.4}, val loss {:.4}, lr {:.5}",
+            stats.epoch + 1,
+            stats.train_loss,
+            stats.val_loss.unwrap_or(f64::NAN),
+            stats.learning_rate
+        );
+        true
+    }
+}
+
+fn main() {
+    let train = synthetic_shapes(600, 1);
+    let test = Dataset::new(synthetic_shapes(150, 2)).with_classes(vec!["horizontal".into(), "vertical".into(), "square".into()]);
+
+    // 1x12x12 -> conv 3x3 -> 8x12x12 -> pool -> 8x6x6 -> conv 3x3 -> 8x4x4 -> pool -> 8x2x2 -> 3
+    let mut network = Sequential::seeded(shapes_image_shape(), 42)
+        .conv2d_with(Conv2DSpec::new(8, 3, Activation::ReLU).with_padding(1))
+        .max_pool(2)
+        .conv2d(8, 3, Activation::ReLU)
+        .max_pool(2)
+        .flatten()
+        .dense(3, Activation::Softmax)
+        .with_loss(CategoricalCrossEntropy)
+        .build();
+    let accuracy = network.evaluate(&test, &[Metric::Accuracy]).unwrap().accuracy.unwrap();
+    println!("test accuracy before training: {:.1}%", 100.0 * accuracy);
+
+    // 数据并行，结果只取决于线程数
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get().min(4));
+    let history = Trainer::new(Adam::new(0.01))
+        .with_batch_size(32)
+        .with_epochs(10)
+        .with_shuffle_seed(7)
+        .with_validation_split(0.1)
+        .with_threads(threads)
+        // 先用 20 次更新预热，再在 10 轮内余弦退火
+        .with_lr_schedule(LinearWarmup::new(20, CosineAnnealing::new(10.0).with_min_lr(1e-4)))
+        .with_callback(PrintProgress)
+        .fit(&mut network, &train)
+        .expect("training failed");
+    let rate = history.samples_per_sec.iter().sum::<f64>() / history.epochs() as f64;
+    println!("trained {} epochs on {} threads, {:.0} samples/sec", history.epochs(), threads, rate);
+    println!("\n{}", network.evaluate(&test, &Metric::CLASSIFICATION).unwrap());
+}
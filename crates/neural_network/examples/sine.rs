@@ -0,0 +1,71 @@
+// 用循环网络预测正弦波的下一个值
+//
+// 多对一：LSTM 读入前 20 个值，输出下一个值
+// 多对多：GRU 在每个时间步都预测下一个值
+//
+// cargo run --release -p neural_network --example sine
+use neural_network::activation::Activation;
+use neural_network::layer::Shape;
+use neural_network::neural_network::NeuralNetwork;
+use neural_network::optimizer::Adam;
+use neural_network::recurrent::RecurrentSpec;
+use neural_network::sequential::Sequential;
+use neural_network::trainer::{evaluate_loss, Trainer};
+
+const WINDOW: usize = 20;
+
+// 带一点相位变化的正弦波
+fn wave(t: usize) -> f64 {
+    (t as f64 * 0.15).sin() * 0.8 + (t as f64 * 0.05).sin() * 0.2
+}
+
+fn train(network: &mut NeuralNetwork, data: &[(Vec<f64>, Vec<f64>)], test: &[(Vec<f64>, Vec<f64>)]) {
+    let before = evaluate_loss(network, test).unwrap();
+    Trainer::new(Adam::new(0.01))
+        .with_batch_size(16)
+        .with_epochs(30)
+        .with_shuffle_seed(3)
+        .fit(network, data)
+        .expect("training failed");
+    println!("test MSE: {:.5} -> {:.5}", before, evaluate_loss(network, test).unwrap());
+}
+
+fn many_to_one() {
+    let samples = |range: std::ops::Range<usize>| -> Vec<(Vec<f64>, Vec<f64>)> {
+        range.map(|start| ((start..start + WINDOW).map(wave).collect(), vec![wave(start + WINDOW)])).collect()
+    };
+    let (data, test) = (samples(0..400), samples(500..600));
+
+    let mut network = Sequential::seeded(Shape::sequence(WINDOW, 1), 1)
+        .lstm(16)
+        .dense(1, Activation::Identity)
+        .build();
+    println!("many to one (LSTM):");
+    train(&mut network, &data, &test);
+
+    let (input, target) = &test[0];
+    println!("next value after t = 500..520: predicted {:.4}, actual {:.4}", network.predict(input).unwrap()[0], target[0]);
+}
+
+fn many_to_many() {
+    let samples = |range: std::ops::Range<usize>| -> Vec<(Vec<f64>, Vec<f64>)> {
+        range.step_by(2).map(|start| {
+            let input = (start..start + WINDOW).map(wave).collect();
+            let target = (start + 1..start + WINDOW + 1).map(wave).collect();
+            (input, target)
+        }).collect()
+    };
+    let (data, test) = (samples(0..800), samples(1000..1200));
+
+    let mut network = Sequential::seeded(Shape::sequence(WINDOW, 1), 2)
+        .recurrent(RecurrentSpec::gru(16).with_return_sequences(true).with_bptt_steps(10))
+        .time_distributed_dense(1, Activation::Identity)
+        .build();
+    println!("many to many (GRU, truncated BPTT of 10 steps):");
+    train(&mut network, &data, &test);
+}
+
+fn main() {
+    many_to_one();
+    many_to_many();
+}
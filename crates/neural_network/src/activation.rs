@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::float::Float;
+
+/// 激活函数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Activation {
+    Sigmoid,
+    Tanh,
+    ReLU,
+    /// 负半轴斜率
+    LeakyReLU(f64),
+    /// 负半轴饱和值 alpha：x < 0 时为 alpha * (e^x - 1)
+    ELU(f64),
+    /// tanh 近似版本
+    GELU,
+    /// 作用于整层输出，输出之和为 1
+    Softmax,
+    Identity,
+}
+
+const GELU_COEF: f64 = 0.044_715;
+// sqrt(2 / pi)
+const SQRT_2_OVER_PI: f64 = 0.797_884_560_802_865_4;
+
+impl Activation {
+    /// 对整层的激活前值 `z` 求激活输出
+    pub fn forward<T: Float>(&self, z: &[T]) -> Vec<T> {
+        match self {
+            Activation::Softmax => softmax(z),
+            _ => z.iter().map(|&x| self.apply(x)).collect(),
+        }
+    }
+
+    fn apply<T: Float>(&self, x: T) -> T {
+        let zero = T::zero();
+        match *self {
+            Activation::Sigmoid => sigmoid(x),
+            Activation::Tanh => x.tanh(),
+            Activation::ReLU => x.max(zero),
+            Activation::LeakyReLU(slope) => if x > zero { x } else { T::from_f64(slope) * x },
+            Activation::ELU(alpha) => if x > zero { x } else { T::from_f64(alpha) * x.exp_m1() },
+            Activation::GELU => {
+                let inner = T::from_f64(SQRT_2_OVER_PI) * (x + T::from_f64(GELU_COEF) * x.powi(3));
+                T::from_f64(0.5) * x * (T::one() + inner.tanh())
+            }
+            Activation::Identity => x,
+            Activation::Softmax => unreachable!("softmax is not element-wise"),
+        }
+    }
+
+    /// 逐元素的导数 f'(z)，`y` 是对应的激活输出。
+    /// Softmax 的导数是一个雅可比矩阵，这里只返回对角线 y(1 - y)，反向传播请用 [`Activation::backward`]。
+    pub fn derivative<T: Float>(&self, z: &[T], y: &[T]) -> Vec<T> {
+        let (zero, one, half) = (T::zero(), T::one(), T::from_f64(0.5));
+        z.iter().zip(y.iter()).map(|(&x, &y)| match *self {
+            Activation::Sigmoid | Activation::Softmax => y * (one - y),
+            Activation::Tanh => one - y * y,
+            Activation::ReLU => if x > zero { one } else { zero },
+            Activation::LeakyReLU(slope) => if x > zero { one } else { T::from_f64(slope) },
+            Activation::ELU(alpha) => if x > zero { one } else { y + T::from_f64(alpha) },
+            Activation::GELU => {
+                let (coef, scale) = (T::from_f64(GELU_COEF), T::from_f64(SQRT_2_OVER_PI));
+                let t = (scale * (x + coef * x.powi(3))).tanh();
+                half * (one + t) + half * x * (one - t * t) * scale * (one + T::from_f64(3.0) * coef * x * x)
+            }
+            Activation::Identity => one,
+        }).collect()
+    }
+
+    /// 反向传播：已知损失对输出 y 的梯度，求对激活前值 z 的梯度（雅可比矩阵转置乘梯度）
+    pub fn backward<T: Float>(&self, z: &[T], y: &[T], output_grad: &[T]) -> Vec<T> {
+        match self {
+            // dL/dz_i = y_i * (g_i - Σ_j g_j * y_j)
+            Activation::Softmax => {
+                let dot: T = output_grad.iter().zip(y.iter()).map(|(&g, &y)| g * y).sum();
+                output_grad.iter().zip(y.iter()).map(|(&g, &y)| y * (g - dot)).collect()
+            }
+            _ => self.derivative(z, y).iter().zip(output_grad.iter()).map(|(&d, &g)| d * g).collect(),
+        }
+    }
+}
+
+// Sigmoid 激活函数
+pub(crate) fn sigmoid<T: Float>(x: T) -> T {
+    T::one() / (T::one() + (-x).exp())
+}
+
+/// 减去最大值后再求指数，避免溢出
+fn softmax<T: Float>(z: &[T]) -> Vec<T> {
+    let max = z.iter().fold(T::neg_infinity(), |acc, &x| acc.max(x));
+    let exps: Vec<T> = z.iter().map(|&x| (x - max).exp()).collect();
+    let sum: T = exps.iter().copied().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Activation; 8] = [
+        Activation::Sigmoid,
+        Activation::Tanh,
+        Activation::ReLU,
+        Activation::LeakyReLU(0.01),
+        Activation::ELU(1.0),
+        Activation::GELU,
+        Activation::Softmax,
+        Activation::Identity,
+    ];
+
+    #[test]
+    fn test_backward_matches_numeric() {
+        // 避开 ReLU 在 0 处不可导的点
+        let z = [-1.3, -0.2, 0.4, 2.1];
+        let grad = [0.3, -0.7, 1.1, 0.5];
+        let eps = 1e-6;
+        for activation in ALL {
+            let y = activation.forward(&z[..]);
+            let analytic = activation.backward(&z, &y, &grad);
+            for i in 0..z.len() {
+                let (mut plus, mut minus) = (z, z);
+                plus[i] += eps;
+                minus[i] -= eps;
+                let weighted = |z: &[f64]| -> f64 {
+                    activation.forward(z).iter().zip(grad.iter()).map(|(y, g)| y * g).sum()
+                };
+                let numeric = (weighted(&plus) - weighted(&minus)) / (2.0 * eps);
+                assert!((analytic[i] - numeric).abs() < 1e-6, "{:?}[{}]: {} vs {}", activation, i, analytic[i], numeric);
+            }
+        }
+    }
+
+    #[test]
+    fn test_softmax_is_stable() {
+        let y = Activation::Softmax.forward(&[1000.0, 1000.0, -1000.0f64]);
+        assert!((y[0] - 0.5).abs() < 1e-12 && (y[1] - 0.5).abs() < 1e-12);
+        assert_eq!(y[2], 0.0);
+        assert!((y.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,405 @@
+//! 基于求导带（tape）的反向模式自动微分。
+//!
+//! 每个 [`Var`] 是求导带上的一个节点，值是一个矩阵（标量是 1 x 1 矩阵）。运算按执行顺序记录在
+//! [`Tape`] 上，[`Var::backward`] 从后往前遍历一次即可得到所有节点的梯度，新的层和损失只需要写出
+//! 前向的表达式。
+//!
+//! ```
+//! use neural_network::autodiff::Tape;
+//! use neural_network::matrix::Matrix;
+//!
+//! let tape = Tape::new();
+//! let x = tape.var(Matrix::from_rows(&[[1.0, 2.0]]));
+//! let w = tape.var(Matrix::from_rows(&[[3.0], [4.0]]));
+//! // y = Σ exp(x W)
+//! let y = x.matmul(w).exp().sum();
+//! let grads = y.backward();
+//! assert_eq!(grads.wrt(w).as_slice(), &[11f64.exp(), 2.0 * 11f64.exp()]);
+//! ```
+
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::activation::Activation;
+use crate::float::Float;
+use crate::matrix::Matrix;
+
+#[derive(Debug, Clone, Copy)]
+enum Op<T> {
+    Leaf,
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    MatMul(usize, usize),
+    Transpose(usize),
+    Neg(usize),
+    Scale(usize, T),
+    Activation(usize, Activation),
+    Log(usize),
+    Exp(usize),
+    Abs(usize),
+    Clamp(usize, T, T),
+    Sum(usize),
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    value: Matrix<T>,
+    op: Op<T>,
+}
+
+/// 记录运算的求导带，节点只增不减，用完整个丢弃
+#[derive(Debug, Default)]
+pub struct Tape<T: Float = f64> {
+    nodes: RefCell<Vec<Node<T>>>,
+}
+
+impl<T: Float> Tape<T> {
+    pub fn new() -> Tape<T> {
+        Tape { nodes: RefCell::new(Vec::new()) }
+    }
+
+    /// 叶子节点：输入、参数或常数
+    pub fn var(&self, value: Matrix<T>) -> Var<'_, T> {
+        self.push(value, Op::Leaf)
+    }
+
+    /// 1 x 1 的叶子节点，参与二元运算时广播到另一边的形状
+    pub fn scalar(&self, value: T) -> Var<'_, T> {
+        self.var(Matrix::new(1, 1, vec![value]))
+    }
+
+    /// 节点个数
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(&self, value: Matrix<T>, op: Op<T>) -> Var<'_, T> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { value, op });
+        Var { tape: self, index: nodes.len() - 1 }
+    }
+}
+
+/// 求导带上的一个节点，可以复制，生命周期不超过所属的求导带。
+///
+/// `+`、`-`、`*` 是逐元素运算，某一维为 1 的一边会沿该维广播（例如偏置行向量、标量），
+/// 矩阵乘法用 [`Var::matmul`]。
+#[derive(Clone, Copy)]
+pub struct Var<'t, T: Float = f64> {
+    tape: &'t Tape<T>,
+    index: usize,
+}
+
+impl<T: Float> fmt::Debug for Var<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (rows, cols) = self.shape();
+        write!(f, "Var({}: {} x {})", self.index, rows, cols)
+    }
+}
+
+impl<'t, T: Float> Var<'t, T> {
+    pub fn tape(&self) -> &'t Tape<T> {
+        self.tape
+    }
+
+    pub fn value(&self) -> Matrix<T> {
+        self.tape.nodes.borrow()[self.index].value.clone()
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.tape.nodes.borrow()[self.index].value.shape()
+    }
+
+    fn unary(self, op: Op<T>, f: impl FnOnce(&Matrix<T>) -> Matrix<T>) -> Var<'t, T> {
+        let value = f(&self.tape.nodes.borrow()[self.index].value);
+        self.tape.push(value, op)
+    }
+
+    fn binary(self, other: Var<'t, T>, op: Op<T>, f: impl FnOnce(&Matrix<T>, &Matrix<T>) -> Matrix<T>) -> Var<'t, T> {
+        assert!(std::ptr::eq(self.tape, other.tape), "variables belong to different tapes");
+        let value = {
+            let nodes = self.tape.nodes.borrow();
+            f(&nodes[self.index].value, &nodes[other.index].value)
+        };
+        self.tape.push(value, op)
+    }
+
+    pub fn matmul(self, other: Var<'t, T>) -> Var<'t, T> {
+        self.binary(other, Op::MatMul(self.index, other.index), |a, b| a.matmul(b))
+    }
+
+    pub fn transpose(self) -> Var<'t, T> {
+        self.unary(Op::Transpose(self.index), Matrix::transpose)
+    }
+
+    pub fn scale(self, factor: T) -> Var<'t, T> {
+        self.unary(Op::Scale(self.index, factor), |a| a.map(|x| x * factor))
+    }
+
+    /// 逐行（逐样本）应用激活函数，softmax 按行归一化
+    pub fn activate(self, activation: Activation) -> Var<'t, T> {
+        self.unary(Op::Activation(self.index, activation), |z| {
+            let mut output = z.clone();
+            for row in 0..z.rows() {
+                output.row_mut(row).copy_from_slice(&activation.forward(z.row(row)));
+            }
+            output
+        })
+    }
+
+    pub fn log(self) -> Var<'t, T> {
+        self.unary(Op::Log(self.index), |a| a.map(T::ln))
+    }
+
+    pub fn exp(self) -> Var<'t, T> {
+        self.unary(Op::Exp(self.index), |a| a.map(T::exp))
+    }
+
+    pub fn abs(self) -> Var<'t, T> {
+        self.unary(Op::Abs(self.index), |a| a.map(T::abs))
+    }
+
+    /// 截断到 [min, max]，被截断的元素梯度为 0
+    pub fn clamp(self, min: T, max: T) -> Var<'t, T> {
+        self.unary(Op::Clamp(self.index, min, max), |a| a.map(|x| x.clamp(min, max)))
+    }
+
+    /// 所有元素之和，1 x 1
+    pub fn sum(self) -> Var<'t, T> {
+        self.unary(Op::Sum(self.index), |a| Matrix::new(1, 1, vec![a.as_slice().iter().copied().sum()]))
+    }
+
+    /// 对 1 x 1 的节点求所有节点的梯度
+    pub fn backward(self) -> Gradients<T> {
+        assert_eq!(self.shape(), (1, 1), "backward needs a scalar, use backward_with for other shapes");
+        self.backward_with(Matrix::new(1, 1, vec![T::one()]))
+    }
+
+    /// 以 `seed` 作为本节点的梯度反向传播，即向量-雅可比积
+    pub fn backward_with(self, seed: Matrix<T>) -> Gradients<T> {
+        assert_eq!(seed.shape(), self.shape(), "seed must have the shape of the variable");
+        let nodes = self.tape.nodes.borrow();
+        let mut grads: Vec<Option<Matrix<T>>> = vec![None; self.index + 1];
+        grads[self.index] = Some(seed);
+        // 父节点的下标总是更小，倒序遍历时每个节点的梯度都已经累积完整
+        for index in (0..=self.index).rev() {
+            let Some(grad) = grads[index].take() else {
+                continue;
+            };
+            let value = |i: usize| &nodes[i].value;
+            match nodes[index].op {
+                Op::Leaf => {}
+                Op::Add(a, b) => {
+                    accumulate(&mut grads, a, reduce(&grad, value(a).shape()));
+                    accumulate(&mut grads, b, reduce(&grad, value(b).shape()));
+                }
+                Op::Sub(a, b) => {
+                    accumulate(&mut grads, a, reduce(&grad, value(a).shape()));
+                    accumulate(&mut grads, b, reduce(&grad.map(|g| -g), value(b).shape()));
+                }
+                Op::Mul(a, b) => {
+                    let times = |other: &Matrix<T>| Matrix::from_fn(grad.rows(), grad.cols(), |r, c| grad[(r, c)] * at(other, r, c));
+                    accumulate(&mut grads, a, reduce(&times(value(b)), value(a).shape()));
+                    accumulate(&mut grads, b, reduce(&times(value(a)), value(b).shape()));
+                }
+                Op::MatMul(a, b) => {
+                    // dA = G B^T，dB = A^T G
+                    accumulate(&mut grads, a, grad.matmul(&value(b).transpose()));
+                    accumulate(&mut grads, b, value(a).transpose().matmul(&grad));
+                }
+                Op::Transpose(a) => accumulate(&mut grads, a, grad.transpose()),
+                Op::Neg(a) => accumulate(&mut grads, a, grad.map(|g| -g)),
+                Op::Scale(a, factor) => accumulate(&mut grads, a, grad.map(|g| g * factor)),
+                Op::Activation(a, activation) => {
+                    let (z, y) = (value(a), &nodes[index].value);
+                    let mut delta = grad.clone();
+                    for row in 0..grad.rows() {
+                        delta.row_mut(row).copy_from_slice(&activation.backward(z.row(row), y.row(row), grad.row(row)));
+                    }
+                    accumulate(&mut grads, a, delta);
+                }
+                Op::Log(a) => accumulate(&mut grads, a, grad.zip_map(value(a), |g, x| g / x)),
+                Op::Exp(a) => accumulate(&mut grads, a, grad.zip_map(&nodes[index].value, |g, y| g * y)),
+                Op::Abs(a) => {
+                    let sign = |x: T| if x > T::zero() { T::one() } else if x < T::zero() { -T::one() } else { T::zero() };
+                    accumulate(&mut grads, a, grad.zip_map(value(a), |g, x| g * sign(x)));
+                }
+                Op::Clamp(a, min, max) => {
+                    accumulate(&mut grads, a, grad.zip_map(value(a), |g, x| if x >= min && x <= max { g } else { T::zero() }));
+                }
+                Op::Sum(a) => {
+                    let (rows, cols) = value(a).shape();
+                    accumulate(&mut grads, a, Matrix::from_fn(rows, cols, |_, _| grad[(0, 0)]));
+                }
+            }
+            grads[index] = Some(grad);
+        }
+        Gradients { grads }
+    }
+}
+
+/// 广播：某一维为 1 时沿该维重复
+fn at<T: Float>(m: &Matrix<T>, row: usize, col: usize) -> T {
+    m[(row % m.rows(), col % m.cols())]
+}
+
+fn broadcast_shape(a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+    let dim = |x: usize, y: usize| match (x, y) {
+        _ if x == y => x,
+        (1, _) => y,
+        (_, 1) => x,
+        _ => panic!("cannot broadcast {} x {} with {} x {}", a.0, a.1, b.0, b.1),
+    };
+    (dim(a.0, b.0), dim(a.1, b.1))
+}
+
+/// 广播的反向：把梯度沿被广播的维求和，还原成操作数的形状
+fn reduce<T: Float>(grad: &Matrix<T>, (rows, cols): (usize, usize)) -> Matrix<T> {
+    if grad.shape() == (rows, cols) {
+        return grad.clone();
+    }
+    let mut reduced = Matrix::zeros(rows, cols);
+    for r in 0..grad.rows() {
+        for c in 0..grad.cols() {
+            reduced[(r % rows, c % cols)] += grad[(r, c)];
+        }
+    }
+    reduced
+}
+
+fn accumulate<T: Float>(grads: &mut [Option<Matrix<T>>], index: usize, grad: Matrix<T>) {
+    match &mut grads[index] {
+        Some(existing) => existing.add_assign(&grad),
+        slot @ None => *slot = Some(grad),
+    }
+}
+
+fn elementwise<T: Float>(a: &Matrix<T>, b: &Matrix<T>, f: impl Fn(T, T) -> T) -> Matrix<T> {
+    let (rows, cols) = broadcast_shape(a.shape(), b.shape());
+    Matrix::from_fn(rows, cols, |r, c| f(at(a, r, c), at(b, r, c)))
+}
+
+impl<'t, T: Float> Add for Var<'t, T> {
+    type Output = Var<'t, T>;
+
+    fn add(self, other: Var<'t, T>) -> Var<'t, T> {
+        self.binary(other, Op::Add(self.index, other.index), |a, b| elementwise(a, b, |x, y| x + y))
+    }
+}
+
+impl<'t, T: Float> Sub for Var<'t, T> {
+    type Output = Var<'t, T>;
+
+    fn sub(self, other: Var<'t, T>) -> Var<'t, T> {
+        self.binary(other, Op::Sub(self.index, other.index), |a, b| elementwise(a, b, |x, y| x - y))
+    }
+}
+
+impl<'t, T: Float> Mul for Var<'t, T> {
+    type Output = Var<'t, T>;
+
+    fn mul(self, other: Var<'t, T>) -> Var<'t, T> {
+        self.binary(other, Op::Mul(self.index, other.index), |a, b| elementwise(a, b, |x, y| x * y))
+    }
+}
+
+impl<'t, T: Float> Neg for Var<'t, T> {
+    type Output = Var<'t, T>;
+
+    fn neg(self) -> Var<'t, T> {
+        self.unary(Op::Neg(self.index), |a| a.map(|x| -x))
+    }
+}
+
+/// [`Var::backward`] 的结果，按节点保存梯度
+#[derive(Debug, Clone)]
+pub struct Gradients<T: Float = f64> {
+    grads: Vec<Option<Matrix<T>>>,
+}
+
+impl<T: Float> Gradients<T> {
+    /// 与输出无关的节点没有梯度
+    pub fn get(&self, var: Var<'_, T>) -> Option<&Matrix<T>> {
+        self.grads.get(var.index).and_then(Option::as_ref)
+    }
+
+    /// 同 `get`，没有梯度时返回同形状的零矩阵
+    pub fn wrt(&self, var: Var<'_, T>) -> Matrix<T> {
+        self.get(var).cloned().unwrap_or_else(|| {
+            let (rows, cols) = var.shape();
+            Matrix::zeros(rows, cols)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 对 f 的每个输入元素做中心差分，与自动微分的梯度比较
+    fn check(inputs: &[Matrix], f: impl for<'t> Fn(&[Var<'t>]) -> Var<'t>) {
+        let tape = Tape::new();
+        let vars: Vec<Var> = inputs.iter().map(|m| tape.var(m.clone())).collect();
+        let grads = f(&vars).backward();
+        let eval = |inputs: &[Matrix]| {
+            let tape = Tape::new();
+            let vars: Vec<Var> = inputs.iter().map(|m| tape.var(m.clone())).collect();
+            f(&vars).value()[(0, 0)]
+        };
+        let eps = 1e-6;
+        for (i, var) in vars.iter().enumerate() {
+            let analytic = grads.wrt(*var);
+            for j in 0..inputs[i].as_slice().len() {
+                let (mut plus, mut minus) = (inputs.to_vec(), inputs.to_vec());
+                plus[i].as_mut_slice()[j] += eps;
+                minus[i].as_mut_slice()[j] -= eps;
+                let numeric = (eval(&plus) - eval(&minus)) / (2.0 * eps);
+                let a = analytic.as_slice()[j];
+                assert!((a - numeric).abs() < 1e-6, "input {} element {}: {} vs {}", i, j, a, numeric);
+            }
+        }
+    }
+
+    #[test]
+    fn test_elementwise_ops() {
+        let a = Matrix::from_rows(&[[0.5, -1.2, 2.0], [1.5, 0.3, -0.7]]);
+        let row = Matrix::from_rows(&[[0.2, 0.4, -0.1]]);
+        let positive = Matrix::from_rows(&[[0.5, 1.2], [2.0, 0.3]]);
+        check(&[a.clone(), row.clone()], |v| (v[0] * v[1] - v[1] + v[0]).sum());
+        check(std::slice::from_ref(&a), |v| (-v[0]).exp().abs().scale(0.5).sum());
+        check(std::slice::from_ref(&positive), |v| v[0].log().sum());
+        check(std::slice::from_ref(&a), |v| (v[0].clamp(-1.0, 1.0) * v[0]).sum());
+        // 标量广播，而且同一个节点被用了多次
+        check(&[a, Matrix::new(1, 1, vec![1.7])], |v| (v[1] - v[0] * v[0] * v[1]).sum());
+    }
+
+    #[test]
+    fn test_matmul_and_activations() {
+        let x = Matrix::from_rows(&[[0.5, -1.2, 2.0], [1.5, 0.3, -0.7]]);
+        let w = Matrix::from_rows(&[[0.1, -0.3, 0.2], [0.4, 0.25, -0.6]]);
+        let weights = Matrix::from_rows(&[[1.0, -2.0], [0.5, 0.7]]);
+        for activation in [Activation::Sigmoid, Activation::Tanh, Activation::ELU(0.8), Activation::GELU, Activation::Softmax] {
+            check(&[x.clone(), w.clone(), weights.clone()], |v| {
+                (v[0].matmul(v[1].transpose()).activate(activation) * v[2]).sum()
+            });
+        }
+    }
+
+    #[test]
+    fn test_unused_and_vector_seed() {
+        let tape = Tape::new();
+        let (x, unused) = (tape.var(Matrix::from_rows(&[[1.0, 2.0]])), tape.scalar(3.0));
+        let y = x.exp();
+        let grads = y.backward_with(Matrix::from_rows(&[[1.0, 0.5]]));
+        assert_eq!(grads.wrt(x).as_slice(), &[1f64.exp(), 0.5 * 2f64.exp()]);
+        assert!(grads.get(unused).is_none());
+        assert_eq!(grads.wrt(unused).as_slice(), &[0.0]);
+        assert_eq!(tape.len(), 3);
+    }
+}
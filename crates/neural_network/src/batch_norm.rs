@@ -0,0 +1,254 @@
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// 批归一化：y = γ (x - μ) / sqrt(σ² + ε) + β，每个输入元素单独统计。
+///
+/// 训练模式下 μ、σ² 取当前批次的均值和（有偏）方差，并更新滑动统计量
+/// running = momentum * running + (1 - momentum) * batch；推理模式和 `predict` 使用滑动统计量。
+/// 训练模式下批大小为 1 时方差为 0，输出恒为 β，应按批次训练。
+#[derive(Debug)]
+pub struct BatchNorm<T: Float = f64> {
+    shape: Shape,
+    momentum: f64,
+    epsilon: f64,
+    gamma: Vec<T>,
+    beta: Vec<T>,
+    running_mean: Vec<T>,
+    running_var: Vec<T>,
+    training: bool,
+    // 前向传播的缓存：归一化后的输入、1 / sqrt(σ² + ε)，以及是否用的批次统计量
+    normalized: Matrix<T>,
+    inv_std: Vec<T>,
+    batch_stats: bool,
+    gamma_grads: Vec<T>,
+    beta_grads: Vec<T>,
+}
+
+impl<T: Float> BatchNorm<T> {
+    /// γ = 1，β = 0，滑动均值 0、方差 1，momentum 0.9，ε = 1e-5
+    pub fn new(shape: impl Into<Shape>) -> BatchNorm<T> {
+        let shape = shape.into();
+        let size = shape.size();
+        Self::from_parts(shape, vec![T::one(); size], vec![T::zero(); size], vec![T::zero(); size], vec![T::one(); size])
+    }
+
+    /// 用已有的参数和滑动统计量构造，长度都必须等于 `shape.size()`
+    pub fn from_parts(shape: Shape, gamma: Vec<T>, beta: Vec<T>, running_mean: Vec<T>, running_var: Vec<T>) -> BatchNorm<T> {
+        let size = shape.size();
+        assert!(
+            [gamma.len(), beta.len(), running_mean.len(), running_var.len()].iter().all(|&len| len == size),
+            "batch norm parameters must have {} elements",
+            size
+        );
+        BatchNorm {
+            shape,
+            momentum: 0.9,
+            epsilon: 1e-5,
+            gamma,
+            beta,
+            running_mean,
+            running_var,
+            training: true,
+            normalized: Matrix::zeros(0, 0),
+            inv_std: Vec::new(),
+            batch_stats: false,
+            gamma_grads: vec![T::zero(); size],
+            beta_grads: vec![T::zero(); size],
+        }
+    }
+
+    pub fn with_momentum(mut self, momentum: f64) -> Self {
+        assert!((0.0..1.0).contains(&momentum), "momentum must be in [0, 1), got {}", momentum);
+        self.momentum = momentum;
+        self
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        assert!(epsilon > 0.0, "epsilon must be positive, got {}", epsilon);
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn momentum(&self) -> f64 {
+        self.momentum
+    }
+
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    pub fn running_mean(&self) -> &[T] {
+        &self.running_mean
+    }
+
+    pub fn running_var(&self) -> &[T] {
+        &self.running_var
+    }
+
+    fn inv_std(&self, var: &[T]) -> Vec<T> {
+        let epsilon = T::from_f64(self.epsilon);
+        var.iter().map(|&v| T::one() / (v + epsilon).sqrt()).collect()
+    }
+
+    /// 返回 (归一化后的输入, 输出)
+    fn normalize(&self, input: &Matrix<T>, mean: &[T], inv_std: &[T]) -> (Matrix<T>, Matrix<T>) {
+        let normalized = Matrix::from_fn(input.rows(), input.cols(), |row, col| (input[(row, col)] - mean[col]) * inv_std[col]);
+        let output = Matrix::from_fn(input.rows(), input.cols(), |row, col| self.gamma[col] * normalized[(row, col)] + self.beta[col]);
+        (normalized, output)
+    }
+}
+
+impl<T: Float> Layer<T> for BatchNorm<T> {
+    fn input_shape(&self) -> Shape {
+        self.shape.clone()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.shape.clone()
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        if !self.training || input.rows() == 0 {
+            self.inv_std = self.inv_std(&self.running_var);
+            self.batch_stats = false;
+            let (normalized, output) = self.normalize(input, &self.running_mean, &self.inv_std);
+            self.normalized = normalized;
+            return output;
+        }
+        let n = T::from_f64(input.rows() as f64);
+        let mean: Vec<T> = input.sum_rows().into_iter().map(|s| s / n).collect();
+        let centered = Matrix::from_fn(input.rows(), input.cols(), |row, col| input[(row, col)] - mean[col]);
+        let var: Vec<T> = centered.map(|x| x * x).sum_rows().into_iter().map(|s| s / n).collect();
+
+        let momentum = T::from_f64(self.momentum);
+        let update = |running: &mut Vec<T>, batch: &[T]| {
+            running.iter_mut().zip(batch).for_each(|(r, &b)| *r = momentum * *r + (T::one() - momentum) * b);
+        };
+        update(&mut self.running_mean, &mean);
+        update(&mut self.running_var, &var);
+
+        self.inv_std = self.inv_std(&var);
+        self.batch_stats = true;
+        let (normalized, output) = self.normalize(input, &mean, &self.inv_std);
+        self.normalized = normalized;
+        output
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        self.normalize(input, &self.running_mean, &self.inv_std(&self.running_var)).1
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        // dγ += Σ dy * x̂，dβ += Σ dy
+        let sum_grad = output_grad.sum_rows();
+        let sum_grad_normalized = output_grad.zip_map(&self.normalized, |g, x| g * x).sum_rows();
+        self.gamma_grads.iter_mut().zip(&sum_grad_normalized).for_each(|(g, &d)| *g += d);
+        self.beta_grads.iter_mut().zip(&sum_grad).for_each(|(g, &d)| *g += d);
+
+        if !self.batch_stats {
+            // 统计量是常数：dx = γ * inv_std * dy
+            return Matrix::from_fn(output_grad.rows(), output_grad.cols(), |row, col| {
+                self.gamma[col] * self.inv_std[col] * output_grad[(row, col)]
+            });
+        }
+        // 均值和方差也依赖输入：dx = γ * inv_std / N * (N * dy - Σ dy - x̂ * Σ (dy * x̂))
+        let n = T::from_f64(output_grad.rows() as f64);
+        Matrix::from_fn(output_grad.rows(), output_grad.cols(), |row, col| {
+            let scale = self.gamma[col] * self.inv_std[col] / n;
+            scale * (n * output_grad[(row, col)] - sum_grad[col] - self.normalized[(row, col)] * sum_grad_normalized[col])
+        })
+    }
+
+    /// 两组参数：γ 和 β，滑动统计量不是参数
+    fn parameters(&self) -> Vec<&[T]> {
+        vec![&self.gamma, &self.beta]
+    }
+
+    fn gradients(&self) -> Vec<&[T]> {
+        vec![&self.gamma_grads, &self.beta_grads]
+    }
+
+    fn visit_parameters(&mut self, f: &mut dyn FnMut(&mut [T], &mut [T])) {
+        f(&mut self.gamma, &mut self.gamma_grads);
+        f(&mut self.beta, &mut self.beta_grads);
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn visit_state(&mut self, f: &mut dyn FnMut(&mut [T])) {
+        f(&mut self.running_mean);
+        f(&mut self.running_var);
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        let to_f64 = |values: &[T]| values.iter().map(|v| v.to_f64()).collect();
+        Some(LayerRecord::BatchNorm {
+            shape: self.shape.clone(),
+            momentum: self.momentum,
+            epsilon: self.epsilon,
+            gamma: to_f64(&self.gamma),
+            beta: to_f64(&self.beta),
+            running_mean: to_f64(&self.running_mean),
+            running_var: to_f64(&self.running_var),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以 Σ (y * w) 为损失，w 固定，比较训练模式下输入和 γ 的解析梯度与数值梯度
+    #[test]
+    fn test_training_gradients_match_numeric() {
+        let input = Matrix::from_rows(&[[0.5, -1.0, 2.0], [1.5, 0.3, -0.7], [-0.2, 0.8, 0.1], [0.9, -0.4, 1.2]]);
+        let weights = Matrix::from_fn(4, 3, |row, col| (row * 3 + col) as f64 * 0.1 - 0.5);
+        let gamma = vec![1.2, 0.7, -0.3];
+        let layer = || BatchNorm::from_parts(Shape::vector(3), gamma.clone(), vec![0.1, 0.0, -0.2], vec![0.0; 3], vec![1.0; 3]);
+        let loss = |layer: &mut BatchNorm, input: &Matrix| -> f64 {
+            layer.forward(input).zip_map(&weights, |y, w| y * w).as_slice().iter().sum()
+        };
+
+        let mut bn = layer();
+        bn.forward(&input);
+        let input_grad = bn.backward(&weights);
+
+        let epsilon = 1e-6;
+        for i in 0..input.as_slice().len() {
+            let (mut plus, mut minus) = (input.clone(), input.clone());
+            plus.as_mut_slice()[i] += epsilon;
+            minus.as_mut_slice()[i] -= epsilon;
+            let numeric = (loss(&mut layer(), &plus) - loss(&mut layer(), &minus)) / (2.0 * epsilon);
+            assert!((numeric - input_grad.as_slice()[i]).abs() < 1e-6, "input {}: {} vs {}", i, numeric, input_grad.as_slice()[i]);
+        }
+        for i in 0..3 {
+            let shifted = |delta: f64| {
+                let mut bn = layer();
+                bn.gamma[i] += delta;
+                loss(&mut bn, &input)
+            };
+            let numeric = (shifted(epsilon) - shifted(-epsilon)) / (2.0 * epsilon);
+            assert!((numeric - bn.gradients()[0][i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_running_statistics() {
+        let mut bn = BatchNorm::<f64>::new(2).with_momentum(0.5);
+        let input = Matrix::from_rows(&[[1.0, 10.0], [3.0, 10.0]]);
+        let output = bn.forward(&input);
+        // 批次内归一化：均值 0，第二列方差为 0，输出为 β
+        assert!((output[(0, 0)] + 1.0).abs() < 1e-4 && (output[(1, 0)] - 1.0).abs() < 1e-4);
+        assert_eq!(output[(0, 1)], 0.0);
+        assert_eq!(bn.running_mean(), &[1.0, 5.0]);
+        assert_eq!(bn.running_var(), &[1.0, 0.5]);
+
+        bn.set_training(false);
+        assert_eq!(bn.forward(&input), bn.predict(&input));
+        assert_eq!(bn.running_mean(), &[1.0, 5.0]);
+    }
+}
@@ -0,0 +1,340 @@
+use rand::Rng;
+
+use crate::activation::Activation;
+use crate::float::Float;
+use crate::init::Initializer;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// 卷积层的配置：卷积核个数和大小（正方形）、步长、填充、激活函数和参数初始化方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conv2DSpec {
+    pub filters: usize,
+    pub kernel_size: usize,
+    /// 默认 1
+    pub stride: usize,
+    /// 四周补 0 的宽度，默认 0
+    pub padding: usize,
+    pub activation: Activation,
+    /// 默认按激活函数选择，见 [`Initializer::for_activation`]
+    pub weight_init: Initializer,
+    /// 默认全 0
+    pub bias_init: Initializer,
+}
+
+impl Conv2DSpec {
+    pub fn new(filters: usize, kernel_size: usize, activation: Activation) -> Self {
+        Conv2DSpec {
+            filters,
+            kernel_size,
+            stride: 1,
+            padding: 0,
+            activation,
+            weight_init: Initializer::for_activation(activation),
+            bias_init: Initializer::Zeros,
+        }
+    }
+
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: usize) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_weight_init(mut self, init: Initializer) -> Self {
+        self.weight_init = init;
+        self
+    }
+
+    pub fn with_bias_init(mut self, init: Initializer) -> Self {
+        self.bias_init = init;
+        self
+    }
+}
+
+/// 二维卷积层，输入输出都是 通道 x 高 x 宽。
+///
+/// 前向传播用 im2col 把每个卷积窗口展开成一行，整个批次的卷积变成一次矩阵乘法：
+/// `cols (N·out_h·out_w x C·k·k) * W^T (C·k·k x filters)`。
+#[derive(Debug)]
+pub struct Conv2D<T: Float = f64> {
+    input: (usize, usize, usize),
+    output: (usize, usize, usize),
+    kernel_size: usize,
+    stride: usize,
+    padding: usize,
+    activation: Activation,
+    // filters x (C·k·k)，每行是一个卷积核，按通道、行、列展开
+    weights: Matrix<T>,
+    biases: Vec<T>,
+    // 前向传播的缓存，反向传播时使用
+    cols: Matrix<T>,
+    pre_activation: Matrix<T>,
+    activated: Matrix<T>,
+    weight_grads: Matrix<T>,
+    bias_grads: Vec<T>,
+}
+
+impl<T: Float> Conv2D<T> {
+    pub fn new<R: Rng + ?Sized>(input: Shape, spec: Conv2DSpec, rng: &mut R) -> Conv2D<T> {
+        let (channels, _, _) = image_dims(&input, "Conv2D");
+        let window = channels * spec.kernel_size * spec.kernel_size;
+        let fan_out = spec.filters * spec.kernel_size * spec.kernel_size;
+        let weights = Matrix::from_fn(spec.filters, window, |_, _| T::from_f64(spec.weight_init.sample(window, fan_out, rng)));
+        let biases = (0..spec.filters).map(|_| T::from_f64(spec.bias_init.sample(window, fan_out, rng))).collect();
+        Self::from_parts(input, spec, weights, biases)
+    }
+
+    /// 用已有的参数构造，`spec` 中的初始化方式不起作用。
+    /// `weights` 必须是 filters x (C·k·k)，`biases` 的长度必须是 filters
+    pub fn from_parts(input: Shape, spec: Conv2DSpec, weights: Matrix<T>, biases: Vec<T>) -> Conv2D<T> {
+        let (channels, height, width) = image_dims(&input, "Conv2D");
+        assert!(spec.kernel_size > 0 && spec.stride > 0, "kernel size and stride must be positive");
+        assert!(
+            height + 2 * spec.padding >= spec.kernel_size && width + 2 * spec.padding >= spec.kernel_size,
+            "kernel {} is larger than the padded input {}",
+            spec.kernel_size,
+            input
+        );
+        assert_eq!(weights.shape(), (spec.filters, channels * spec.kernel_size * spec.kernel_size), "weights must be filters x (channels * kernel_size^2)");
+        assert_eq!(biases.len(), spec.filters, "one bias per filter is required");
+        let out_size = |len: usize| (len + 2 * spec.padding - spec.kernel_size) / spec.stride + 1;
+        Conv2D {
+            input: (channels, height, width),
+            output: (spec.filters, out_size(height), out_size(width)),
+            kernel_size: spec.kernel_size,
+            stride: spec.stride,
+            padding: spec.padding,
+            activation: spec.activation,
+            weight_grads: Matrix::zeros(weights.rows(), weights.cols()),
+            bias_grads: vec![T::zero(); biases.len()],
+            weights,
+            biases,
+            cols: Matrix::zeros(0, 0),
+            pre_activation: Matrix::zeros(0, 0),
+            activated: Matrix::zeros(0, 0),
+        }
+    }
+
+    /// filters x (C·k·k)
+    pub fn weights(&self) -> &Matrix<T> {
+        &self.weights
+    }
+
+    pub fn biases(&self) -> &[T] {
+        &self.biases
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.activation
+    }
+
+    fn out_positions(&self) -> usize {
+        self.output.1 * self.output.2
+    }
+
+    /// 窗口展开：第 n 个样本在位置 (oy, ox) 的窗口是第 n·out_h·out_w + oy·out_w + ox 行，
+    /// 越界（填充）的元素为 0
+    fn im2col(&self, input: &Matrix<T>) -> Matrix<T> {
+        let (channels, _, _) = self.input;
+        let (_, out_h, out_w) = self.output;
+        let k = self.kernel_size;
+        let mut cols = Matrix::zeros(input.rows() * out_h * out_w, channels * k * k);
+        for n in 0..input.rows() {
+            let sample = input.row(n);
+            for oy in 0..out_h {
+                for ox in 0..out_w {
+                    let row = cols.row_mut((n * out_h + oy) * out_w + ox);
+                    self.for_each_tap(oy, ox, |col, index| row[col] = sample[index]);
+                }
+            }
+        }
+        cols
+    }
+
+    /// im2col 的逆操作，把每个窗口的梯度加回输入对应的位置
+    fn col2im(&self, cols: &Matrix<T>, samples: usize) -> Matrix<T> {
+        let (channels, height, width) = self.input;
+        let (_, out_h, out_w) = self.output;
+        let mut input_grad = Matrix::zeros(samples, channels * height * width);
+        for n in 0..samples {
+            let sample = input_grad.row_mut(n);
+            for oy in 0..out_h {
+                for ox in 0..out_w {
+                    let row = cols.row((n * out_h + oy) * out_w + ox);
+                    self.for_each_tap(oy, ox, |col, index| sample[index] += row[col]);
+                }
+            }
+        }
+        input_grad
+    }
+
+    /// 遍历输出位置 (oy, ox) 的窗口中没有落在填充区的元素，给出 (窗口内下标, 输入样本内下标)
+    fn for_each_tap(&self, oy: usize, ox: usize, mut f: impl FnMut(usize, usize)) {
+        let (channels, height, width) = self.input;
+        let k = self.kernel_size;
+        for c in 0..channels {
+            for ky in 0..k {
+                // 窗口坐标减去填充得到输入坐标，落在填充区的跳过
+                let Some(iy) = (oy * self.stride + ky).checked_sub(self.padding).filter(|&iy| iy < height) else {
+                    continue;
+                };
+                for kx in 0..k {
+                    let Some(ix) = (ox * self.stride + kx).checked_sub(self.padding).filter(|&ix| ix < width) else {
+                        continue;
+                    };
+                    f((c * k + ky) * k + kx, (c * height + iy) * width + ix);
+                }
+            }
+        }
+    }
+
+    /// 返回 (cols, Z)，Z 每行一个样本，按 卷积核、行、列 展开
+    fn pre_activate(&self, input: &Matrix<T>) -> (Matrix<T>, Matrix<T>) {
+        let cols = self.im2col(input);
+        let product = cols.matmul(&self.weights.transpose());
+        let positions = self.out_positions();
+        let z = Matrix::from_fn(input.rows(), self.weights.rows() * positions, |n, index| {
+            let (filter, position) = (index / positions, index % positions);
+            product[(n * positions + position, filter)] + self.biases[filter]
+        });
+        (cols, z)
+    }
+
+    fn activate(&self, z: &Matrix<T>) -> Matrix<T> {
+        let mut output = z.clone();
+        for row in 0..z.rows() {
+            output.row_mut(row).copy_from_slice(&self.activation.forward(z.row(row)));
+        }
+        output
+    }
+
+    /// 已知损失对激活前值 z 的梯度时的反向传播
+    fn backward_pre_activation(&mut self, delta: &Matrix<T>) -> Matrix<T> {
+        // 排成和 im2col 乘积相同的布局：每行一个 (样本, 位置)，每列一个卷积核
+        let positions = self.out_positions();
+        let d = Matrix::from_fn(delta.rows() * positions, self.weights.rows(), |row, filter| {
+            delta[(row / positions, filter * positions + row % positions)]
+        });
+        // dW += D^T * cols，db += Σ D，dcols = D * W
+        self.weight_grads.add_assign(&d.transpose().matmul(&self.cols));
+        self.bias_grads.iter_mut().zip(d.sum_rows()).for_each(|(g, s)| *g += s);
+        self.col2im(&d.matmul(&self.weights), delta.rows())
+    }
+}
+
+/// 三维形状的 (通道, 高, 宽)，否则 panic
+pub(crate) fn image_dims(shape: &Shape, layer: &str) -> (usize, usize, usize) {
+    shape.as_image().unwrap_or_else(|| panic!("{} expects a channels x height x width input, got {}", layer, shape))
+}
+
+impl<T: Float> Layer<T> for Conv2D<T> {
+    fn input_shape(&self) -> Shape {
+        Shape::image(self.input.0, self.input.1, self.input.2)
+    }
+
+    fn output_shape(&self) -> Shape {
+        Shape::image(self.output.0, self.output.1, self.output.2)
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let (cols, z) = self.pre_activate(input);
+        self.cols = cols;
+        self.activated = self.activate(&z);
+        self.pre_activation = z;
+        self.activated.clone()
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        self.activate(&self.pre_activate(input).1)
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        let mut delta = Matrix::zeros(output_grad.rows(), output_grad.cols());
+        for row in 0..output_grad.rows() {
+            let d = self.activation.backward(self.pre_activation.row(row), self.activated.row(row), output_grad.row(row));
+            delta.row_mut(row).copy_from_slice(&d);
+        }
+        self.backward_pre_activation(&delta)
+    }
+
+    fn fused_backward(&mut self, delta: &mut dyn FnMut(Activation) -> Option<Matrix<T>>) -> Option<Matrix<T>> {
+        let delta = delta(self.activation)?;
+        Some(self.backward_pre_activation(&delta))
+    }
+
+    /// 两组参数：卷积核（行优先）和偏置
+    fn parameters(&self) -> Vec<&[T]> {
+        vec![self.weights.as_slice(), &self.biases]
+    }
+
+    fn gradients(&self) -> Vec<&[T]> {
+        vec![self.weight_grads.as_slice(), &self.bias_grads]
+    }
+
+    fn visit_parameters(&mut self, f: &mut dyn FnMut(&mut [T], &mut [T])) {
+        f(self.weights.as_mut_slice(), self.weight_grads.as_mut_slice());
+        f(&mut self.biases, &mut self.bias_grads);
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        Some(LayerRecord::Conv2D {
+            input_shape: self.input_shape(),
+            filters: self.weights.rows(),
+            kernel_size: self.kernel_size,
+            stride: self.stride,
+            padding: self.padding,
+            activation: self.activation,
+            weights: self.weights.as_slice().iter().map(|w| w.to_f64()).collect(),
+            biases: self.biases.iter().map(|b| b.to_f64()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_direct_convolution() {
+        let spec = Conv2DSpec::new(2, 3, Activation::Identity).with_stride(2).with_padding(1);
+        let weights = Matrix::from_fn(2, 2 * 9, |f, i| (f * 18 + i) as f64 * 0.05 - 0.4);
+        let layer = Conv2D::from_parts(Shape::image(2, 5, 4), spec, weights.clone(), vec![0.1, -0.2]);
+        assert_eq!(layer.output_shape(), Shape::image(2, 3, 2));
+
+        let input = Matrix::from_fn(2, 2 * 5 * 4, |n, i| ((n * 40 + i) as f64 * 0.37).sin());
+        let output = layer.predict(&input);
+        for n in 0..2 {
+            for f in 0..2 {
+                for oy in 0..3 {
+                    for ox in 0..2 {
+                        let mut sum = [0.1, -0.2][f];
+                        for c in 0..2 {
+                            for ky in 0..3 {
+                                for kx in 0..3 {
+                                    let (iy, ix) = ((oy * 2 + ky) as isize - 1, (ox * 2 + kx) as isize - 1);
+                                    if (0..5).contains(&iy) && (0..4).contains(&ix) {
+                                        let value = input[(n, (c * 5 + iy as usize) * 4 + ix as usize)];
+                                        sum += weights[(f, (c * 3 + ky) * 3 + kx)] * value;
+                                    }
+                                }
+                            }
+                        }
+                        assert!((output[(n, (f * 3 + oy) * 2 + ox)] - sum).abs() < 1e-12);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expects a channels x height x width input")]
+    fn test_requires_image_input() {
+        let _ = Conv2D::<f64>::new(Shape::vector(16), Conv2DSpec::new(1, 3, Activation::ReLU), &mut rand::thread_rng());
+    }
+}
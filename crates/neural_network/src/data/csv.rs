@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::Path;
+
+use super::{one_hot, DataError, Dataset};
+use crate::float::Float;
+
+/// 按下标（从 0 开始）或表头中的名称指定一列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Column {
+        Column::Index(index)
+    }
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Column {
+        Column::Name(name.to_string())
+    }
+}
+
+impl From<String> for Column {
+    fn from(name: String) -> Column {
+        Column::Name(name)
+    }
+}
+
+/// CSV 的读取方式
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// 默认逗号
+    pub delimiter: char,
+    /// 第一行是否是表头，默认是
+    pub has_header: bool,
+    /// 特征列，默认除目标列以外的所有列
+    pub features: Option<Vec<Column>>,
+    /// 目标列，默认最后一列
+    pub targets: Option<Vec<Column>>,
+    /// 把唯一的目标列当作类别做 one-hot 编码，默认按数值读取
+    pub one_hot_target: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions { delimiter: ',', has_header: true, features: None, targets: None, one_hot_target: false }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn without_header(mut self) -> Self {
+        self.has_header = false;
+        self
+    }
+
+    pub fn with_features<C: Into<Column>>(mut self, columns: impl IntoIterator<Item = C>) -> Self {
+        self.features = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_target(self, column: impl Into<Column>) -> Self {
+        self.with_targets([column])
+    }
+
+    pub fn with_targets<C: Into<Column>>(mut self, columns: impl IntoIterator<Item = C>) -> Self {
+        self.targets = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_one_hot_target(mut self) -> Self {
+        self.one_hot_target = true;
+        self
+    }
+}
+
+impl<T: Float> Dataset<T> {
+    pub fn from_csv<P: AsRef<Path>>(path: P, options: &CsvOptions) -> Result<Dataset<T>, DataError> {
+        Self::parse_csv(&fs::read_to_string(path)?, options)
+    }
+
+    /// 解析 CSV 文本。字段可以用双引号括起来（`""` 表示一个引号），空行跳过
+    pub fn parse_csv(text: &str, options: &CsvOptions) -> Result<Dataset<T>, DataError> {
+        let mut records = parse_records(text, options.delimiter).into_iter();
+        let header = if options.has_header { records.next().map(|(_, fields)| fields) } else { None };
+        let rows: Vec<(usize, Vec<String>)> = records.collect();
+        let width = match (&header, rows.first()) {
+            (Some(header), _) => header.len(),
+            (None, Some((_, fields))) => fields.len(),
+            (None, None) => return Err(DataError::Empty),
+        };
+        if let Some(&(line, ref fields)) = rows.iter().find(|(_, fields)| fields.len() != width) {
+            return Err(DataError::RaggedRow { line, expected: width, found: fields.len() });
+        }
+
+        let resolve = |column: &Column| -> Result<usize, DataError> {
+            let index = match column {
+                Column::Index(index) => *index,
+                Column::Name(name) => header.as_ref()
+                    .and_then(|header| header.iter().position(|h| h == name))
+                    .ok_or_else(|| DataError::MissingColumn(name.clone()))?,
+            };
+            if index < width { Ok(index) } else { Err(DataError::ColumnOutOfRange(index)) }
+        };
+        let targets = match &options.targets {
+            Some(columns) => columns.iter().map(resolve).collect::<Result<Vec<_>, _>>()?,
+            None => vec![width - 1],
+        };
+        let features = match &options.features {
+            Some(columns) => columns.iter().map(resolve).collect::<Result<Vec<_>, _>>()?,
+            None => (0..width).filter(|i| !targets.contains(i)).collect(),
+        };
+        let column_name = |index: usize| header.as_ref().map_or_else(|| index.to_string(), |header| header[index].clone());
+        let number = |line: usize, index: usize, value: &str| -> Result<T, DataError> {
+            value.trim().parse::<f64>().map(T::from_f64).map_err(|_| DataError::Parse {
+                line,
+                column: column_name(index),
+                value: value.to_string(),
+            })
+        };
+
+        let inputs = rows.iter()
+            .map(|(line, fields)| features.iter().map(|&i| number(*line, i, &fields[i])).collect())
+            .collect::<Result<Vec<Vec<T>>, _>>()?;
+        let (targets, classes) = if options.one_hot_target {
+            let &[target] = &targets[..] else {
+                return Err(DataError::OneHotColumns(targets.len()));
+            };
+            let values: Vec<&str> = rows.iter().map(|(_, fields)| fields[target].trim()).collect();
+            let classes = sorted_classes(&values);
+            let encoded = values.iter()
+                .map(|value| one_hot(classes.iter().position(|c| c == value).unwrap(), classes.len()))
+                .collect();
+            (encoded, Some(classes))
+        } else {
+            let values = rows.iter()
+                .map(|(line, fields)| targets.iter().map(|&i| number(*line, i, &fields[i])).collect())
+                .collect::<Result<Vec<Vec<T>>, _>>()?;
+            (values, None)
+        };
+
+        Ok(Dataset {
+            samples: inputs.into_iter().zip(targets).collect(),
+            feature_names: header.as_ref().map_or_else(Vec::new, |_| features.iter().map(|&i| column_name(i)).collect()),
+            classes,
+        })
+    }
+}
+
+/// 去重后排序，都是数字时按数值排序
+fn sorted_classes(values: &[&str]) -> Vec<String> {
+    let mut classes: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    classes.sort();
+    classes.dedup();
+    if classes.iter().all(|c| c.parse::<f64>().is_ok()) {
+        classes.sort_by(|a, b| a.parse::<f64>().unwrap().total_cmp(&b.parse::<f64>().unwrap()));
+    }
+    classes
+}
+
+/// 拆分成 (行号, 字段)，行号从 1 开始。引号内可以有分隔符和换行
+fn parse_records(text: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let (mut fields, mut field) = (Vec::new(), String::new());
+    let (mut line, mut record_line) = (1, 1);
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                // 空行跳过
+                if fields.len() > 1 || !fields[0].trim().is_empty() {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IRIS: &str = "sepal length,sepal width,species\n5.1,3.5,setosa\n\n7.0,3.2,versicolor\r\n6.3,3.3,\"virginica\"\n4.9,3.0,setosa\n";
+
+    #[test]
+    fn test_one_hot_target() {
+        let options = CsvOptions::new().with_target("species").with_one_hot_target();
+        let dataset = Dataset::<f64>::parse_csv(IRIS, &options).unwrap();
+        assert_eq!(dataset.len(), 4);
+        assert_eq!(dataset.classes().unwrap(), ["setosa", "versicolor", "virginica"]);
+        assert_eq!(dataset.feature_names(), ["sepal length", "sepal width"]);
+        assert_eq!(dataset.samples()[2], (vec![6.3, 3.3], vec![0.0, 0.0, 1.0]));
+        assert_eq!(dataset.labels(), vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let options = CsvOptions::new().with_delimiter(';').without_header().with_features([1]).with_target(0);
+        // 引号里的分隔符属于字段内容
+        assert!(matches!(
+            Dataset::<f64>::parse_csv("1;\"2;5\";3\n", &options),
+            Err(DataError::Parse { line: 1, ref column, ref value }) if column == "1" && value == "2;5"
+        ));
+        let options = options.with_features([2, 1]);
+        let dataset = Dataset::<f32>::parse_csv("1;2;3\n4;5;6", &options).unwrap();
+        assert_eq!(dataset.samples(), [(vec![3.0, 2.0], vec![1.0]), (vec![6.0, 5.0], vec![4.0])]);
+        assert!(dataset.feature_names().is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let options = CsvOptions::new();
+        assert!(matches!(Dataset::<f64>::parse_csv("a,b\n1,2\n3\n", &options), Err(DataError::RaggedRow { line: 3, expected: 2, found: 1 })));
+        assert!(matches!(Dataset::<f64>::parse_csv("a,b\n1,x\n", &options), Err(DataError::Parse { line: 2, .. })));
+        let missing = CsvOptions::new().with_target("c");
+        assert!(matches!(Dataset::<f64>::parse_csv("a,b\n1,2\n", &missing), Err(DataError::MissingColumn(_))));
+        let two = CsvOptions::new().with_targets([0, 1]).with_one_hot_target();
+        assert!(matches!(Dataset::<f64>::parse_csv("a,b\n1,2\n", &two), Err(DataError::OneHotColumns(2))));
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::Path;
+
+use super::{one_hot, DataError, Dataset};
+use crate::float::Float;
+
+/// IDX 文件的内容，`data` 按行优先展开，长度是 `dims` 的乘积
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxArray {
+    pub dims: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+/// 读取 MNIST 使用的 IDX 格式（未压缩，`.gz` 需要先解压）。
+///
+/// 格式：两个 0 字节 | 元素类型 | 维数 n | n 个大端 u32 的维度 | 大端存储的数据。
+/// 元素类型 0x08 u8、0x09 i8、0x0B i16、0x0C i32、0x0D f32、0x0E f64
+pub fn read_idx<P: AsRef<Path>>(path: P) -> Result<IdxArray, DataError> {
+    parse_idx(&fs::read(path)?)
+}
+
+pub(crate) fn parse_idx(bytes: &[u8]) -> Result<IdxArray, DataError> {
+    let error = |message: &str| DataError::Idx(message.to_string());
+    let &[0, 0, kind, ndims, ref rest @ ..] = bytes else {
+        return Err(error("bad magic number"));
+    };
+    let element_size = match kind {
+        0x08 | 0x09 => 1,
+        0x0B => 2,
+        0x0C | 0x0D => 4,
+        0x0E => 8,
+        _ => return Err(DataError::Idx(format!("unknown element type 0x{:02x}", kind))),
+    };
+    let ndims = ndims as usize;
+    if rest.len() < 4 * ndims {
+        return Err(error("truncated header"));
+    }
+    let (header, body) = rest.split_at(4 * ndims);
+    let dims: Vec<usize> = header.chunks_exact(4).map(|b| u32::from_be_bytes(b.try_into().unwrap()) as usize).collect();
+    let count = dims.iter().try_fold(1usize, |acc, &d| acc.checked_mul(d)).ok_or_else(|| error("dimensions overflow"))?;
+    let len = count.checked_mul(element_size).ok_or_else(|| error("dimensions overflow"))?;
+    if body.len() != len {
+        return Err(DataError::Idx(format!("expected {} bytes of data, found {}", len, body.len())));
+    }
+    let data = body.chunks_exact(element_size).map(|b| match kind {
+        0x08 => b[0] as f64,
+        0x09 => b[0] as i8 as f64,
+        0x0B => i16::from_be_bytes([b[0], b[1]]) as f64,
+        0x0C => i32::from_be_bytes(b.try_into().unwrap()) as f64,
+        0x0D => f32::from_be_bytes(b.try_into().unwrap()) as f64,
+        _ => f64::from_be_bytes(b.try_into().unwrap()),
+    }).collect();
+    Ok(IdxArray { dims, data })
+}
+
+impl<T: Float> Dataset<T> {
+    /// 读取 MNIST 格式的图像和标签文件。图像按行优先展开成一个样本，u8 像素除以 255 缩放到 [0, 1]；
+    /// 标签必须和 MNIST 一样是 u8（元素类型 0x08），做 one-hot 编码，类别数为最大标签加 1
+    pub fn from_idx<P: AsRef<Path>, Q: AsRef<Path>>(images: P, labels: Q) -> Result<Dataset<T>, DataError> {
+        let (image_bytes, label_bytes) = (fs::read(images)?, fs::read(labels)?);
+        Self::from_idx_bytes(&image_bytes, &label_bytes)
+    }
+
+    pub(crate) fn from_idx_bytes(images: &[u8], labels: &[u8]) -> Result<Dataset<T>, DataError> {
+        let scale = if images.get(2) == Some(&0x08) { 1.0 / 255.0 } else { 1.0 };
+        // 限制类别数，一个损坏的大标签不会让每个样本都分配巨大的 one-hot 向量
+        if labels.get(2).is_some_and(|&kind| kind != 0x08) {
+            return Err(DataError::Idx("labels must be unsigned bytes (element type 0x08)".to_string()));
+        }
+        let (images, labels) = (parse_idx(images)?, parse_idx(labels)?);
+        let count = *images.dims.first().ok_or_else(|| DataError::Idx("images have no dimensions".to_string()))?;
+        if labels.dims != [count] {
+            return Err(DataError::Idx(format!("expected {} labels, found dimensions {:?}", count, labels.dims)));
+        }
+        let classes = labels.data.iter().fold(0.0f64, |max, &l| max.max(l)) as usize + 1;
+        let size = images.data.len() / count.max(1);
+        let samples = images.data.chunks(size.max(1)).zip(&labels.data)
+            .map(|(pixels, &label)| (pixels.iter().map(|&p| T::from_f64(p * scale)).collect(), one_hot(label as usize, classes)))
+            .collect();
+        Ok(Dataset::new(samples).with_classes((0..classes).map(|c| c.to_string()).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idx(kind: u8, dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, kind, dims.len() as u8];
+        dims.iter().for_each(|d| bytes.extend_from_slice(&d.to_be_bytes()));
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_mnist_format() {
+        // 两张 2x2 的图像
+        let images = idx(0x08, &[2, 2, 2], &[0, 255, 51, 0, 255, 255, 0, 0]);
+        let labels = idx(0x08, &[2], &[3, 1]);
+        let dataset = Dataset::<f64>::from_idx_bytes(&images, &labels).unwrap();
+        assert_eq!(dataset.samples()[0], (vec![0.0, 1.0, 0.2, 0.0], vec![0.0, 0.0, 0.0, 1.0]));
+        assert_eq!(dataset.labels(), vec![3, 1]);
+        assert_eq!(dataset.classes().unwrap().len(), 4);
+
+        let floats = idx(0x0D, &[2], &[0x3f, 0x80, 0, 0, 0xc0, 0, 0, 0]);
+        assert_eq!(parse_idx(&floats).unwrap(), IdxArray { dims: vec![2], data: vec![1.0, -2.0] });
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(parse_idx(&[1, 2, 3]).is_err());
+        assert!(parse_idx(&idx(0x07, &[1], &[0])).is_err());
+        assert!(parse_idx(&idx(0x08, &[3], &[0, 0])).is_err());
+        // 元素个数乘以元素大小溢出
+        let overflow = idx(0x0E, &[u32::MAX, u32::MAX], &[]);
+        assert!(matches!(parse_idx(&overflow), Err(DataError::Idx(message)) if message == "dimensions overflow"));
+        let images = idx(0x08, &[2, 1], &[0, 0]);
+        assert!(Dataset::<f64>::from_idx_bytes(&images, &idx(0x08, &[3], &[0, 1, 2])).is_err());
+        // 标签不是 u8
+        let huge_label = idx(0x0C, &[2], &[0x7f, 0xff, 0xff, 0xff, 0, 0, 0, 1]);
+        assert!(matches!(Dataset::<f64>::from_idx_bytes(&images, &huge_label), Err(DataError::Idx(_))));
+    }
+}
@@ -0,0 +1,168 @@
+//! 训练数据：CSV 和 MNIST IDX 文件的读取、特征缩放、训练/测试集划分和 k 折交叉验证。
+//!
+//! ```no_run
+//! use neural_network::activation::Activation;
+//! use neural_network::data::{CsvOptions, Dataset, Scaler, StandardScaler};
+//! use neural_network::sequential::Sequential;
+//!
+//! let options = CsvOptions::new().with_target("species").with_one_hot_target();
+//! let dataset: Dataset = Dataset::from_csv("iris.csv", &options).unwrap();
+//! let (train, test) = dataset.stratified_split(0.2, 42);
+//! // 缩放层作为网络的第一层，与模型一起保存
+//! let scaler = StandardScaler::fit(&train).unwrap();
+//! let network = Sequential::<f64>::new(train.input_size())
+//!     .layer(scaler.layer())
+//!     .dense(8, Activation::ReLU)
+//!     .dense(train.target_size(), Activation::Softmax)
+//!     .build();
+//! ```
+
+use std::io;
+
+use thiserror::Error;
+
+use crate::float::Float;
+
+mod csv;
+mod idx;
+mod scaler;
+mod split;
+mod synthetic;
+
+pub use self::csv::{Column, CsvOptions};
+pub use self::idx::{read_idx, IdxArray};
+pub use self::scaler::{MinMaxScaler, Rescale, Scaler, StandardScaler};
+pub use self::split::KFold;
+pub use self::synthetic::{shapes_image_shape, synthetic_shapes, SHAPES_CLASSES, SHAPES_SIZE};
+
+#[derive(Error, Debug)]
+pub enum DataError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    /// `line` 从 1 开始，包括表头
+    #[error("line {line}, column {column}: cannot parse {value:?} as a number")]
+    Parse { line: usize, column: String, value: String },
+    #[error("line {line}: expected {expected} fields, found {found}")]
+    RaggedRow { line: usize, expected: usize, found: usize },
+    /// `index` 从 0 开始，`what` 是 input 或 target
+    #[error("sample {index}: expected {what} length {expected}, found {found}")]
+    RaggedSample { index: usize, what: &'static str, expected: usize, found: usize },
+    #[error("no column named {0:?}")]
+    MissingColumn(String),
+    #[error("column index {0} is out of range")]
+    ColumnOutOfRange(usize),
+    #[error("one-hot encoding needs exactly one target column, found {0}")]
+    OneHotColumns(usize),
+    #[error("invalid IDX file: {0}")]
+    Idx(String),
+    #[error("the dataset is empty")]
+    Empty,
+}
+
+/// 每个样本是一对 (输入, 目标)，可以直接传给 [`Trainer::fit`](crate::trainer::Trainer::fit)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset<T: Float = f64> {
+    samples: Vec<(Vec<T>, Vec<T>)>,
+    feature_names: Vec<String>,
+    // 目标做了 one-hot 编码时各类别的名称，按编码顺序
+    classes: Option<Vec<String>>,
+}
+
+impl<T: Float> Dataset<T> {
+    /// 所有样本的输入长度必须相同，目标长度也必须相同，否则 panic。
+    /// 样本来自外部数据时用 [`Dataset::try_new`]
+    pub fn new(samples: Vec<(Vec<T>, Vec<T>)>) -> Dataset<T> {
+        Self::try_new(samples).expect("all samples must have the same input and target lengths")
+    }
+
+    /// 与 `new` 相同，长度不一致时返回 `RaggedSample`，长度以第一个样本为准
+    pub fn try_new(samples: Vec<(Vec<T>, Vec<T>)>) -> Result<Dataset<T>, DataError> {
+        if let Some((input, target)) = samples.first() {
+            for (index, (x, y)) in samples.iter().enumerate() {
+                for (what, expected, found) in [("input", input.len(), x.len()), ("target", target.len(), y.len())] {
+                    if expected != found {
+                        return Err(DataError::RaggedSample { index, what, expected, found });
+                    }
+                }
+            }
+        }
+        Ok(Dataset { samples, feature_names: Vec::new(), classes: None })
+    }
+
+    pub fn with_feature_names(mut self, names: Vec<String>) -> Self {
+        self.feature_names = names;
+        self
+    }
+
+    pub fn with_classes(mut self, classes: Vec<String>) -> Self {
+        self.classes = Some(classes);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn samples(&self) -> &[(Vec<T>, Vec<T>)] {
+        &self.samples
+    }
+
+    pub fn samples_mut(&mut self) -> &mut [(Vec<T>, Vec<T>)] {
+        &mut self.samples
+    }
+
+    pub fn into_samples(self) -> Vec<(Vec<T>, Vec<T>)> {
+        self.samples
+    }
+
+    /// 没有样本时为 0
+    pub fn input_size(&self) -> usize {
+        self.samples.first().map_or(0, |(input, _)| input.len())
+    }
+
+    pub fn target_size(&self) -> usize {
+        self.samples.first().map_or(0, |(_, target)| target.len())
+    }
+
+    /// 从 CSV 读取时为特征列的表头，没有表头或不是从 CSV 读取时为空
+    pub fn feature_names(&self) -> &[String] {
+        &self.feature_names
+    }
+
+    /// 目标做了 one-hot 编码时各类别的名称
+    pub fn classes(&self) -> Option<&[String]> {
+        self.classes.as_deref()
+    }
+
+    /// 每个样本的类别：目标有多个元素时取最大值的下标（one-hot），只有一个元素时取四舍五入后的值
+    pub fn labels(&self) -> Vec<usize> {
+        self.samples.iter().map(|(_, target)| label(target)).collect()
+    }
+
+    /// 按下标取出样本，保留特征名和类别
+    pub fn subset(&self, indices: &[usize]) -> Dataset<T> {
+        Dataset {
+            samples: indices.iter().map(|&i| self.samples[i].clone()).collect(),
+            feature_names: self.feature_names.clone(),
+            classes: self.classes.clone(),
+        }
+    }
+}
+
+pub(crate) fn label<T: Float>(target: &[T]) -> usize {
+    match target {
+        [value] => value.to_f64().round().max(0.0) as usize,
+        _ => (0..target.len()).fold(0, |best, i| if target[i] > target[best] { i } else { best }),
+    }
+}
+
+/// 类别下标的 one-hot 编码
+pub fn one_hot<T: Float>(class: usize, classes: usize) -> Vec<T> {
+    let mut encoded = vec![T::zero(); classes];
+    encoded[class] = T::one();
+    encoded
+}
@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use super::{DataError, Dataset};
+use crate::autodiff::Var;
+use crate::error::{check_len, NnError};
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// 逐特征的线性缩放 y = x * scale + offset，只缩放输入，不缩放目标。
+///
+/// 缩放器可以单独序列化保存；也可以用 [`Scaler::layer`] 转成网络的第一层，
+/// 这样缩放参数会随模型一起保存，推理时直接输入原始特征。
+pub trait Scaler {
+    /// 每个特征的 (scale, offset)
+    fn scale_offset(&self) -> (Vec<f64>, Vec<f64>);
+
+    /// 输入长度与特征数不符时返回 `ShapeMismatch`
+    fn transform<T: Float>(&self, input: &[T]) -> Result<Vec<T>, NnError> {
+        let (scale, offset) = self.scale_offset();
+        check_len("input", scale.len(), input.len())?;
+        Ok(input.iter().zip(scale.iter().zip(&offset)).map(|(&x, (&s, &o))| x * T::from_f64(s) + T::from_f64(o)).collect())
+    }
+
+    fn inverse_transform<T: Float>(&self, input: &[T]) -> Result<Vec<T>, NnError> {
+        let (scale, offset) = self.scale_offset();
+        check_len("input", scale.len(), input.len())?;
+        Ok(input.iter().zip(scale.iter().zip(&offset)).map(|(&y, (&s, &o))| (y - T::from_f64(o)) / T::from_f64(s)).collect())
+    }
+
+    /// 原地缩放数据集中所有样本的输入；先检查所有样本，出错时数据集保持不变
+    fn transform_dataset<T: Float>(&self, dataset: &mut Dataset<T>) -> Result<(), NnError> {
+        let features = self.scale_offset().0.len();
+        for (input, _) in dataset.samples() {
+            check_len("input", features, input.len())?;
+        }
+        for (input, _) in dataset.samples_mut() {
+            *input = self.transform(input)?;
+        }
+        Ok(())
+    }
+
+    fn layer<T: Float>(&self) -> Rescale<T> {
+        let (scale, offset) = self.scale_offset();
+        Rescale::new(scale, offset)
+    }
+}
+
+/// 标准化：减去均值再除以标准差，常数特征的标准差按 1 处理
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandardScaler {
+    pub mean: Vec<f64>,
+    pub std_dev: Vec<f64>,
+}
+
+impl StandardScaler {
+    /// 用数据集的输入计算每个特征的均值和（有偏）标准差，数据集为空时返回 `Empty`
+    pub fn fit<T: Float>(dataset: &Dataset<T>) -> Result<StandardScaler, DataError> {
+        if dataset.is_empty() {
+            return Err(DataError::Empty);
+        }
+        let n = dataset.len() as f64;
+        let mut mean = vec![0.0; dataset.input_size()];
+        let mut sum_squares = vec![0.0; dataset.input_size()];
+        for (input, _) in dataset.samples() {
+            for (i, x) in input.iter().enumerate() {
+                mean[i] += x.to_f64() / n;
+            }
+        }
+        for (input, _) in dataset.samples() {
+            for (i, x) in input.iter().enumerate() {
+                sum_squares[i] += (x.to_f64() - mean[i]).powi(2);
+            }
+        }
+        let std_dev = sum_squares.into_iter().map(|s| (s / n).sqrt()).map(|s| if s > 0.0 { s } else { 1.0 }).collect();
+        Ok(StandardScaler { mean, std_dev })
+    }
+}
+
+impl Scaler for StandardScaler {
+    fn scale_offset(&self) -> (Vec<f64>, Vec<f64>) {
+        self.mean.iter().zip(&self.std_dev).map(|(&m, &s)| (1.0 / s, -m / s)).unzip()
+    }
+}
+
+/// 把每个特征线性映射到 [low, high]，默认 [0, 1]。常数特征映射到 low
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinMaxScaler {
+    pub min: Vec<f64>,
+    pub max: Vec<f64>,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl MinMaxScaler {
+    pub fn fit<T: Float>(dataset: &Dataset<T>) -> Result<MinMaxScaler, DataError> {
+        Self::fit_range(dataset, 0.0, 1.0)
+    }
+
+    /// 数据集为空时返回 `Empty`
+    pub fn fit_range<T: Float>(dataset: &Dataset<T>, low: f64, high: f64) -> Result<MinMaxScaler, DataError> {
+        assert!(low < high, "the target range must not be empty");
+        if dataset.is_empty() {
+            return Err(DataError::Empty);
+        }
+        let mut min = vec![f64::INFINITY; dataset.input_size()];
+        let mut max = vec![f64::NEG_INFINITY; dataset.input_size()];
+        for (input, _) in dataset.samples() {
+            for (i, x) in input.iter().enumerate() {
+                min[i] = min[i].min(x.to_f64());
+                max[i] = max[i].max(x.to_f64());
+            }
+        }
+        Ok(MinMaxScaler { min, max, low, high })
+    }
+}
+
+impl Scaler for MinMaxScaler {
+    fn scale_offset(&self) -> (Vec<f64>, Vec<f64>) {
+        self.min.iter().zip(&self.max).map(|(&min, &max)| {
+            let scale = if max > min { (self.high - self.low) / (max - min) } else { 1.0 };
+            (scale, self.low - min * scale)
+        }).unzip()
+    }
+}
+
+/// 逐元素的 y = x * scale + offset，没有可训练的参数。由 [`Scaler::layer`] 创建，作为网络的第一层
+#[derive(Debug)]
+pub struct Rescale<T: Float = f64> {
+    scale: Vec<T>,
+    offset: Vec<T>,
+}
+
+impl<T: Float> Rescale<T> {
+    pub fn new(scale: Vec<f64>, offset: Vec<f64>) -> Rescale<T> {
+        assert_eq!(scale.len(), offset.len(), "scale and offset must have the same length");
+        Rescale { scale: scale.into_iter().map(T::from_f64).collect(), offset: offset.into_iter().map(T::from_f64).collect() }
+    }
+}
+
+impl<T: Float> Layer<T> for Rescale<T> {
+    fn input_shape(&self) -> Shape {
+        Shape::vector(self.scale.len())
+    }
+
+    fn output_shape(&self) -> Shape {
+        Shape::vector(self.scale.len())
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        self.predict(input)
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        Matrix::from_fn(input.rows(), input.cols(), |row, col| input[(row, col)] * self.scale[col] + self.offset[col])
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        Matrix::from_fn(output_grad.rows(), output_grad.cols(), |row, col| output_grad[(row, col)] * self.scale[col])
+    }
+
+    fn forward_on_tape<'t>(&self, input: Var<'t, T>, _parameters: &mut Vec<Var<'t, T>>) -> Option<Var<'t, T>> {
+        let tape = input.tape();
+        Some(input * tape.var(Matrix::row_vector(&self.scale)) + tape.var(Matrix::row_vector(&self.offset)))
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        Some(LayerRecord::Rescale {
+            scale: self.scale.iter().map(|s| s.to_f64()).collect(),
+            offset: self.offset.iter().map(|o| o.to_f64()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset() -> Dataset {
+        Dataset::new(vec![(vec![1.0, 5.0], vec![0.0]), (vec![3.0, 5.0], vec![1.0]), (vec![5.0, 5.0], vec![0.0])])
+    }
+
+    #[test]
+    fn test_standard_scaler() {
+        let scaler = StandardScaler::fit(&dataset()).unwrap();
+        assert_eq!(scaler.mean, vec![3.0, 5.0]);
+        assert_eq!(scaler.std_dev[1], 1.0);
+        let scaled = scaler.transform(&[5.0, 5.0]).unwrap();
+        assert!((scaled[0] - 1.5f64.sqrt()).abs() < 1e-12 && scaled[1] == 0.0);
+        let restored = scaler.inverse_transform(&scaled).unwrap();
+        assert!((restored[0] - 5.0).abs() < 1e-12 && restored[1] == 5.0);
+
+        let json = serde_json::to_string(&scaler).unwrap();
+        assert_eq!(serde_json::from_str::<StandardScaler>(&json).unwrap(), scaler);
+    }
+
+    #[test]
+    fn test_min_max_scaler_layer() {
+        let mut data = dataset();
+        let scaler = MinMaxScaler::fit_range(&data, -1.0, 1.0).unwrap();
+        let layer: Rescale = scaler.layer();
+        let output = layer.predict(&Matrix::from_rows(&[[1.0, 5.0], [4.0, 5.0]]));
+        assert_eq!(output.as_slice(), &[-1.0, -1.0, 0.5, -1.0]);
+
+        scaler.transform_dataset(&mut data).unwrap();
+        assert_eq!(data.samples()[2].0, vec![1.0, -1.0]);
+        assert_eq!(data.samples()[2].1, vec![0.0]);
+    }
+
+    #[test]
+    fn test_empty_dataset() {
+        let empty = Dataset::<f64>::new(Vec::new());
+        assert!(matches!(StandardScaler::fit(&empty), Err(DataError::Empty)));
+        assert!(matches!(MinMaxScaler::fit(&empty), Err(DataError::Empty)));
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        let ragged = Dataset::<f64>::try_new(vec![(vec![1.0, 2.0], vec![0.0]), (vec![3.0], vec![1.0])]);
+        assert!(matches!(ragged, Err(DataError::RaggedSample { index: 1, what: "input", expected: 2, found: 1 })));
+
+        let scaler = StandardScaler::fit(&dataset()).unwrap();
+        let mismatch = NnError::ShapeMismatch { what: "input", expected: 2, actual: 3 };
+        assert_eq!(scaler.transform(&[1.0, 2.0, 3.0]), Err(mismatch.clone()));
+        assert_eq!(scaler.inverse_transform(&[1.0, 2.0, 3.0]), Err(mismatch));
+
+        let mut wide = Dataset::new(vec![(vec![1.0, 2.0, 3.0], vec![0.0])]);
+        assert!(scaler.transform_dataset(&mut wide).is_err());
+        assert_eq!(wide.samples()[0].0, vec![1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::Dataset;
+use crate::float::Float;
+
+impl<T: Float> Dataset<T> {
+    /// 打乱后划分成 (训练集, 测试集)，测试集约占 `test_fraction`。相同的种子得到相同的划分
+    pub fn train_test_split(&self, test_fraction: f64, seed: u64) -> (Dataset<T>, Dataset<T>) {
+        check_fraction(test_fraction);
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        indices.shuffle(&mut StdRng::seed_from_u64(seed));
+        let test_len = (self.len() as f64 * test_fraction).round() as usize;
+        let (test, train) = indices.split_at(test_len);
+        (self.subset(train), self.subset(test))
+    }
+
+    /// 分层划分：每个类别（见 [`Dataset::labels`]）各取约 `test_fraction` 放进测试集，
+    /// 两边的类别比例与原数据集一致
+    pub fn stratified_split(&self, test_fraction: f64, seed: u64) -> (Dataset<T>, Dataset<T>) {
+        check_fraction(test_fraction);
+        let (mut train, mut test) = (Vec::new(), Vec::new());
+        for indices in self.shuffled_classes(seed) {
+            let test_len = (indices.len() as f64 * test_fraction).round() as usize;
+            test.extend_from_slice(&indices[..test_len]);
+            train.extend_from_slice(&indices[test_len..]);
+        }
+        // 否则样本会按类别排好
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(1));
+        train.shuffle(&mut rng);
+        test.shuffle(&mut rng);
+        (self.subset(&train), self.subset(&test))
+    }
+
+    /// k 折交叉验证，每个样本恰好在一折里做一次验证集
+    pub fn k_fold(&self, k: usize, seed: u64) -> KFold<'_, T> {
+        check_folds(k, self.len());
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        indices.shuffle(&mut StdRng::seed_from_u64(seed));
+        let mut folds = vec![Vec::new(); k];
+        indices.into_iter().enumerate().for_each(|(i, index)| folds[i % k].push(index));
+        KFold { dataset: self, folds, next: 0 }
+    }
+
+    /// 分层的 k 折交叉验证，每一折中各类别的比例与原数据集一致
+    pub fn stratified_k_fold(&self, k: usize, seed: u64) -> KFold<'_, T> {
+        check_folds(k, self.len());
+        let mut folds = vec![Vec::new(); k];
+        // 接着上一个类别的位置轮流分配，各折大小最多相差 1
+        let mut fold = 0;
+        for indices in self.shuffled_classes(seed) {
+            for index in indices {
+                folds[fold].push(index);
+                fold = (fold + 1) % k;
+            }
+        }
+        KFold { dataset: self, folds, next: 0 }
+    }
+
+    // 按类别分组，每组内部打乱
+    fn shuffled_classes(&self, seed: u64) -> Vec<Vec<usize>> {
+        let mut classes: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (index, label) in self.labels().into_iter().enumerate() {
+            classes.entry(label).or_default().push(index);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        classes.into_values().map(|mut indices| {
+            indices.shuffle(&mut rng);
+            indices
+        }).collect()
+    }
+}
+
+fn check_fraction(fraction: f64) {
+    assert!((0.0..=1.0).contains(&fraction), "test fraction must be in [0, 1], found {}", fraction);
+}
+
+fn check_folds(k: usize, len: usize) {
+    assert!(k >= 2 && k <= len, "need 2 <= k <= {} folds, found {}", len, k);
+}
+
+/// 依次产生每一折的 (训练集, 验证集)
+#[derive(Debug)]
+pub struct KFold<'a, T: Float = f64> {
+    dataset: &'a Dataset<T>,
+    folds: Vec<Vec<usize>>,
+    next: usize,
+}
+
+impl<'a, T: Float> Iterator for KFold<'a, T> {
+    type Item = (Dataset<T>, Dataset<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let validation = self.folds.get(self.next)?;
+        let train: Vec<usize> = self.folds.iter().enumerate()
+            .filter(|&(fold, _)| fold != self.next)
+            .flat_map(|(_, indices)| indices.iter().copied())
+            .collect();
+        self.next += 1;
+        Some((self.dataset.subset(&train), self.dataset.subset(validation)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.folds.len() - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Float> ExactSizeIterator for KFold<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::one_hot;
+
+    // 30 个样本，类别 0/1/2 各 20/6/4 个，输入是样本编号
+    fn dataset() -> Dataset {
+        Dataset::new((0..30).map(|i| (vec![i as f64], one_hot(if i < 20 { 0 } else if i < 26 { 1 } else { 2 }, 3))).collect())
+    }
+
+    fn counts(dataset: &Dataset) -> Vec<usize> {
+        (0..3).map(|c| dataset.labels().iter().filter(|&&l| l == c).count()).collect()
+    }
+
+    fn ids(dataset: &Dataset) -> Vec<usize> {
+        dataset.samples().iter().map(|(x, _)| x[0] as usize).collect()
+    }
+
+    #[test]
+    fn test_splits() {
+        let data = dataset();
+        let (train, test) = data.train_test_split(0.2, 1);
+        assert_eq!((train.len(), test.len()), (24, 6));
+        let mut all = [ids(&train), ids(&test)].concat();
+        all.sort();
+        assert_eq!(all, (0..30).collect::<Vec<_>>());
+        assert_eq!(data.train_test_split(0.2, 1), (train, test));
+
+        let (train, test) = data.stratified_split(0.5, 3);
+        assert_eq!(counts(&train), [10, 3, 2]);
+        assert_eq!(counts(&test), [10, 3, 2]);
+    }
+
+    #[test]
+    fn test_k_fold() {
+        let data = dataset();
+        for folds in [data.k_fold(4, 5), data.stratified_k_fold(4, 5)] {
+            assert_eq!(folds.len(), 4);
+            let mut validated = Vec::new();
+            for (train, validation) in folds {
+                assert_eq!(train.len() + validation.len(), 30);
+                assert!(ids(&train).iter().all(|id| !ids(&validation).contains(id)));
+                validated.extend(ids(&validation));
+            }
+            validated.sort();
+            assert_eq!(validated, (0..30).collect::<Vec<_>>());
+        }
+        // 分层时每一折都有每个类别
+        for (_, validation) in data.stratified_k_fold(4, 5) {
+            assert!(counts(&validation).iter().all(|&c| c >= 1));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::layer::Shape;
+
+/// [`synthetic_shapes`] 的图像大小
+pub const SHAPES_SIZE: usize = 12;
+/// [`synthetic_shapes`] 的类别：横线、竖线、正方形边框
+pub const SHAPES_CLASSES: usize = 3;
+
+/// [`synthetic_shapes`] 中每张图像的形状，1 x 12 x 12
+pub fn shapes_image_shape() -> Shape {
+    Shape::image(1, SHAPES_SIZE, SHAPES_SIZE)
+}
+
+/// 生成一个小的合成图像分类数据集，不需要下载。
+///
+/// 每张图像是 1 x 12 x 12 的灰度图，背景是 [0, 0.2) 的噪声，上面在随机位置画一条横线、一条竖线
+/// 或一个正方形边框，长度也是随机的。目标是 one-hot 编码的类别，三个类别轮流出现。
+pub fn synthetic_shapes(samples: usize, seed: u64) -> Vec<(Vec<f64>, Vec<f64>)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..samples).map(|i| {
+        let class = i % SHAPES_CLASSES;
+        let mut image: Vec<f64> = (0..SHAPES_SIZE * SHAPES_SIZE).map(|_| rng.gen_range(0.0..0.2)).collect();
+        let len = rng.gen_range(5..=8);
+        let (y, x) = (rng.gen_range(0..=SHAPES_SIZE - len), rng.gen_range(0..=SHAPES_SIZE - len));
+        let mut draw = |y: usize, x: usize| image[y * SHAPES_SIZE + x] = rng.gen_range(0.8..1.0);
+        match class {
+            0 => (0..len).for_each(|d| draw(y + len / 2, x + d)),
+            1 => (0..len).for_each(|d| draw(y + d, x + len / 2)),
+            _ => (0..len).for_each(|d| {
+                draw(y, x + d);
+                draw(y + len - 1, x + d);
+                draw(y + d, x);
+                draw(y + d, x + len - 1);
+            }),
+        }
+        let mut target = vec![0.0; SHAPES_CLASSES];
+        target[class] = 1.0;
+        (image, target)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_shapes() {
+        let data = synthetic_shapes(30, 1);
+        assert_eq!(data, synthetic_shapes(30, 1));
+        for (i, (image, target)) in data.iter().enumerate() {
+            assert_eq!(image.len(), shapes_image_shape().size());
+            assert!(image.iter().all(|&v| (0.0..1.0).contains(&v)));
+            assert_eq!(target.iter().position(|&t| t == 1.0), Some(i % SHAPES_CLASSES));
+            // 至少画了 5 个亮点
+            assert!(image.iter().filter(|&&v| v >= 0.8).count() >= 5);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// [使用Rust从零构建神经网络：性能与精度的完美结合](https://mp.weixin.qq.com/s/wnmdbF9hYFq55veusS7Fdg)
+use rand::Rng;
+
+use crate::activation::Activation;
+use crate::autodiff::Var;
+use crate::float::Float;
+use crate::init::Initializer;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+use crate::regularizer::Regularizer;
+
+/// 全连接层：y = f(x W^T + b)
+#[derive(Debug)]
+pub struct Dense<T: Float = f64> {
+    // output_size x input_size，每行对应一个神经元
+    weights: Matrix<T>,
+    biases: Vec<T>,
+    activation: Activation,
+    regularizer: Regularizer,
+    // 前向传播的缓存（每行一个样本），反向传播时使用
+    input: Matrix<T>,
+    pre_activation: Matrix<T>,
+    output: Matrix<T>,
+    // 累积的梯度，调用 zero_grad 清零
+    weight_grads: Matrix<T>,
+    bias_grads: Vec<T>,
+}
+
+impl<T: Float> Dense<T> {
+    pub fn new<R: Rng + ?Sized>(
+        input_size: usize,
+        output_size: usize,
+        activation: Activation,
+        weight_init: Initializer,
+        bias_init: Initializer,
+        rng: &mut R,
+    ) -> Dense<T> {
+        // 按顺序采样，同样的种子得到同样的参数
+        let weights = Matrix::from_fn(output_size, input_size, |_, _| T::from_f64(weight_init.sample(input_size, output_size, rng)));
+
+        let biases = (0..output_size).map(|_| T::from_f64(bias_init.sample(input_size, output_size, rng))).collect();
+
+        Self::from_parts(weights, biases, activation)
+    }
+
+    /// 用已有的参数构造，`weights` 的行数必须等于 `biases` 的长度
+    pub fn from_parts(weights: Matrix<T>, biases: Vec<T>, activation: Activation) -> Dense<T> {
+        assert_eq!(weights.rows(), biases.len(), "one bias per output neuron is required");
+        let weight_grads = Matrix::zeros(weights.rows(), weights.cols());
+        let bias_grads = vec![T::zero(); biases.len()];
+        Dense {
+            weights,
+            biases,
+            activation,
+            regularizer: Regularizer::default(),
+            input: Matrix::zeros(0, 0),
+            pre_activation: Matrix::zeros(0, 0),
+            output: Matrix::zeros(0, 0),
+            weight_grads,
+            bias_grads,
+        }
+    }
+
+    /// 权重的 L1/L2 惩罚，梯度在反向传播时加到权重梯度上
+    pub fn with_regularizer(mut self, regularizer: Regularizer) -> Dense<T> {
+        self.regularizer = regularizer;
+        self
+    }
+
+    /// output_size x input_size
+    pub fn weights(&self) -> &Matrix<T> {
+        &self.weights
+    }
+
+    pub fn biases(&self) -> &[T] {
+        &self.biases
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.activation
+    }
+
+    pub fn regularizer(&self) -> Regularizer {
+        self.regularizer
+    }
+
+    /// Z = X * W^T + b
+    fn pre_activate(&self, input: &Matrix<T>) -> Matrix<T> {
+        let mut z = input.matmul(&self.weights.transpose());
+        z.add_row_vector(&self.biases);
+        z
+    }
+
+    /// 逐样本（逐行）求激活输出，softmax 也是按样本归一化
+    fn activate(&self, z: &Matrix<T>) -> Matrix<T> {
+        let mut output = z.clone();
+        for row in 0..z.rows() {
+            output.row_mut(row).copy_from_slice(&self.activation.forward(z.row(row)));
+        }
+        output
+    }
+
+    /// 已知损失对激活前值 z 的梯度时的反向传播
+    fn backward_pre_activation(&mut self, delta: &Matrix<T>) -> Matrix<T> {
+        // dW += dZ^T * X，db += Σ dZ，dX = dZ * W
+        self.weight_grads.add_assign(&delta.transpose().matmul(&self.input));
+        // 梯度对批次求和，惩罚项也按样本数累加，除以批大小后正好是一份
+        self.regularizer.add_gradient(self.weights.as_slice(), self.weight_grads.as_mut_slice(), delta.rows() as f64);
+        self.bias_grads.iter_mut().zip(delta.sum_rows()).for_each(|(g, d)| *g += d);
+        delta.matmul(&self.weights)
+    }
+}
+
+impl<T: Float> Layer<T> for Dense<T> {
+    fn input_shape(&self) -> Shape {
+        Shape::vector(self.weights.cols())
+    }
+
+    fn output_shape(&self) -> Shape {
+        Shape::vector(self.biases.len())
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        self.input = input.clone();
+        self.pre_activation = self.pre_activate(input);
+        self.output = self.activate(&self.pre_activation);
+        self.output.clone()
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        self.activate(&self.pre_activate(input))
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        // 链式法则：dL/dz = J_f(z)^T * dL/dy，逐样本计算
+        let mut delta = Matrix::zeros(output_grad.rows(), output_grad.cols());
+        for row in 0..output_grad.rows() {
+            let d = self.activation.backward(self.pre_activation.row(row), self.output.row(row), output_grad.row(row));
+            delta.row_mut(row).copy_from_slice(&d);
+        }
+        self.backward_pre_activation(&delta)
+    }
+
+    fn fused_backward(&mut self, delta: &mut dyn FnMut(Activation) -> Option<Matrix<T>>) -> Option<Matrix<T>> {
+        let delta = delta(self.activation)?;
+        Some(self.backward_pre_activation(&delta))
+    }
+
+    /// 两组参数：权重矩阵（行优先）和偏置
+    fn parameters(&self) -> Vec<&[T]> {
+        vec![self.weights.as_slice(), &self.biases]
+    }
+
+    fn gradients(&self) -> Vec<&[T]> {
+        vec![self.weight_grads.as_slice(), &self.bias_grads]
+    }
+
+    fn visit_parameters(&mut self, f: &mut dyn FnMut(&mut [T], &mut [T])) {
+        f(self.weights.as_mut_slice(), self.weight_grads.as_mut_slice());
+        f(&mut self.biases, &mut self.bias_grads);
+    }
+
+    fn penalty(&self) -> f64 {
+        self.regularizer.penalty(self.weights.as_slice())
+    }
+
+    fn forward_on_tape<'t>(&self, input: Var<'t, T>, parameters: &mut Vec<Var<'t, T>>) -> Option<Var<'t, T>> {
+        let tape = input.tape();
+        let (weights, biases) = (tape.var(self.weights.clone()), tape.var(Matrix::row_vector(&self.biases)));
+        parameters.extend([weights, biases]);
+        Some((input.matmul(weights.transpose()) + biases).activate(self.activation))
+    }
+
+    fn penalty_on_tape<'t>(&self, parameters: &[Var<'t, T>]) -> Option<Var<'t, T>> {
+        (!self.regularizer.is_none()).then(|| self.regularizer.penalty_on_tape(parameters[0]))
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        Some(LayerRecord::Dense {
+            input_size: self.weights.cols(),
+            output_size: self.biases.len(),
+            activation: self.activation,
+            regularizer: self.regularizer,
+            weights: self.weights.as_slice().iter().map(|w| w.to_f64()).collect(),
+            biases: self.biases.iter().map(|b| b.to_f64()).collect(),
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// Inverted dropout：训练时每个元素以概率 `rate` 置 0，其余的除以 1 - rate，推理时原样输出，期望不变
+#[derive(Debug)]
+pub struct Dropout<T: Float = f64> {
+    shape: Shape,
+    rate: f64,
+    training: bool,
+    rng: StdRng,
+    // 最近一次训练模式 forward 的掩码（保留的元素为 1 / (1 - rate)），推理模式下为 None
+    mask: Option<Matrix<T>>,
+}
+
+impl<T: Float> Dropout<T> {
+    /// `rate` 必须在 [0, 1) 内
+    pub fn new(shape: impl Into<Shape>, rate: f64) -> Dropout<T> {
+        Self::with_rng(shape, rate, StdRng::from_entropy())
+    }
+
+    /// 用给定的随机数生成器生成掩码，结果可复现
+    pub fn with_rng(shape: impl Into<Shape>, rate: f64, rng: StdRng) -> Dropout<T> {
+        assert!((0.0..1.0).contains(&rate), "dropout rate must be in [0, 1), got {}", rate);
+        Dropout { shape: shape.into(), rate, training: true, rng, mask: None }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+impl<T: Float> Layer<T> for Dropout<T> {
+    fn input_shape(&self) -> Shape {
+        self.shape.clone()
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.shape.clone()
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        if !self.training || self.rate == 0.0 {
+            self.mask = None;
+            return input.clone();
+        }
+        let keep = T::from_f64(1.0 / (1.0 - self.rate));
+        let mask = Matrix::from_fn(input.rows(), input.cols(), |_, _| {
+            if self.rng.gen::<f64>() < self.rate { T::zero() } else { keep }
+        });
+        let output = input.zip_map(&mask, |x, m| x * m);
+        self.mask = Some(mask);
+        output
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        input.clone()
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        match &self.mask {
+            Some(mask) => output_grad.zip_map(mask, |g, m| g * m),
+            None => output_grad.clone(),
+        }
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        Some(LayerRecord::Dropout { shape: self.shape.clone(), rate: self.rate })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_train_and_eval() {
+        let mut dropout = Dropout::<f64>::with_rng(1000, 0.25, StdRng::seed_from_u64(3));
+        let input = Matrix::from_fn(2, 1000, |_, _| 1.0);
+
+        let output = dropout.forward(&input);
+        let dropped = output.as_slice().iter().filter(|&&x| x == 0.0).count();
+        assert!((400..600).contains(&dropped), "{}", dropped);
+        assert!(output.as_slice().iter().all(|&x| x == 0.0 || (x - 4.0 / 3.0).abs() < 1e-12));
+        // 梯度只流过保留的元素
+        let grad = dropout.backward(&input);
+        assert_eq!(grad, output);
+
+        dropout.set_training(false);
+        assert_eq!(dropout.forward(&input), input);
+        assert_eq!(dropout.backward(&input), input);
+        assert_eq!(dropout.predict(&input), input);
+    }
+}
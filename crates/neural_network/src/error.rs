@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+/// 前向、反向传播和训练中的错误
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum NnError {
+    /// 向量长度或矩阵列数不符，`what` 说明是哪个量
+    #[error("shape mismatch for {what}: expected {expected}, found {actual}")]
+    ShapeMismatch { what: &'static str, expected: usize, actual: usize },
+    #[error("the network has no layers")]
+    EmptyNetwork,
+    /// 计算结果出现 NaN，通常是学习率过大导致发散
+    #[error("NaN detected in {0}")]
+    NaN(&'static str),
+    /// 层或损失函数没有实现自动微分，内容说明是哪一个
+    #[error("{0} does not support automatic differentiation")]
+    NoAutodiff(String),
+    /// 多线程训练需要复制网络，该层不支持 `replicate`
+    #[error("layer {0} cannot be replicated for multi-threaded training")]
+    NotReplicable(usize),
+    /// 分类指标要求单个输出的目标是 0 或 1，内容是四舍五入后的目标值
+    #[error("classification metrics need 0/1 or one-hot targets, found label {0}")]
+    InvalidLabel(usize),
+    /// 训练配置的取值不合法，内容说明是哪一项
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(&'static str),
+}
+
+/// 长度不等时返回 `ShapeMismatch`
+pub(crate) fn check_len(what: &'static str, expected: usize, actual: usize) -> Result<(), NnError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(NnError::ShapeMismatch { what, expected, actual })
+    }
+}
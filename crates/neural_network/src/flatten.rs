@@ -0,0 +1,49 @@
+use crate::autodiff::Var;
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// 把任意形状展平成向量，接在卷积或池化层之后、全连接层之前。
+/// 样本本来就按行优先展开存放，只改变形状，数据原样传递
+#[derive(Debug)]
+pub struct Flatten<T: Float = f64> {
+    input: Shape,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Float> Flatten<T> {
+    pub fn new(input: impl Into<Shape>) -> Flatten<T> {
+        Flatten { input: input.into(), _marker: std::marker::PhantomData }
+    }
+}
+
+impl<T: Float> Layer<T> for Flatten<T> {
+    fn input_shape(&self) -> Shape {
+        self.input.clone()
+    }
+
+    fn output_shape(&self) -> Shape {
+        Shape::vector(self.input.size())
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        input.clone()
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        input.clone()
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        output_grad.clone()
+    }
+
+    fn forward_on_tape<'t>(&self, input: Var<'t, T>, _parameters: &mut Vec<Var<'t, T>>) -> Option<Var<'t, T>> {
+        Some(input)
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        Some(LayerRecord::Flatten { input_shape: self.input.clone() })
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// 网络使用的浮点类型，为 f32 和 f64 实现。
+///
+/// 超参数（学习率、初始化分布等）统一用 f64 表示，进入计算时通过 `from_f64` 转换。
+pub trait Float:
+    Copy
+    + Debug
+    + Display
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+    + Serialize
+    + DeserializeOwned
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    /// 机器精度，1 与下一个可表示数之差
+    fn epsilon() -> Self;
+    fn infinity() -> Self;
+    fn neg_infinity() -> Self;
+    fn exp(self) -> Self;
+    fn exp_m1(self) -> Self;
+    fn ln(self) -> Self;
+    fn tanh(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn clamp(self, min: Self, max: Self) -> Self;
+    fn is_finite(self) -> bool;
+    fn is_nan(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($t:ident) => {
+        impl Float for $t {
+            fn zero() -> Self {
+                0.0
+            }
+
+            fn one() -> Self {
+                1.0
+            }
+
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn epsilon() -> Self {
+                $t::EPSILON
+            }
+
+            fn infinity() -> Self {
+                $t::INFINITY
+            }
+
+            fn neg_infinity() -> Self {
+                $t::NEG_INFINITY
+            }
+
+            fn exp(self) -> Self {
+                $t::exp(self)
+            }
+
+            fn exp_m1(self) -> Self {
+                $t::exp_m1(self)
+            }
+
+            fn ln(self) -> Self {
+                $t::ln(self)
+            }
+
+            fn tanh(self) -> Self {
+                $t::tanh(self)
+            }
+
+            fn sqrt(self) -> Self {
+                $t::sqrt(self)
+            }
+
+            fn abs(self) -> Self {
+                $t::abs(self)
+            }
+
+            fn powi(self, n: i32) -> Self {
+                $t::powi(self, n)
+            }
+
+            fn max(self, other: Self) -> Self {
+                $t::max(self, other)
+            }
+
+            fn min(self, other: Self) -> Self {
+                $t::min(self, other)
+            }
+
+            fn clamp(self, min: Self, max: Self) -> Self {
+                $t::clamp(self, min, max)
+            }
+
+            fn is_finite(self) -> bool {
+                $t::is_finite(self)
+            }
+
+            fn is_nan(self) -> bool {
+                $t::is_nan(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Activation;
+    use crate::loss::BinaryCrossEntropy;
+    use crate::neural_network::NeuralNetwork;
+    use crate::optimizer::Adam;
+    use crate::trainer::Trainer;
+
+    type Samples<T> = Vec<(Vec<T>, Vec<T>)>;
+
+    /// 用给定精度训练 XOR，返回训练后的网络和数据
+    fn train_xor<T: Float>() -> (NeuralNetwork<T>, Samples<T>) {
+        let data: Samples<T> = [([0.0, 0.0], 0.0), ([0.0, 1.0], 1.0), ([1.0, 0.0], 1.0), ([1.0, 1.0], 0.0)]
+            .iter()
+            .map(|&(x, y)| (x.iter().map(|&v| T::from_f64(v)).collect(), vec![T::from_f64(y)]))
+            .collect();
+        let mut network = NeuralNetwork::<T>::seeded(&[2, 8, 1], &[Activation::Tanh, Activation::Sigmoid], 3)
+            .with_loss(BinaryCrossEntropy);
+        Trainer::new(Adam::new(0.05)).with_batch_size(4).with_epochs(500).fit(&mut network, &data).unwrap();
+        (network, data)
+    }
+
+    #[test]
+    fn test_xor_in_both_precisions() {
+        let (single, data) = train_xor::<f32>();
+        for (input, target) in &data {
+            assert!((single.predict(input).unwrap()[0] - target[0]).abs() < 0.1, "f32 {:?}", input);
+        }
+        let (double, data) = train_xor::<f64>();
+        for (input, target) in &data {
+            assert!((double.predict(input).unwrap()[0] - target[0]).abs() < 0.1, "f64 {:?}", input);
+        }
+        // 同样的种子和超参数，两种精度的结果应该非常接近
+        for (input, _) in &data {
+            let input32: Vec<f32> = input.iter().map(|&x| x as f32).collect();
+            assert!((single.predict(&input32).unwrap()[0] as f64 - double.predict(input).unwrap()[0]).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_f32_model_round_trips_through_f64_format() {
+        let (network, data) = train_xor::<f32>();
+        let loaded = NeuralNetwork::<f32>::from_bytes(&network.to_bytes().unwrap()).unwrap();
+        for (input, _) in &data {
+            assert_eq!(network.predict(input).unwrap(), loaded.predict(input).unwrap());
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use crate::error::NnError;
+use crate::layer::Layer;
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+
+/// 梯度检验的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientCheck {
+    /// 检验过的参数个数
+    pub checked: usize,
+    pub max_abs_error: f64,
+    /// 相对误差 |a - n| / max(|a| + |n|, 1e-8) 的最大值
+    pub max_relative_error: f64,
+}
+
+impl GradientCheck {
+    pub fn passed(&self, tolerance: f64) -> bool {
+        self.max_relative_error <= tolerance
+    }
+}
+
+/// 用中心差分 (L(θ+ε) - L(θ-ε)) / 2ε 逐个参数估计数值梯度，并与反向传播得到的解析梯度比较。
+///
+/// 损失函数使用网络配置的 [`Loss`](crate::loss::Loss) 加上各层的正则化惩罚。
+/// 检验在推理模式下进行（不做 dropout，批归一化使用滑动统计量），结束后恢复原来的模式。
+/// 会清空网络已累积的梯度，参数在检验结束后恢复原值。
+pub fn gradient_check(
+    network: &mut NeuralNetwork,
+    input: &[f64],
+    target: &[f64],
+    epsilon: f64,
+) -> Result<GradientCheck, NnError> {
+    let training = network.is_training();
+    network.set_training(false);
+    let result = check(network, input, target, epsilon, false);
+    network.set_training(training);
+    result
+}
+
+/// 同 [`gradient_check`]，但解析梯度由自动微分（[`NeuralNetwork::compute_gradients_on_tape`]）给出，
+/// 用来确认层和损失函数在求导带上的表达式与前向计算一致
+pub fn gradient_check_on_tape(
+    network: &mut NeuralNetwork,
+    input: &[f64],
+    target: &[f64],
+    epsilon: f64,
+) -> Result<GradientCheck, NnError> {
+    let training = network.is_training();
+    network.set_training(false);
+    let result = check(network, input, target, epsilon, true);
+    network.set_training(training);
+    result
+}
+
+fn check(network: &mut NeuralNetwork, input: &[f64], target: &[f64], epsilon: f64, on_tape: bool) -> Result<GradientCheck, NnError> {
+    network.zero_grad();
+    if on_tape {
+        network.compute_gradients_on_tape(&Matrix::row_vector(input), &Matrix::row_vector(target))?;
+    } else {
+        network.forward(input)?;
+        network.compute_gradients(target)?;
+    }
+
+    let loss = |network: &NeuralNetwork| -> Result<f64, NnError> {
+        Ok(network.loss().checked_value(&network.predict(input)?, target)? + network.penalty())
+    };
+    let mut result = GradientCheck { checked: 0, max_abs_error: 0.0, max_relative_error: 0.0 };
+    for layer_index in 0..network.layers().len() {
+        let layer = &network.layers()[layer_index];
+        let analytic_grads: Vec<Vec<f64>> = layer.gradients().iter().map(|group| group.to_vec()).collect();
+        for (group, grads) in analytic_grads.iter().enumerate() {
+            for (index, &analytic) in grads.iter().enumerate() {
+                let original = network.layers()[layer_index].parameters()[group][index];
+
+                set_param(network.layers_mut()[layer_index].as_mut(), group, index, original + epsilon);
+                let plus = loss(network)?;
+                set_param(network.layers_mut()[layer_index].as_mut(), group, index, original - epsilon);
+                let minus = loss(network)?;
+                set_param(network.layers_mut()[layer_index].as_mut(), group, index, original);
+
+                let numeric = (plus - minus) / (2.0 * epsilon);
+                let abs_error = (analytic - numeric).abs();
+                let relative_error = abs_error / (analytic.abs() + numeric.abs()).max(1e-8);
+                result.checked += 1;
+                result.max_abs_error = result.max_abs_error.max(abs_error);
+                result.max_relative_error = result.max_relative_error.max(relative_error);
+            }
+        }
+    }
+    network.zero_grad();
+    Ok(result)
+}
+
+// 修改第 group 组的第 index 个参数
+fn set_param(layer: &mut dyn Layer<f64>, group: usize, index: usize, value: f64) {
+    let mut current = 0;
+    layer.visit_parameters(&mut |params, _| {
+        if current == group {
+            params[index] = value;
+        }
+        current += 1;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Activation;
+    use crate::layer::mean_squared_error;
+    use crate::conv::Conv2DSpec;
+    use crate::layer::Shape;
+    use crate::loss::*;
+    use crate::neural_network::LayerSpec;
+    use crate::recurrent::RecurrentSpec;
+    use crate::regularizer::Regularizer;
+    use crate::sequential::Sequential;
+
+    #[test]
+    fn test_gradients_match_numeric() {
+        let mut network = NeuralNetwork::seeded(&[3, 5, 4, 2], &[Activation::Sigmoid; 3], 1);
+        let check = gradient_check(&mut network, &[0.5, -1.2, 0.3], &[0.2, 0.9], 1e-5).unwrap();
+        assert_eq!(check.checked, (3 * 5 + 5) + (5 * 4 + 4) + (4 * 2 + 2));
+        assert!(check.passed(1e-6), "{:?}", check);
+    }
+
+    #[test]
+    fn test_mixed_activations() {
+        let hidden = [Activation::Tanh, Activation::LeakyReLU(0.1), Activation::ELU(1.0), Activation::GELU, Activation::Identity];
+        for activation in hidden {
+            let mut network = NeuralNetwork::seeded(&[3, 6, 2], &[activation, Activation::Sigmoid], 1);
+            let check = gradient_check(&mut network, &[0.7, -0.4, 1.5], &[1.0, 0.0], 1e-5).unwrap();
+            assert!(check.passed(1e-5), "{:?}: {:?}", activation, check);
+        }
+    }
+
+    #[test]
+    fn test_losses_with_output_activations() {
+        let input = [0.1, 0.8, -0.5, 0.3];
+        let target = [0.0, 1.0, 0.0];
+        let cases: Vec<(Activation, Box<dyn Loss>)> = vec![
+            // 合并求导
+            (Activation::Softmax, Box::new(CategoricalCrossEntropy)),
+            (Activation::Sigmoid, Box::new(BinaryCrossEntropy)),
+            // 普通链式法则
+            (Activation::Sigmoid, Box::new(CategoricalCrossEntropy)),
+            (Activation::Softmax, Box::new(MeanSquaredError)),
+            (Activation::Identity, Box::new(Huber { delta: 0.5 })),
+            (Activation::Tanh, Box::new(MeanAbsoluteError)),
+        ];
+        for (output_activation, loss) in cases {
+            let mut network = NeuralNetwork::seeded(&[4, 5, 3], &[Activation::Tanh, output_activation], 1)
+                .with_loss(loss);
+            let check = gradient_check(&mut network, &input, &target, 1e-5).unwrap();
+            assert!(check.passed(1e-5), "{:?} + {:?}: {:?}", output_activation, network.loss(), check);
+        }
+    }
+
+    #[test]
+    fn test_regularization_layers() {
+        let mut network = Sequential::<f64>::seeded(4, 11)
+            .dense_with(LayerSpec::new(6, Activation::Tanh).with_regularizer(Regularizer::l1_l2(0.01, 0.05)))
+            .batch_norm()
+            .dropout(0.5)
+            .dense_with(LayerSpec::new(3, Activation::Softmax).with_regularizer(Regularizer::l2(0.1)))
+            .with_loss(CategoricalCrossEntropy)
+            .build();
+        // 先训练几步，让滑动统计量偏离初始值
+        for _ in 0..5 {
+            network.forward_batch(&Matrix::from_rows(&[[0.3, -0.2, 1.1, 0.5], [1.0, 0.4, -0.6, 0.0]])).unwrap();
+        }
+        let check = gradient_check(&mut network, &[0.1, 0.8, -0.5, 0.3], &[0.0, 1.0, 0.0], 1e-5).unwrap();
+        assert_eq!(check.checked, (4 * 6 + 6) + 2 * 6 + (6 * 3 + 3));
+        assert!(check.passed(1e-5), "{:?}", check);
+        assert!(network.is_training());
+    }
+
+    #[test]
+    fn test_convolutional_layers() {
+        let pooled = Sequential::<f64>::seeded(Shape::image(2, 6, 6), 21)
+            .conv2d_with(Conv2DSpec::new(3, 3, Activation::Tanh).with_padding(1))
+            .max_pool(2)
+            .conv2d(2, 2, Activation::Identity)
+            .avg_pool(2)
+            .flatten()
+            .dense(3, Activation::Softmax)
+            .with_loss(CategoricalCrossEntropy)
+            .build();
+        let strided = Sequential::<f64>::seeded(Shape::image(2, 6, 6), 22)
+            .conv2d_with(Conv2DSpec::new(3, 3, Activation::Sigmoid).with_stride(2).with_padding(1))
+            .flatten()
+            .dense(3, Activation::Softmax)
+            .with_loss(CategoricalCrossEntropy)
+            .build();
+        let input: Vec<f64> = (0..72).map(|i| (i as f64 * 0.7).sin()).collect();
+        for mut network in [pooled, strided] {
+            let check = gradient_check(&mut network, &input, &[0.0, 0.0, 1.0], 1e-5).unwrap();
+            assert!(check.passed(1e-5), "{:?}", check);
+        }
+    }
+
+    #[test]
+    fn test_recurrent_layers() {
+        let input: Vec<f64> = (0..12).map(|i| (i as f64 * 0.9).cos()).collect();
+        for spec in [RecurrentSpec::rnn(4), RecurrentSpec::lstm(4), RecurrentSpec::gru(4)] {
+            // 多对一
+            let mut many_to_one = Sequential::<f64>::seeded(Shape::sequence(6, 2), 31)
+                .recurrent(spec)
+                .dense(2, Activation::Identity)
+                .build();
+            let check = gradient_check(&mut many_to_one, &input, &[0.5, -0.3], 1e-5).unwrap();
+            assert!(check.passed(1e-5), "{:?} many to one: {:?}", spec.kind, check);
+
+            // 两层堆叠的多对多，每个时间步一个 softmax 输出
+            let mut many_to_many = Sequential::<f64>::seeded(Shape::sequence(6, 2), 32)
+                .recurrent(spec.with_return_sequences(true))
+                .recurrent(RecurrentSpec::new(spec.kind, 3).with_return_sequences(true))
+                .time_distributed_dense(2, Activation::Softmax)
+                .with_loss(CategoricalCrossEntropy)
+                .build();
+            assert_eq!(many_to_many.output_size(), 12);
+            let target: Vec<f64> = (0..12).map(|i| if i % 4 < 2 { (i % 2) as f64 } else { ((i + 1) % 2) as f64 }).collect();
+            let check = gradient_check(&mut many_to_many, &input, &target, 1e-5).unwrap();
+            assert!(check.passed(1e-5), "{:?} many to many: {:?}", spec.kind, check);
+        }
+    }
+
+    #[test]
+    fn test_autodiff_gradients() {
+        let losses: Vec<(Activation, Box<dyn Loss>)> = vec![
+            (Activation::Softmax, Box::new(CategoricalCrossEntropy)),
+            (Activation::Sigmoid, Box::new(BinaryCrossEntropy)),
+            (Activation::Softmax, Box::new(MeanSquaredError)),
+            (Activation::Identity, Box::new(Huber { delta: 0.5 })),
+            (Activation::Tanh, Box::new(MeanAbsoluteError)),
+        ];
+        let inputs = Matrix::from_rows(&[[0.1, 0.8, -0.5, 0.3], [1.2, -0.4, 0.0, 0.9], [-0.7, 0.2, 0.6, -1.1]]);
+        let targets = Matrix::from_rows(&[[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+        for (output_activation, loss) in losses {
+            let mut network = Sequential::<f64>::seeded(4, 41)
+                .dense_with(LayerSpec::new(5, Activation::GELU).with_regularizer(Regularizer::l1_l2(0.01, 0.05)))
+                .dense(3, output_activation)
+                .with_loss(loss)
+                .build();
+            let check = gradient_check_on_tape(&mut network, inputs.row(0), targets.row(0), 1e-5).unwrap();
+            assert!(check.passed(1e-5), "{:?}: {:?}", network.loss(), check);
+
+            // 批次上与手写的反向传播一致
+            network.zero_grad();
+            network.forward_batch(&inputs).unwrap();
+            let loss = network.compute_gradients_batch(&targets).unwrap();
+            let expected: Vec<Vec<f64>> = network.layers().iter().flat_map(|l| l.gradients()).map(|g| g.to_vec()).collect();
+            network.zero_grad();
+            assert!((network.compute_gradients_on_tape(&inputs, &targets).unwrap() - loss).abs() < 1e-12);
+            let actual = network.layers().iter().flat_map(|l| l.gradients());
+            for (expected, actual) in expected.iter().zip(actual) {
+                assert!(expected.iter().zip(actual).all(|(e, a)| (e - a).abs() < 1e-10), "{:?}", network.loss());
+            }
+        }
+
+        let mut conv = Sequential::<f64>::seeded(Shape::image(1, 3, 3), 1).conv2d(1, 2, Activation::ReLU).flatten().build();
+        let result = conv.compute_gradients_on_tape(&Matrix::zeros(1, 9), &Matrix::zeros(1, 4));
+        assert_eq!(result, Err(NnError::NoAutodiff("layer 0".to_string())));
+    }
+
+    #[test]
+    fn test_xor_loss_decreases() {
+        let data = [
+            ([0.0, 0.0], [0.0]),
+            ([0.0, 1.0], [1.0]),
+            ([1.0, 0.0], [1.0]),
+            ([1.0, 1.0], [0.0]),
+        ];
+        let mut network = NeuralNetwork::seeded(&[2, 4, 1], &[Activation::Sigmoid; 2], 1);
+        let total_loss = |network: &NeuralNetwork| -> f64 {
+            data.iter().map(|(x, y)| mean_squared_error(&network.predict(x).unwrap(), y).unwrap()).sum()
+        };
+
+        let initial = total_loss(&network);
+        for _ in 0..2000 {
+            for (input, target) in &data {
+                network.forward(input).unwrap();
+                network.backward(target, 0.5).unwrap();
+            }
+        }
+        assert!(total_loss(&network) < initial);
+    }
+}
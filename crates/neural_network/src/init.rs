@@ -0,0 +1,118 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::activation::Activation;
+
+/// 参数初始化方式，`fan_in` / `fan_out` 是该层的输入、输出个数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Initializer {
+    /// [low, high) 上的均匀分布
+    Uniform { low: f64, high: f64 },
+    Normal { mean: f64, std_dev: f64 },
+    /// Glorot 均匀分布：±sqrt(6 / (fan_in + fan_out))，适合 sigmoid / tanh
+    XavierUniform,
+    /// Glorot 正态分布：标准差 sqrt(2 / (fan_in + fan_out))
+    XavierNormal,
+    /// Kaiming 均匀分布：±sqrt(6 / fan_in)，适合 ReLU 系列
+    HeUniform,
+    /// Kaiming 正态分布：标准差 sqrt(2 / fan_in)
+    HeNormal,
+    Zeros,
+    Constant(f64),
+}
+
+impl Initializer {
+    /// 按激活函数选择权重的初始化方式：ReLU 系列用 He，其余用 Xavier
+    pub fn for_activation(activation: Activation) -> Initializer {
+        match activation {
+            Activation::ReLU | Activation::LeakyReLU(_) | Activation::ELU(_) | Activation::GELU => Initializer::HeNormal,
+            _ => Initializer::XavierUniform,
+        }
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, fan_in: usize, fan_out: usize, rng: &mut R) -> f64 {
+        let (fan_in, fan_out) = (fan_in.max(1) as f64, fan_out.max(1) as f64);
+        match *self {
+            Initializer::Uniform { low, high } => rng.gen_range(low..high),
+            Initializer::Normal { mean, std_dev } => mean + std_dev * standard_normal(rng),
+            Initializer::XavierUniform => {
+                let limit = (6.0 / (fan_in + fan_out)).sqrt();
+                rng.gen_range(-limit..limit)
+            }
+            Initializer::XavierNormal => (2.0 / (fan_in + fan_out)).sqrt() * standard_normal(rng),
+            Initializer::HeUniform => {
+                let limit = (6.0 / fan_in).sqrt();
+                rng.gen_range(-limit..limit)
+            }
+            Initializer::HeNormal => (2.0 / fan_in).sqrt() * standard_normal(rng),
+            Initializer::Zeros => 0.0,
+            Initializer::Constant(value) => value,
+        }
+    }
+}
+
+/// Box-Muller 变换生成标准正态分布
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // u1 取 (0, 1]，避免 ln(0)
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::neural_network::{LayerSpec, NeuralNetwork};
+
+    fn mean_and_variance(init: Initializer, fan_in: usize, fan_out: usize) -> (f64, f64) {
+        let mut rng = StdRng::seed_from_u64(1);
+        let samples: Vec<f64> = (0..20_000).map(|_| init.sample(fan_in, fan_out, &mut rng)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_variance() {
+        let cases: [(Initializer, f64, f64); 5] = [
+            (Initializer::Normal { mean: 1.0, std_dev: 2.0 }, 1.0, 4.0),
+            // 均匀分布 U(-a, a) 的方差是 a^2 / 3
+            (Initializer::XavierUniform, 0.0, 2.0 / (100.0 + 50.0)),
+            (Initializer::XavierNormal, 0.0, 2.0 / (100.0 + 50.0)),
+            (Initializer::HeUniform, 0.0, 2.0 / 100.0),
+            (Initializer::HeNormal, 0.0, 2.0 / 100.0),
+        ];
+        for (init, expected_mean, expected_variance) in cases {
+            let (mean, variance) = mean_and_variance(init, 100, 50);
+            assert!((mean - expected_mean).abs() < 0.05 * expected_variance.sqrt().max(1.0), "{:?} mean {}", init, mean);
+            assert!((variance / expected_variance - 1.0).abs() < 0.05, "{:?} variance {}", init, variance);
+        }
+        assert_eq!(mean_and_variance(Initializer::Zeros, 3, 3), (0.0, 0.0));
+        assert_eq!(mean_and_variance(Initializer::Constant(0.5), 3, 3), (0.5, 0.0));
+    }
+
+    #[test]
+    fn test_same_seed_is_bit_identical() {
+        let activations = [Activation::ReLU, Activation::Tanh, Activation::Softmax];
+        let a: NeuralNetwork = NeuralNetwork::seeded(&[4, 16, 8, 3], &activations, 42);
+        let b: NeuralNetwork = NeuralNetwork::seeded(&[4, 16, 8, 3], &activations, 42);
+        let c: NeuralNetwork = NeuralNetwork::seeded(&[4, 16, 8, 3], &activations, 43);
+        assert_eq!(a.to_bytes().unwrap(), b.to_bytes().unwrap());
+        assert_ne!(a.to_bytes().unwrap(), c.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_layer_specs() {
+        let specs = [
+            LayerSpec::new(3, Activation::ReLU).with_bias_init(Initializer::Constant(0.1)),
+            LayerSpec::new(2, Activation::Identity).with_weight_init(Initializer::Zeros),
+        ];
+        let network = NeuralNetwork::from_specs(5, &specs, &mut StdRng::seed_from_u64(7));
+        // 第二层权重全 0、偏置全 0，输出恒为 0
+        assert_eq!(network.predict(&[1.0, -2.0, 3.0, 0.5, 0.0]).unwrap(), vec![0.0, 0.0]);
+        assert!(network.layers()[0].parameters()[1].iter().all(|&b| b == 0.1));
+        assert_eq!(specs[0].weight_init, Initializer::HeNormal);
+    }
+}
@@ -1,61 +1,169 @@
-// [使用Rust从零构建神经网络：性能与精度的完美结合](https://mp.weixin.qq.com/s/wnmdbF9hYFq55veusS7Fdg)
-use rand::Rng;
-// 定义神经网络单层结构
-#[derive(Debug)]
-pub(crate) struct Layer {
-    weights: Vec<Vec<f64>>,
-    biases: Vec<f64>,
-}
+use std::fmt::{self, Debug};
 
-impl Layer {
-    pub fn new(input_size: usize, output_size: usize) -> Layer {
-        let mut rng = rand::thread_rng();
+use serde::{Deserialize, Serialize};
 
-        // 用随机值初始化权重和偏置
-        let weights = (0..output_size)
-            .map(|_| (0..input_size).map(|_| rng.gen_range(-1.0..1.0)).collect())
-            .collect();
+use crate::activation::Activation;
+use crate::autodiff::Var;
+use crate::error::NnError;
+use crate::float::Float;
+use crate::loss::{Loss, MeanSquaredError};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
 
-        let biases = (0..output_size).map(|_| rng.gen_range(-1.0..1.0)).collect();
+/// 单个样本的形状，例如向量 `[n]`、序列 `[时间步 x 特征]` 或图像 `[通道 x 高 x 宽]`。
+/// 层之间传递时每个样本按行优先展开成矩阵的一行，长度为 [`Shape::size`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Shape {
+    dims: Vec<usize>,
+}
 
-        Layer { weights, biases }
+impl Shape {
+    pub fn new(dims: Vec<usize>) -> Shape {
+        Shape { dims }
     }
 
-    pub(crate) fn forward(&self, input: &[f64]) -> Vec<f64> {
-        self.weights.iter().enumerate().map(|(i, neuron_weights)| {
+    pub fn vector(len: usize) -> Shape {
+        Shape { dims: vec![len] }
+    }
 
-            let sum: f64 = neuron_weights.iter().zip(input.iter())
-                .map(|(w, i)| w * i)
-                .sum();
+    /// 时间步 x 特征的序列，按时间步展开
+    pub fn sequence(steps: usize, features: usize) -> Shape {
+        Shape { dims: vec![steps, features] }
+    }
 
-            sigmoid(sum + self.biases[i])
-        }).collect()
+    /// 二维形状返回 (时间步, 特征)
+    pub fn as_sequence(&self) -> Option<(usize, usize)> {
+        match self.dims[..] {
+            [steps, features] => Some((steps, features)),
+            _ => None,
+        }
     }
 
-    pub(crate) fn backward(&mut self, input: &[f64], error: &[f64], learning_rate: f64) -> Vec<f64> {
-        let mut input_error = vec![0.0; input.len()];
+    /// 通道 x 高 x 宽的图像，按通道、行、列的顺序展开
+    pub fn image(channels: usize, height: usize, width: usize) -> Shape {
+        Shape { dims: vec![channels, height, width] }
+    }
 
-        for (i, neuron_weights) in self.weights.iter_mut().enumerate() {
-            for (j, weight) in neuron_weights.iter_mut().enumerate() {
-                input_error[j] += *weight * error[i];
-                *weight -= learning_rate * error[i] * input[j];
-            }
-            self.biases[i] -= learning_rate * error[i];
+    /// 三维形状返回 (通道, 高, 宽)
+    pub fn as_image(&self) -> Option<(usize, usize, usize)> {
+        match self.dims[..] {
+            [channels, height, width] => Some((channels, height, width)),
+            _ => None,
         }
-        input_error
     }
 
+    pub fn dims(&self) -> &[usize] {
+        &self.dims
+    }
+
+    /// 元素总数
+    pub fn size(&self) -> usize {
+        self.dims.iter().product()
+    }
+
+    /// 元素总数，溢出时返回 `None`，用于检查来自文件等不可信来源的形状
+    pub fn checked_size(&self) -> Option<usize> {
+        self.dims.iter().try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+    }
+}
+
+impl From<usize> for Shape {
+    fn from(len: usize) -> Shape {
+        Shape::vector(len)
+    }
 }
 
-// Sigmoid 激活函数
-fn sigmoid(x: f64) -> f64 {
-    1.0 / (1.0 + (-x).exp())
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dims: Vec<String> = self.dims.iter().map(usize::to_string).collect();
+        write!(f, "[{}]", dims.join(" x "))
+    }
 }
 
-// 定义均方误差（MSE）计算函数
-pub fn mean_squared_error(predicted: &[f64], actual: &[f64]) -> f64 {
-    predicted.iter()
-        .zip(actual.iter())
-        .map(|(p, a)| (p - a).powi(2))
-        .sum::<f64>() / predicted.len() as f64
-}
\ No newline at end of file
+/// 网络中的一层。输入输出都是每行一个样本的矩阵，形状由网络在构造时检查，层内不再检查。
+///
+/// 有参数的层把参数分成若干组，`parameters`、`gradients` 和 `visit_parameters` 必须按相同顺序给出各组。
+pub trait Layer<T: Float = f64>: Debug + Send + Sync {
+    fn input_shape(&self) -> Shape;
+
+    fn output_shape(&self) -> Shape;
+
+    /// 训练时的前向传播，缓存反向传播需要的中间结果
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T>;
+
+    /// 只做推理，不修改缓存
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T>;
+
+    /// 根据损失对本层输出的梯度累积参数梯度（对批次求和），返回损失对本层输入的梯度。
+    /// 必须先调用 `forward`。
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T>;
+
+    /// 输出是 f(z) 形式的层作为输出层时可以和损失合并求导：用激活函数 f 调用 `delta`，
+    /// 得到损失对 z 的梯度后直接从 z 反向传播，返回损失对本层输入的梯度。
+    /// 本层不是这种形式或 `delta` 返回 `None`（损失不支持合并）时返回 `None`，调用方改用 `backward`
+    fn fused_backward(&mut self, _delta: &mut dyn FnMut(Activation) -> Option<Matrix<T>>) -> Option<Matrix<T>> {
+        None
+    }
+
+    fn parameters(&self) -> Vec<&[T]> {
+        Vec::new()
+    }
+
+    fn gradients(&self) -> Vec<&[T]> {
+        Vec::new()
+    }
+
+    /// 依次访问每组参数及其梯度，优化器通过它更新参数
+    fn visit_parameters(&mut self, _f: &mut dyn FnMut(&mut [T], &mut [T])) {}
+
+    fn zero_grad(&mut self) {
+        self.visit_parameters(&mut |_, grads| grads.fill(T::zero()));
+    }
+
+    /// 参数的正则化惩罚，不计入 `Loss` 的值；梯度由层自己在 `backward` 中累加
+    fn penalty(&self) -> f64 {
+        0.0
+    }
+
+    /// 在求导带上写出本层（推理模式）的前向计算：参数按 `visit_parameters` 的顺序作为叶子节点追加到
+    /// `parameters`，返回输出节点。不支持自动微分的层返回 `None`
+    fn forward_on_tape<'t>(&self, _input: Var<'t, T>, _parameters: &mut Vec<Var<'t, T>>) -> Option<Var<'t, T>> {
+        None
+    }
+
+    /// 用本层的参数节点（`forward_on_tape` 追加的部分）写出正则化惩罚，没有惩罚时返回 `None`
+    fn penalty_on_tape<'t>(&self, _parameters: &[Var<'t, T>]) -> Option<Var<'t, T>> {
+        None
+    }
+
+    /// 切换训练/推理模式，只影响 `forward`，`predict` 始终按推理模式计算
+    fn set_training(&mut self, _training: bool) {}
+
+    /// 清空循环层在 stateful 模式下保留的状态
+    fn reset_state(&mut self) {}
+
+    /// 不参与梯度下降、但训练时由 `forward` 更新的状态，例如批归一化的滑动统计量
+    fn visit_state(&mut self, _f: &mut dyn FnMut(&mut [T])) {}
+
+    /// 重设层内随机数生成器的种子（dropout），多线程训练时让每个工作副本可复现
+    fn reseed(&mut self, _seed: u64) {}
+
+    /// 结构和参数相同的副本，用于多线程训练，梯度和缓存不复制。默认经由 `to_record` 构造
+    fn replicate(&self) -> Option<Box<dyn Layer<T>>> {
+        self.to_record()?.into_layer().ok()
+    }
+
+    fn param_count(&self) -> usize {
+        self.parameters().iter().map(|group| group.len()).sum()
+    }
+
+    /// 保存模型时使用，不支持保存的层返回 `None`
+    fn to_record(&self) -> Option<LayerRecord> {
+        None
+    }
+}
+
+// 定义均方误差（MSE）计算函数，长度不一致时返回错误
+pub fn mean_squared_error<T: Float>(predicted: &[T], actual: &[T]) -> Result<T, NnError> {
+    MeanSquaredError.checked_value(predicted, actual)
+}
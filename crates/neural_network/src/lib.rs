@@ -1,2 +1,26 @@
 pub mod layer;
+pub mod dense;
+pub mod sequential;
 pub mod neural_network;
+pub mod activation;
+pub mod init;
+pub mod loss;
+pub mod optimizer;
+pub mod schedule;
+pub mod trainer;
+pub mod persist;
+pub mod gradient_check;
+pub mod autodiff;
+pub mod matrix;
+pub mod float;
+pub mod error;
+pub mod regularizer;
+pub mod dropout;
+pub mod batch_norm;
+pub mod conv;
+pub mod pooling;
+pub mod flatten;
+pub mod data;
+pub mod recurrent;
+pub mod time_distributed;
+pub mod metrics;
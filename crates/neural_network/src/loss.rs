@@ -0,0 +1,299 @@
+use std::fmt::Debug;
+
+use crate::activation::Activation;
+use crate::autodiff::Var;
+use crate::error::{check_len, NnError};
+use crate::float::Float;
+use crate::matrix::Matrix;
+
+/// 交叉熵里对概率的截断，防止 ln(0)
+const PROB_EPSILON: f64 = 1e-15;
+
+/// 概率截断的下限，f32 下 1 - 1e-15 会舍入成 1，所以不小于该类型的机器精度
+fn prob_epsilon<T: Float>() -> T {
+    T::from_f64(PROB_EPSILON).max(T::epsilon())
+}
+
+/// 样本输出个数，作为求平均的分母
+fn len<T: Float>(values: &[T]) -> T {
+    T::from_f64(values.len() as f64)
+}
+
+/// 求导带上的批次损失除以每个样本的输出个数，与 `value` 中的 mean 对应
+fn per_output<'t, T: Float>(sum: Var<'t, T>, target: &Matrix<T>) -> Var<'t, T> {
+    sum.scale(T::one() / T::from_f64(target.cols() as f64))
+}
+
+/// 损失函数
+pub trait Loss<T: Float = f64>: Debug + Send + Sync {
+    /// 单个样本的损失值，调用方保证两者长度一致，需要检查时用 [`Loss::checked_value`]
+    fn value(&self, predicted: &[T], target: &[T]) -> T;
+
+    /// 损失对预测值的梯度，同样不检查长度
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T>;
+
+    /// 输出层激活函数可以和损失合并求导时，直接返回损失对激活前值的梯度，
+    /// 例如 softmax + 交叉熵、sigmoid + 二元交叉熵，既省去雅可比矩阵也更稳定
+    fn fused_gradient(&self, _activation: Activation, _predicted: &[T], _target: &[T]) -> Option<Vec<T>> {
+        None
+    }
+
+    /// 在求导带上写出整个批次（每行一个样本）的损失之和，不支持自动微分时返回 `None`
+    fn value_on_tape<'t>(&self, _predicted: Var<'t, T>, _target: &Matrix<T>) -> Option<Var<'t, T>> {
+        None
+    }
+
+    /// 检查长度后求损失值，结果为 NaN 时返回错误
+    fn checked_value(&self, predicted: &[T], target: &[T]) -> Result<T, NnError> {
+        check_len("target", predicted.len(), target.len())?;
+        let value = self.value(predicted, target);
+        if value.is_nan() {
+            return Err(NnError::NaN("loss"));
+        }
+        Ok(value)
+    }
+
+    fn checked_gradient(&self, predicted: &[T], target: &[T]) -> Result<Vec<T>, NnError> {
+        check_len("target", predicted.len(), target.len())?;
+        Ok(self.gradient(predicted, target))
+    }
+}
+
+impl<T: Float, L: Loss<T> + ?Sized> Loss<T> for Box<L> {
+    fn value(&self, predicted: &[T], target: &[T]) -> T {
+        (**self).value(predicted, target)
+    }
+
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T> {
+        (**self).gradient(predicted, target)
+    }
+
+    fn fused_gradient(&self, activation: Activation, predicted: &[T], target: &[T]) -> Option<Vec<T>> {
+        (**self).fused_gradient(activation, predicted, target)
+    }
+
+    fn value_on_tape<'t>(&self, predicted: Var<'t, T>, target: &Matrix<T>) -> Option<Var<'t, T>> {
+        (**self).value_on_tape(predicted, target)
+    }
+}
+
+/// 均方误差 mean((p - t)^2)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeanSquaredError;
+
+impl<T: Float> Loss<T> for MeanSquaredError {
+    fn value(&self, predicted: &[T], target: &[T]) -> T {
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| (p - t).powi(2))
+            .sum::<T>() / len(predicted)
+    }
+
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T> {
+        let scale = T::from_f64(2.0) / len(predicted);
+        predicted.iter().zip(target.iter()).map(|(&p, &t)| scale * (p - t)).collect()
+    }
+
+    fn value_on_tape<'t>(&self, predicted: Var<'t, T>, target: &Matrix<T>) -> Option<Var<'t, T>> {
+        let diff = predicted - predicted.tape().var(target.clone());
+        Some(per_output((diff * diff).sum(), target))
+    }
+}
+
+/// 平均绝对误差 mean(|p - t|)，在 p == t 处取次梯度 0
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeanAbsoluteError;
+
+impl<T: Float> Loss<T> for MeanAbsoluteError {
+    fn value(&self, predicted: &[T], target: &[T]) -> T {
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| (p - t).abs())
+            .sum::<T>() / len(predicted)
+    }
+
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T> {
+        let step = T::one() / len(predicted);
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| if p > t { step } else if p < t { -step } else { T::zero() })
+            .collect()
+    }
+
+    fn value_on_tape<'t>(&self, predicted: Var<'t, T>, target: &Matrix<T>) -> Option<Var<'t, T>> {
+        Some(per_output((predicted - predicted.tape().var(target.clone())).abs().sum(), target))
+    }
+}
+
+/// Huber 损失：误差小于 `delta` 时是平方误差，超过后是线性的，对离群点不敏感
+#[derive(Debug, Clone, Copy)]
+pub struct Huber {
+    pub delta: f64,
+}
+
+impl Default for Huber {
+    fn default() -> Self {
+        Huber { delta: 1.0 }
+    }
+}
+
+impl<T: Float> Loss<T> for Huber {
+    fn value(&self, predicted: &[T], target: &[T]) -> T {
+        let (delta, half) = (T::from_f64(self.delta), T::from_f64(0.5));
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| {
+                let diff = (p - t).abs();
+                if diff <= delta {
+                    half * diff * diff
+                } else {
+                    delta * (diff - half * delta)
+                }
+            })
+            .sum::<T>() / len(predicted)
+    }
+
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T> {
+        let (delta, n) = (T::from_f64(self.delta), len(predicted));
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| (p - t).clamp(-delta, delta) / n)
+            .collect()
+    }
+
+    /// 0.5 q² + delta (|d| - q)，其中 q = min(|d|, delta)
+    fn value_on_tape<'t>(&self, predicted: Var<'t, T>, target: &Matrix<T>) -> Option<Var<'t, T>> {
+        let delta = T::from_f64(self.delta);
+        let diff = (predicted - predicted.tape().var(target.clone())).abs();
+        let quadratic = diff.clamp(T::zero(), delta);
+        let value = (quadratic * quadratic).scale(T::from_f64(0.5)) + (diff - quadratic).scale(delta);
+        Some(per_output(value.sum(), target))
+    }
+}
+
+/// 二元交叉熵 -mean(t ln p + (1 - t) ln(1 - p))，每个输出是一个独立的二分类概率
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryCrossEntropy;
+
+impl<T: Float> Loss<T> for BinaryCrossEntropy {
+    fn value(&self, predicted: &[T], target: &[T]) -> T {
+        let (eps, one) = (prob_epsilon::<T>(), T::one());
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| {
+                let p = p.clamp(eps, one - eps);
+                -(t * p.ln() + (one - t) * (one - p).ln())
+            })
+            .sum::<T>() / len(predicted)
+    }
+
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T> {
+        let (eps, one, n) = (prob_epsilon::<T>(), T::one(), len(predicted));
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| {
+                let p = p.clamp(eps, one - eps);
+                (p - t) / (p * (one - p)) / n
+            })
+            .collect()
+    }
+
+    fn fused_gradient(&self, activation: Activation, predicted: &[T], target: &[T]) -> Option<Vec<T>> {
+        if activation != Activation::Sigmoid {
+            return None;
+        }
+        let n = len(predicted);
+        Some(predicted.iter().zip(target.iter()).map(|(&p, &t)| (p - t) / n).collect())
+    }
+
+    fn value_on_tape<'t>(&self, predicted: Var<'t, T>, target: &Matrix<T>) -> Option<Var<'t, T>> {
+        let tape = predicted.tape();
+        let (p, t, one) = (predicted.clamp(prob_epsilon(), T::one() - prob_epsilon()), tape.var(target.clone()), tape.scalar(T::one()));
+        Some(per_output(-(t * p.log() + (one - t) * (one - p).log()).sum(), target))
+    }
+}
+
+/// 分类交叉熵 -Σ t ln p，目标是 one-hot 或概率分布
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CategoricalCrossEntropy;
+
+impl<T: Float> Loss<T> for CategoricalCrossEntropy {
+    fn value(&self, predicted: &[T], target: &[T]) -> T {
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| -t * p.clamp(prob_epsilon(), T::one()).ln())
+            .sum()
+    }
+
+    fn gradient(&self, predicted: &[T], target: &[T]) -> Vec<T> {
+        predicted.iter()
+            .zip(target.iter())
+            .map(|(&p, &t)| -t / p.clamp(prob_epsilon(), T::one()))
+            .collect()
+    }
+
+    /// softmax 输出时梯度化简为 `p - t`（要求目标之和为 1）
+    fn fused_gradient(&self, activation: Activation, predicted: &[T], target: &[T]) -> Option<Vec<T>> {
+        if activation != Activation::Softmax {
+            return None;
+        }
+        Some(predicted.iter().zip(target.iter()).map(|(&p, &t)| p - t).collect())
+    }
+
+    fn value_on_tape<'t>(&self, predicted: Var<'t, T>, target: &Matrix<T>) -> Option<Var<'t, T>> {
+        let t = predicted.tape().var(target.clone());
+        Some(-(t * predicted.clamp(prob_epsilon(), T::one()).log()).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradient_matches_numeric() {
+        let predicted = [0.2, 0.7, 0.1];
+        let target = [0.0, 1.0, 0.0];
+        let losses: [&dyn Loss; 6] = [
+            &MeanSquaredError,
+            &MeanAbsoluteError,
+            &Huber::default(),
+            &Huber { delta: 0.25 },
+            &BinaryCrossEntropy,
+            &CategoricalCrossEntropy,
+        ];
+        let eps = 1e-7;
+        for loss in losses {
+            let analytic = loss.gradient(&predicted, &target);
+            for i in 0..predicted.len() {
+                let (mut plus, mut minus) = (predicted, predicted);
+                plus[i] += eps;
+                minus[i] -= eps;
+                let numeric = (loss.value(&plus, &target) - loss.value(&minus, &target)) / (2.0 * eps);
+                assert!((analytic[i] - numeric).abs() < 1e-6, "{:?}[{}]: {} vs {}", loss, i, analytic[i], numeric);
+            }
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        assert!((MeanSquaredError.value(&[1.0, 3.0], &[0.0, 0.0f64]) - 5.0).abs() < 1e-12);
+        assert!((MeanAbsoluteError.value(&[1.0, -3.0], &[0.0, 0.0f64]) - 2.0).abs() < 1e-12);
+        // 误差 3 超过 delta=1：1 * (3 - 0.5)
+        assert!((Huber::default().value(&[3.0], &[0.0f64]) - 2.5).abs() < 1e-12);
+        assert!((CategoricalCrossEntropy.value(&[0.5, 0.5], &[1.0, 0.0f64]) - std::f64::consts::LN_2).abs() < 1e-12);
+        // 完全错误的预测不会得到无穷大
+        assert!(BinaryCrossEntropy.value(&[0.0], &[1.0f64]).is_finite());
+        assert!(BinaryCrossEntropy.value(&[1.0], &[0.0f32]).is_finite());
+    }
+
+    #[test]
+    fn test_checked_value() {
+        assert_eq!(
+            MeanSquaredError.checked_value(&[1.0, 2.0], &[1.0f64]),
+            Err(NnError::ShapeMismatch { what: "target", expected: 2, actual: 1 })
+        );
+        assert_eq!(MeanAbsoluteError.checked_value(&[f64::NAN], &[0.0]), Err(NnError::NaN("loss")));
+        assert_eq!(Huber::default().checked_gradient(&[2.0], &[0.0f64]), Ok(vec![1.0]));
+    }
+}
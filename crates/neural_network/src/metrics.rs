@@ -0,0 +1,473 @@
+//! 模型评估指标：分类的准确率、精确率/召回率/F1、混淆矩阵、ROC 和 AUC，回归的 MAE、RMSE、R²。
+//!
+//! 分母为 0 的指标（例如某个类别从未被预测）按 0 计算。
+
+use std::fmt;
+
+use crate::data::{label, Dataset};
+use crate::error::{check_len, NnError};
+use crate::float::Float;
+use crate::neural_network::NeuralNetwork;
+
+/// 预测正确的比例，没有样本时为 0
+pub fn accuracy(predicted: &[usize], actual: &[usize]) -> f64 {
+    assert_eq!(predicted.len(), actual.len(), "predicted and actual labels must have the same length");
+    let correct = predicted.iter().zip(actual).filter(|(p, a)| p == a).count();
+    ratio(correct as f64, actual.len() as f64)
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 { 0.0 } else { numerator / denominator }
+}
+
+fn harmonic_mean(precision: f64, recall: f64) -> f64 {
+    ratio(2.0 * precision * recall, precision + recall)
+}
+
+/// 一个类别（或平均）的精确率、召回率、F1 和样本数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassScores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// 真实属于该类的样本数，平均时为总样本数
+    pub support: usize,
+}
+
+/// `counts[actual][predicted]` 为真实类别是 actual、预测为 predicted 的样本数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    counts: Vec<Vec<usize>>,
+    labels: Vec<String>,
+}
+
+impl ConfusionMatrix {
+    /// 类别下标必须小于 `classes`，类别名默认是下标
+    pub fn new(predicted: &[usize], actual: &[usize], classes: usize) -> ConfusionMatrix {
+        assert_eq!(predicted.len(), actual.len(), "predicted and actual labels must have the same length");
+        let mut counts = vec![vec![0; classes]; classes];
+        for (&p, &a) in predicted.iter().zip(actual) {
+            assert!(p < classes && a < classes, "label out of range for {} classes", classes);
+            counts[a][p] += 1;
+        }
+        ConfusionMatrix { counts, labels: (0..classes).map(|c| c.to_string()).collect() }
+    }
+
+    /// 打印时使用的类别名
+    pub fn with_labels(mut self, labels: Vec<String>) -> Self {
+        assert_eq!(labels.len(), self.classes(), "need one label per class");
+        self.labels = labels;
+        self
+    }
+
+    pub fn classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn count(&self, actual: usize, predicted: usize) -> usize {
+        self.counts[actual][predicted]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        ratio((0..self.classes()).map(|c| self.counts[c][c]).sum::<usize>() as f64, self.total() as f64)
+    }
+
+    // (真正例, 预测为该类的数量, 真实为该类的数量)
+    fn tallies(&self, class: usize) -> (usize, usize, usize) {
+        let predicted = self.counts.iter().map(|row| row[class]).sum();
+        (self.counts[class][class], predicted, self.counts[class].iter().sum())
+    }
+
+    pub fn scores(&self, class: usize) -> ClassScores {
+        let (true_positives, predicted, actual) = self.tallies(class);
+        let precision = ratio(true_positives as f64, predicted as f64);
+        let recall = ratio(true_positives as f64, actual as f64);
+        ClassScores { precision, recall, f1: harmonic_mean(precision, recall), support: actual }
+    }
+
+    pub fn per_class(&self) -> Vec<ClassScores> {
+        (0..self.classes()).map(|c| self.scores(c)).collect()
+    }
+
+    /// 各类别指标的算术平均，每个类别权重相同
+    pub fn macro_average(&self) -> ClassScores {
+        let scores = self.per_class();
+        let mean = |f: fn(&ClassScores) -> f64| ratio(scores.iter().map(f).sum(), scores.len() as f64);
+        ClassScores { precision: mean(|s| s.precision), recall: mean(|s| s.recall), f1: mean(|s| s.f1), support: self.total() }
+    }
+
+    /// 把所有类别的计数加在一起再计算，每个样本权重相同。单标签分类时三个值都等于准确率
+    pub fn micro_average(&self) -> ClassScores {
+        let (mut true_positives, mut predicted, mut actual) = (0, 0, 0);
+        for class in 0..self.classes() {
+            let (tp, p, a) = self.tallies(class);
+            true_positives += tp;
+            predicted += p;
+            actual += a;
+        }
+        let precision = ratio(true_positives as f64, predicted as f64);
+        let recall = ratio(true_positives as f64, actual as f64);
+        ClassScores { precision, recall, f1: harmonic_mean(precision, recall), support: self.total() }
+    }
+}
+
+/// 行是真实类别，列是预测类别，右对齐
+impl fmt::Display for ConfusionMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let corner = "actual \\ predicted";
+        let first = self.labels.iter().map(|l| l.chars().count()).chain([corner.len()]).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..self.classes())
+            .map(|c| self.counts.iter().map(|row| row[c].to_string().len()).chain([self.labels[c].chars().count()]).max().unwrap_or(0))
+            .collect();
+        write!(f, "{:<first$}", corner)?;
+        for (label, width) in self.labels.iter().zip(&widths) {
+            write!(f, "  {:>width$}", label)?;
+        }
+        for (label, row) in self.labels.iter().zip(&self.counts) {
+            write!(f, "\n{:<first$}", label)?;
+            for (count, width) in row.iter().zip(&widths) {
+                write!(f, "  {:>width$}", count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ROC 曲线上的点，按阈值从高到低排列，第一个点是 (0, 0)，阈值为正无穷
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocCurve {
+    pub false_positive_rate: Vec<f64>,
+    pub true_positive_rate: Vec<f64>,
+    pub thresholds: Vec<f64>,
+}
+
+impl RocCurve {
+    /// `scores` 越大越可能是正例。分数相同的样本在同一个阈值处一起计入
+    pub fn new(scores: &[f64], positives: &[bool]) -> RocCurve {
+        assert_eq!(scores.len(), positives.len(), "scores and labels must have the same length");
+        let mut order: Vec<usize> = (0..scores.len()).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        let total_positives = positives.iter().filter(|&&p| p).count() as f64;
+        let total_negatives = positives.len() as f64 - total_positives;
+
+        let mut curve = RocCurve { false_positive_rate: vec![0.0], true_positive_rate: vec![0.0], thresholds: vec![f64::INFINITY] };
+        let (mut true_positives, mut false_positives) = (0.0, 0.0);
+        for (i, &index) in order.iter().enumerate() {
+            if positives[index] {
+                true_positives += 1.0;
+            } else {
+                false_positives += 1.0;
+            }
+            if order.get(i + 1).is_some_and(|&next| scores[next] == scores[index]) {
+                continue;
+            }
+            curve.false_positive_rate.push(ratio(false_positives, total_negatives));
+            curve.true_positive_rate.push(ratio(true_positives, total_positives));
+            curve.thresholds.push(scores[index]);
+        }
+        curve
+    }
+
+    /// 曲线下面积（梯形法）。只有正例或只有负例时没有意义，返回 NaN
+    pub fn auc(&self) -> f64 {
+        let degenerate = |rates: &[f64]| rates.last().is_some_and(|&r| r == 0.0);
+        if degenerate(&self.false_positive_rate) || degenerate(&self.true_positive_rate) {
+            return f64::NAN;
+        }
+        let points = || self.false_positive_rate.iter().zip(&self.true_positive_rate);
+        points().zip(points().skip(1)).map(|((x0, y0), (x1, y1))| (x1 - x0) * (y0 + y1) / 2.0).sum()
+    }
+}
+
+/// 二分类的 ROC AUC
+pub fn roc_auc(scores: &[f64], positives: &[bool]) -> f64 {
+    RocCurve::new(scores, positives).auc()
+}
+
+fn check_regression(predicted: &[f64], actual: &[f64]) {
+    assert_eq!(predicted.len(), actual.len(), "predicted and actual values must have the same length");
+}
+
+/// 平均绝对误差
+pub fn mean_absolute_error(predicted: &[f64], actual: &[f64]) -> f64 {
+    check_regression(predicted, actual);
+    ratio(predicted.iter().zip(actual).map(|(p, a)| (p - a).abs()).sum(), actual.len() as f64)
+}
+
+/// 均方根误差
+pub fn root_mean_squared_error(predicted: &[f64], actual: &[f64]) -> f64 {
+    check_regression(predicted, actual);
+    ratio(predicted.iter().zip(actual).map(|(p, a)| (p - a).powi(2)).sum(), actual.len() as f64).sqrt()
+}
+
+/// 决定系数 R² = 1 - 残差平方和 / 总平方和。真实值为常数时，完全预测正确为 1，否则为 0
+pub fn r2_score(predicted: &[f64], actual: &[f64]) -> f64 {
+    check_regression(predicted, actual);
+    let mean = ratio(actual.iter().sum(), actual.len() as f64);
+    let residual: f64 = predicted.iter().zip(actual).map(|(p, a)| (a - p).powi(2)).sum();
+    let total: f64 = actual.iter().map(|a| (a - mean).powi(2)).sum();
+    match (residual == 0.0, total == 0.0) {
+        (true, _) => 1.0,
+        (false, true) => 0.0,
+        (false, false) => 1.0 - residual / total,
+    }
+}
+
+/// [`NeuralNetwork::evaluate`] 要计算的指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Accuracy,
+    /// 各类别和宏/微平均的精确率、召回率、F1
+    PrecisionRecallF1,
+    ConfusionMatrix,
+    /// 多分类时为一对其余的宏平均
+    RocAuc,
+    MeanAbsoluteError,
+    RootMeanSquaredError,
+    /// 多个输出时取各输出的平均
+    R2,
+}
+
+impl Metric {
+    pub const CLASSIFICATION: [Metric; 4] = [Metric::Accuracy, Metric::PrecisionRecallF1, Metric::ConfusionMatrix, Metric::RocAuc];
+    pub const REGRESSION: [Metric; 3] = [Metric::MeanAbsoluteError, Metric::RootMeanSquaredError, Metric::R2];
+}
+
+/// 评估结果，没有请求的指标为 `None`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub samples: usize,
+    pub accuracy: Option<f64>,
+    pub per_class: Option<Vec<ClassScores>>,
+    pub macro_average: Option<ClassScores>,
+    pub micro_average: Option<ClassScores>,
+    pub confusion_matrix: Option<ConfusionMatrix>,
+    /// 没有一个类别同时有正例和负例时为 NaN
+    pub roc_auc: Option<f64>,
+    pub mean_absolute_error: Option<f64>,
+    pub root_mean_squared_error: Option<f64>,
+    pub r2: Option<f64>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "samples: {}", self.samples)?;
+        let mut value = |name: &str, value: Option<f64>| match value {
+            Some(value) => write!(f, "\n{}: {:.4}", name, value),
+            None => Ok(()),
+        };
+        value("accuracy", self.accuracy)?;
+        value("roc auc", self.roc_auc)?;
+        value("mae", self.mean_absolute_error)?;
+        value("rmse", self.root_mean_squared_error)?;
+        value("r2", self.r2)?;
+        if let Some(per_class) = &self.per_class {
+            let labels: Vec<String> = match &self.confusion_matrix {
+                Some(matrix) => matrix.labels().to_vec(),
+                None => (0..per_class.len()).map(|c| c.to_string()).collect(),
+            };
+            let width = labels.iter().map(|l| l.chars().count()).chain([9]).max().unwrap_or(0);
+            write!(f, "\n\n{:<width$}  precision     recall         f1    support", "")?;
+            let rows = labels.iter().map(String::as_str).zip(per_class.iter())
+                .chain([("macro avg", self.macro_average.as_ref()), ("micro avg", self.micro_average.as_ref())]
+                    .into_iter().filter_map(|(name, scores)| scores.map(|s| (name, s))));
+            for (name, s) in rows {
+                write!(f, "\n{:<width$}  {:>9.4}  {:>9.4}  {:>9.4}  {:>9}", name, s.precision, s.recall, s.f1, s.support)?;
+            }
+        }
+        if let Some(matrix) = &self.confusion_matrix {
+            write!(f, "\n\n{}", matrix)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float> NeuralNetwork<T> {
+    /// 在数据集上计算指定的指标。
+    ///
+    /// 分类时目标是 one-hot 编码（多个输出，取最大值）或单个 0/1 输出（四舍五入），
+    /// 类别名取自 [`Dataset::classes`]。网络输出与目标的长度不同时返回 `ShapeMismatch`；
+    /// 请求分类指标时，单个输出的目标必须是 0 或 1，否则返回 `InvalidLabel`。
+    pub fn evaluate(&self, dataset: &Dataset<T>, metrics: &[Metric]) -> Result<Report, NnError> {
+        if !dataset.is_empty() {
+            check_len("target", self.output_size(), dataset.target_size())?;
+        }
+        let outputs: Vec<Vec<f64>> = dataset.samples().iter()
+            .map(|(input, _)| Ok(self.predict(input)?.iter().map(|v| v.to_f64()).collect()))
+            .collect::<Result<_, NnError>>()?;
+        let targets: Vec<Vec<f64>> = dataset.samples().iter().map(|(_, target)| target.iter().map(|v| v.to_f64()).collect()).collect();
+        let mut report = Report { samples: dataset.len(), ..Report::default() };
+
+        // 类别数只由目标长度决定；单个输出的预测值可以是任意实数，截到最后一个类别
+        let classes = dataset.target_size().max(2);
+        let predicted: Vec<usize> = outputs.iter().map(|output| label(output).min(classes - 1)).collect();
+        let actual = dataset.labels();
+        if metrics.iter().any(|metric| Metric::CLASSIFICATION.contains(metric)) {
+            if let Some(&invalid) = actual.iter().find(|&&l| l >= classes) {
+                return Err(NnError::InvalidLabel(invalid));
+            }
+        }
+        let confusion = || {
+            let matrix = ConfusionMatrix::new(&predicted, &actual, classes);
+            match dataset.classes() {
+                Some(names) if names.len() == classes => matrix.with_labels(names.to_vec()),
+                _ => matrix,
+            }
+        };
+        // 每一列输出
+        let column = |rows: &[Vec<f64>], c: usize| -> Vec<f64> { rows.iter().map(|row| row[c]).collect() };
+        let flat = |rows: &[Vec<f64>]| rows.concat();
+
+        for metric in metrics {
+            match metric {
+                Metric::Accuracy => report.accuracy = Some(accuracy(&predicted, &actual)),
+                Metric::PrecisionRecallF1 => {
+                    let matrix = confusion();
+                    report.per_class = Some(matrix.per_class());
+                    report.macro_average = Some(matrix.macro_average());
+                    report.micro_average = Some(matrix.micro_average());
+                }
+                Metric::ConfusionMatrix => report.confusion_matrix = Some(confusion()),
+                Metric::RocAuc => {
+                    report.roc_auc = Some(if dataset.target_size() == 1 {
+                        let positives: Vec<bool> = actual.iter().map(|&l| l == 1).collect();
+                        roc_auc(&column(&outputs, 0), &positives)
+                    } else {
+                        let aucs: Vec<f64> = (0..dataset.target_size())
+                            .map(|c| roc_auc(&column(&outputs, c), &actual.iter().map(|&l| l == c).collect::<Vec<_>>()))
+                            .filter(|auc| !auc.is_nan())
+                            .collect();
+                        if aucs.is_empty() { f64::NAN } else { aucs.iter().sum::<f64>() / aucs.len() as f64 }
+                    })
+                }
+                Metric::MeanAbsoluteError => report.mean_absolute_error = Some(mean_absolute_error(&flat(&outputs), &flat(&targets))),
+                Metric::RootMeanSquaredError => {
+                    report.root_mean_squared_error = Some(root_mean_squared_error(&flat(&outputs), &flat(&targets)))
+                }
+                Metric::R2 => {
+                    let size = dataset.target_size();
+                    let total: f64 = (0..size).map(|c| r2_score(&column(&outputs, c), &column(&targets, c))).sum();
+                    report.r2 = Some(ratio(total, size as f64));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Activation;
+    use crate::dense::Dense;
+    use crate::matrix::Matrix;
+    use crate::sequential::Sequential;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_classification_scores() {
+        let actual = [0, 0, 0, 1, 1, 2];
+        let predicted = [0, 0, 1, 1, 2, 2];
+        assert!(close(accuracy(&predicted, &actual), 4.0 / 6.0));
+        let matrix = ConfusionMatrix::new(&predicted, &actual, 3).with_labels(vec!["cat".into(), "dog".into(), "bird".into()]);
+        assert_eq!(matrix.count(0, 1), 1);
+        let dog = matrix.scores(1);
+        assert!(close(dog.precision, 0.5) && close(dog.recall, 0.5) && dog.support == 2);
+        let bird = matrix.scores(2);
+        assert!(close(bird.precision, 0.5) && close(bird.recall, 1.0) && close(bird.f1, 2.0 / 3.0));
+        let macro_average = matrix.macro_average();
+        assert!(close(macro_average.precision, (1.0 + 0.5 + 0.5) / 3.0));
+        assert!(close(macro_average.recall, (2.0 / 3.0 + 0.5 + 1.0) / 3.0));
+        let micro = matrix.micro_average();
+        assert!(close(micro.f1, matrix.accuracy()) && micro.support == 6);
+        assert_eq!(
+            matrix.to_string(),
+            "actual \\ predicted  cat  dog  bird\n\
+             cat                   2    1     0\n\
+             dog                   0    1     1\n\
+             bird                  0    0     1"
+        );
+    }
+
+    #[test]
+    fn test_roc_curve() {
+        let scores = [0.9, 0.8, 0.7, 0.6, 0.55, 0.4];
+        let positives = [true, true, false, true, false, false];
+        let curve = RocCurve::new(&scores, &positives);
+        assert_eq!(curve.thresholds.len(), 7);
+        assert_eq!(curve.true_positive_rate[2], 2.0 / 3.0);
+        assert!(close(curve.auc(), 8.0 / 9.0));
+        // 分数全部相同时是对角线
+        assert!(close(roc_auc(&[0.5; 4], &[true, false, true, false]), 0.5));
+        assert!(roc_auc(&[0.1, 0.2], &[true, true]).is_nan());
+    }
+
+    #[test]
+    fn test_regression_scores() {
+        let actual = [3.0, -0.5, 2.0, 7.0];
+        let predicted = [2.5, 0.0, 2.0, 8.0];
+        assert!(close(mean_absolute_error(&predicted, &actual), 0.5));
+        assert!(close(root_mean_squared_error(&predicted, &actual), 0.375f64.sqrt()));
+        assert!(close(r2_score(&predicted, &actual), 1.0 - 1.5 / 29.1875));
+        assert_eq!(r2_score(&[1.0, 1.0], &[1.0, 1.0]), 1.0);
+        assert_eq!(r2_score(&[1.0, 2.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // 输出等于输入的网络，方便构造预测值
+        let identity = Dense::from_parts(Matrix::from_rows(&[[1.0, 0.0], [0.0, 1.0]]), vec![0.0; 2], Activation::Identity);
+        let network = Sequential::<f64>::new(2).layer(identity).build();
+        let samples = vec![
+            (vec![0.9, 0.1], vec![1.0, 0.0]),
+            (vec![0.3, 0.7], vec![0.0, 1.0]),
+            (vec![0.6, 0.4], vec![0.0, 1.0]),
+            (vec![0.2, 0.8], vec![0.0, 1.0]),
+        ];
+        let dataset = Dataset::new(samples).with_classes(vec!["no".into(), "yes".into()]);
+        let report = network.evaluate(&dataset, &Metric::CLASSIFICATION).unwrap();
+        assert_eq!(report.accuracy, Some(0.75));
+        assert_eq!(report.confusion_matrix.as_ref().unwrap().labels(), ["no", "yes"]);
+        assert_eq!(report.per_class.as_ref().unwrap()[1].recall, 2.0 / 3.0);
+        assert_eq!(report.roc_auc, Some(1.0));
+        assert!(report.mean_absolute_error.is_none());
+        assert!(report.to_string().contains("macro avg"));
+
+        let report = network.evaluate(&dataset, &Metric::REGRESSION).unwrap();
+        assert!(close(report.mean_absolute_error.unwrap(), (0.1 + 0.3 + 0.6 + 0.2) / 4.0));
+        assert!(report.r2.unwrap() < 1.0);
+        assert!(report.accuracy.is_none());
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        let network = Sequential::<f64>::new(2).dense(1, Activation::Identity).build();
+        let two_targets = Dataset::new(vec![(vec![0.0, 1.0], vec![0.0, 1.0])]);
+        assert_eq!(
+            network.evaluate(&two_targets, &Metric::REGRESSION),
+            Err(NnError::ShapeMismatch { what: "target", expected: 1, actual: 2 })
+        );
+
+        // 回归目标不能算分类指标，回归指标照常计算
+        let regression = Dataset::new(vec![(vec![0.0, 1.0], vec![1e9]), (vec![1.0, 0.0], vec![0.0])]);
+        assert_eq!(network.evaluate(&regression, &[Metric::ConfusionMatrix]), Err(NnError::InvalidLabel(1_000_000_000)));
+        assert!(network.evaluate(&regression, &[Metric::RootMeanSquaredError]).is_ok());
+
+        // 很大的预测值算作类别 1
+        let scaled = Sequential::<f64>::new(1).layer(Dense::from_parts(Matrix::from_rows(&[[1e9]]), vec![0.0], Activation::Identity)).build();
+        let binary = Dataset::new(vec![(vec![1.0], vec![1.0]), (vec![0.0], vec![0.0])]);
+        let report = scaled.evaluate(&binary, &[Metric::ConfusionMatrix]).unwrap();
+        assert_eq!(report.confusion_matrix.unwrap().classes(), 2);
+    }
+}
@@ -1,45 +1,492 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::activation::Activation;
+use crate::autodiff::Tape;
+use crate::error::{check_len, NnError};
+use crate::float::Float;
+use crate::dense::Dense;
+use crate::init::Initializer;
 use crate::layer::Layer;
+use crate::loss::{Loss, MeanSquaredError};
+use crate::matrix::Matrix;
+use crate::optimizer::{GradientClip, Optimizer};
+use crate::regularizer::Regularizer;
 
 // 定义神经网络结构
 #[derive(Debug)]
-pub struct NeuralNetwork {
-    layers: Vec<Layer>,
+pub struct NeuralNetwork<T: Float = f64> {
+    layers: Vec<Box<dyn Layer<T>>>,
+    // 训练时使用的损失函数，默认均方误差
+    loss: Box<dyn Loss<T>>,
+    // 最近一次 forward 的输出，计算损失梯度时使用
+    output: Matrix<T>,
+    training: bool,
+}
+
+/// 全连接层的配置：神经元个数、激活函数、参数初始化方式和正则化
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerSpec {
+    pub size: usize,
+    pub activation: Activation,
+    /// 默认按激活函数选择，见 [`Initializer::for_activation`]
+    pub weight_init: Initializer,
+    /// 默认全 0
+    pub bias_init: Initializer,
+    /// 默认不加惩罚
+    pub regularizer: Regularizer,
 }
 
-impl NeuralNetwork {
+impl LayerSpec {
+    pub fn new(size: usize, activation: Activation) -> Self {
+        LayerSpec {
+            size,
+            activation,
+            weight_init: Initializer::for_activation(activation),
+            bias_init: Initializer::Zeros,
+            regularizer: Regularizer::default(),
+        }
+    }
+
+    pub fn with_weight_init(mut self, init: Initializer) -> Self {
+        self.weight_init = init;
+        self
+    }
+
+    pub fn with_bias_init(mut self, init: Initializer) -> Self {
+        self.bias_init = init;
+        self
+    }
+
+    pub fn with_regularizer(mut self, regularizer: Regularizer) -> Self {
+        self.regularizer = regularizer;
+        self
+    }
+}
+
+impl<T: Float> NeuralNetwork<T> {
+
+    /// 所有层都使用 sigmoid 激活。
+    /// 精度由类型参数决定，默认 f64，单精度用 `NeuralNetwork::<f32>::new(..)`
+    pub fn new(layer_sizes: &[usize]) -> NeuralNetwork<T> {
+        let activations = vec![Activation::Sigmoid; layer_sizes.len().saturating_sub(1)];
+        Self::with_activations(layer_sizes, &activations)
+    }
+
+    /// 为每一层指定激活函数，`activations` 的长度必须是 `layer_sizes.len() - 1`，
+    /// 例如 `with_activations(&[784, 64, 10], &[Activation::ReLU, Activation::Softmax])`。
+    ///
+    /// 长度不符时 panic，需要处理错误时用 [`NeuralNetwork::try_with_activations`]
+    pub fn with_activations(layer_sizes: &[usize], activations: &[Activation]) -> NeuralNetwork<T> {
+        Self::try_with_activations(layer_sizes, activations).expect("one activation per layer is required")
+    }
+
+    /// 与 `with_activations` 相同，长度不符时返回 `ShapeMismatch`
+    pub fn try_with_activations(layer_sizes: &[usize], activations: &[Activation]) -> Result<NeuralNetwork<T>, NnError> {
+        let specs = Self::specs(layer_sizes, activations)?;
+        Ok(Self::from_specs(Self::input_len(layer_sizes), &specs, &mut rand::thread_rng()))
+    }
+
+    /// 与 `with_activations` 相同，但用固定种子初始化参数，结果可复现。长度不符时 panic
+    pub fn seeded(layer_sizes: &[usize], activations: &[Activation], seed: u64) -> NeuralNetwork<T> {
+        Self::try_seeded(layer_sizes, activations, seed).expect("one activation per layer is required")
+    }
+
+    /// 与 `seeded` 相同，长度不符时返回 `ShapeMismatch`
+    pub fn try_seeded(layer_sizes: &[usize], activations: &[Activation], seed: u64) -> Result<NeuralNetwork<T>, NnError> {
+        let specs = Self::specs(layer_sizes, activations)?;
+        Ok(Self::from_specs(Self::input_len(layer_sizes), &specs, &mut StdRng::seed_from_u64(seed)))
+    }
+
+    /// 逐层指定全连接层的配置，参数从 `rng` 中采样。需要其他类型的层时用 [`Sequential`](crate::sequential::Sequential)
+    pub fn from_specs<R: Rng + ?Sized>(input_size: usize, specs: &[LayerSpec], rng: &mut R) -> NeuralNetwork<T> {
+        let mut fan_in = input_size;
+        let layers = specs.iter().map(|spec| {
+            let layer = Dense::new(fan_in, spec.size, spec.activation, spec.weight_init, spec.bias_init, rng)
+                .with_regularizer(spec.regularizer);
+            fan_in = spec.size;
+            Box::new(layer) as Box<dyn Layer<T>>
+        }).collect();
+        Self::from_layers(layers)
+    }
+
+    /// `layer_sizes` 为空时得到没有层的网络，前向传播会返回 `EmptyNetwork`
+    fn input_len(layer_sizes: &[usize]) -> usize {
+        layer_sizes.first().copied().unwrap_or(0)
+    }
+
+    fn specs(layer_sizes: &[usize], activations: &[Activation]) -> Result<Vec<LayerSpec>, NnError> {
+        check_len("activations", layer_sizes.len().saturating_sub(1), activations.len())?;
+        Ok(layer_sizes.iter().skip(1)
+            .zip(activations.iter())
+            .map(|(&size, &activation)| LayerSpec::new(size, activation))
+            .collect())
+    }
+
+    /// 调用方保证相邻层的形状一致
+    pub(crate) fn from_layers(layers: Vec<Box<dyn Layer<T>>>) -> NeuralNetwork<T> {
+        NeuralNetwork { layers, loss: Box::new(MeanSquaredError), output: Matrix::zeros(0, 0), training: true }
+    }
+
+    /// 指定训练使用的损失函数，例如 softmax 输出配合 `CategoricalCrossEntropy`
+    pub fn with_loss(mut self, loss: impl Loss<T> + 'static) -> Self {
+        self.set_loss(loss);
+        self
+    }
+
+    pub fn set_loss(&mut self, loss: impl Loss<T> + 'static) {
+        self.loss = Box::new(loss);
+    }
+
+    pub(crate) fn set_boxed_loss(&mut self, loss: Box<dyn Loss<T>>) {
+        self.loss = loss;
+    }
+
+    pub fn loss(&self) -> &dyn Loss<T> {
+        self.loss.as_ref()
+    }
+
+    /// 输入展开后的长度，没有层时为 0
+    pub fn input_size(&self) -> usize {
+        self.layers.first().map_or(0, |layer| layer.input_shape().size())
+    }
+
+    /// 输出展开后的长度，没有层时为 0
+    pub fn output_size(&self) -> usize {
+        self.layers.last().map_or(0, |layer| layer.output_shape().size())
+    }
+
+    /// 切换训练/推理模式，默认是训练模式。推理模式下 `forward` 不做 dropout，批归一化使用滑动统计量；
+    /// `predict` 不受影响，始终按推理模式计算
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+        self.layers.iter_mut().for_each(|layer| layer.set_training(training));
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+
+    /// 清空 stateful 循环层保留的状态，开始处理新的序列前调用
+    pub fn reset_state(&mut self) {
+        self.layers.iter_mut().for_each(|layer| layer.reset_state());
+    }
+
+    /// 各层正则化惩罚之和，不包含在 `compute_gradients` 返回的损失中
+    pub fn penalty(&self) -> f64 {
+        self.layers.iter().map(|layer| layer.penalty()).sum()
+    }
+
+    /// 前向传播，同时缓存各层的中间结果供 `backward` 使用
+    pub fn forward(&mut self, input: &[T]) -> Result<Vec<T>, NnError> {
+        Ok(self.forward_batch(&Matrix::row_vector(input))?.into_vec())
+    }
+
+    /// 只做推理，不需要可变借用
+    pub fn predict(&self, input: &[T]) -> Result<Vec<T>, NnError> {
+        Ok(self.predict_batch(&Matrix::row_vector(input))?.into_vec())
+    }
+
+    /// 同 `forward`，输入长度不对或出现 NaN 时 panic
+    pub fn forward_or_panic(&mut self, input: &[T]) -> Vec<T> {
+        self.forward(input).unwrap_or_else(|e| panic!("forward failed: {}", e))
+    }
+
+    /// 同 `predict`，输入长度不对或出现 NaN 时 panic
+    pub fn predict_or_panic(&self, input: &[T]) -> Vec<T> {
+        self.predict(input).unwrap_or_else(|e| panic!("predict failed: {}", e))
+    }
+
+    /// 整个批次一起前向传播，`inputs` 每行一个样本，返回的输出也是每行一个样本
+    pub fn forward_batch(&mut self, inputs: &Matrix<T>) -> Result<Matrix<T>, NnError> {
+        self.check_input(inputs)?;
+        let output = self.layers.iter_mut().fold(inputs.clone(), |acc, layer| layer.forward(&acc));
+        self.output = check_nan(output)?;
+        Ok(self.output.clone())
+    }
+
+    pub fn predict_batch(&self, inputs: &Matrix<T>) -> Result<Matrix<T>, NnError> {
+        self.check_input(inputs)?;
+        let output = self.layers.iter().fold(inputs.clone(), |acc, layer| layer.predict(&acc));
+        check_nan(output)
+    }
+
+    fn check_input(&self, inputs: &Matrix<T>) -> Result<(), NnError> {
+        if self.layers.is_empty() {
+            return Err(NnError::EmptyNetwork);
+        }
+        check_len("input", self.input_size(), inputs.cols())
+    }
+
+    /// 根据最近一次 `forward` 的结果计算损失的梯度并累积到各层，不更新参数，返回该样本的损失值。
+    /// 输出层激活和损失可以合并求导时（softmax + 交叉熵等）直接从激活前值开始反向传播。
+    pub fn compute_gradients(&mut self, target: &[T]) -> Result<T, NnError> {
+        self.compute_gradients_batch(&Matrix::row_vector(target))
+    }
+
+    /// 批次版本的 `compute_gradients`，`targets` 每行对应最近一次 `forward_batch` 的一个样本。
+    /// 梯度对批次求和，返回各样本损失之和。损失为 NaN 时不累积梯度。
+    pub fn compute_gradients_batch(&mut self, targets: &Matrix<T>) -> Result<T, NnError> {
+        backpropagate(&mut self.layers, &self.output, self.loss.as_ref(), targets)
+    }
+
+    /// 同 `compute_gradients_batch`，但使用给定的损失函数，多线程训练时工作副本共用主网络的损失
+    pub(crate) fn compute_gradients_with(&mut self, loss: &dyn Loss<T>, targets: &Matrix<T>) -> Result<T, NnError> {
+        backpropagate(&mut self.layers, &self.output, loss, targets)
+    }
+
+    /// 用自动微分计算一个批次的梯度并累积到各层，返回各样本损失之和。
+    ///
+    /// 结果应与（推理模式下的）`forward_batch` + `compute_gradients_batch` 相同，正则化惩罚的梯度同样按样本数累加。
+    /// 用来验证手写的反向传播，或者让还没有手写 `backward` 的新层先训练起来。
+    /// 所有层和损失函数都必须实现 `forward_on_tape` / `value_on_tape`，否则返回 [`NnError::NoAutodiff`]。
+    pub fn compute_gradients_on_tape(&mut self, inputs: &Matrix<T>, targets: &Matrix<T>) -> Result<T, NnError> {
+        self.check_input(inputs)?;
+        check_len("batch size", inputs.rows(), targets.rows())?;
+        check_len("target", self.output_size(), targets.cols())?;
+        let tape = Tape::new();
+        let (mut output, mut penalty) = (tape.var(inputs.clone()), tape.scalar(T::zero()));
+        let mut parameters = Vec::new();
+        // 每层的参数节点在 parameters 中的范围
+        let mut ranges = Vec::with_capacity(self.layers.len());
+        for (index, layer) in self.layers.iter().enumerate() {
+            let start = parameters.len();
+            output = layer.forward_on_tape(output, &mut parameters).ok_or_else(|| NnError::NoAutodiff(format!("layer {}", index)))?;
+            if let Some(layer_penalty) = layer.penalty_on_tape(&parameters[start..]) {
+                penalty = penalty + layer_penalty;
+            }
+            ranges.push(start..parameters.len());
+        }
+        let loss = self.loss.value_on_tape(output, targets).ok_or_else(|| NnError::NoAutodiff(format!("loss {:?}", self.loss)))?;
+        let value = loss.value()[(0, 0)];
+        if value.is_nan() {
+            return Err(NnError::NaN("loss"));
+        }
+
+        let grads = (loss + penalty.scale(T::from_f64(inputs.rows() as f64))).backward();
+        for (layer, range) in self.layers.iter_mut().zip(ranges) {
+            let mut groups = parameters[range].iter();
+            layer.visit_parameters(&mut |_, layer_grads| {
+                if let Some(&var) = groups.next() {
+                    layer_grads.iter_mut().zip(grads.wrt(var).as_slice()).for_each(|(g, &d)| *g += d);
+                }
+            });
+        }
+        Ok(value)
+    }
+
+    /// 用累积的梯度做一次普通的梯度下降，等价于 `step(&mut Sgd::new(learning_rate))`
+    pub fn apply_gradients(&mut self, learning_rate: f64) {
+        let learning_rate = T::from_f64(learning_rate);
+        self.visit_parameters(|params, grads| {
+            params.iter_mut().zip(grads.iter()).for_each(|(p, &g)| *p -= learning_rate * g);
+        });
+    }
+
+    /// 用优化器根据累积的梯度更新一次参数
+    pub fn step(&mut self, optimizer: &mut dyn Optimizer<T>) {
+        optimizer.begin_step();
+        let mut id = 0;
+        self.visit_parameters(|params, grads| {
+            optimizer.update(id, params, grads);
+            id += 1;
+        });
+    }
+
+    /// 梯度乘以系数，例如累积了一个批次的梯度后除以批大小
+    pub fn scale_gradients(&mut self, factor: f64) {
+        let factor = T::from_f64(factor);
+        self.visit_parameters(|_, grads| grads.iter_mut().for_each(|g| *g *= factor));
+    }
+
+    /// 所有梯度拼成一个向量后的 L2 范数
+    pub fn gradient_norm(&mut self) -> f64 {
+        let mut sum = 0.0;
+        self.visit_parameters(|_, grads| sum += grads.iter().map(|g| g.to_f64().powi(2)).sum::<f64>());
+        sum.sqrt()
+    }
+
+    pub fn clip_gradients(&mut self, clip: GradientClip) {
+        match clip {
+            GradientClip::Norm(max_norm) => {
+                let norm = self.gradient_norm();
+                if norm > max_norm {
+                    self.scale_gradients(max_norm / norm);
+                }
+            }
+            GradientClip::Value(max) => {
+                let max = T::from_f64(max);
+                self.visit_parameters(|_, grads| grads.iter_mut().for_each(|g| *g = g.clamp(-max, max)));
+            }
+        }
+    }
+
+    /// 复制另一个结构相同的网络的参数，不复制梯度和损失函数
+    pub fn copy_parameters_from(&mut self, other: &NeuralNetwork<T>) {
+        for (layer, source) in self.layers.iter_mut().zip(other.layers.iter()) {
+            let mut groups = source.parameters().into_iter();
+            layer.visit_parameters(&mut |params, _| {
+                if let Some(group) = groups.next() {
+                    params.copy_from_slice(group);
+                }
+            });
+        }
+    }
+
+    fn visit_parameters(&mut self, mut f: impl FnMut(&mut [T], &mut [T])) {
+        for layer in self.layers.iter_mut() {
+            layer.visit_parameters(&mut f);
+        }
+    }
+
+    pub fn zero_grad(&mut self) {
+        self.layers.iter_mut().for_each(|layer| layer.zero_grad());
+    }
 
-    pub fn new(layer_sizes: &[usize]) -> NeuralNetwork {
+    /// 单样本的一次完整更新：清空梯度、反向传播、梯度下降，返回更新前的损失。必须先调用 `forward`。
+    pub fn backward(&mut self, target: &[T], learning_rate: f64) -> Result<T, NnError> {
+        self.zero_grad();
+        let loss = self.compute_gradients(target)?;
+        self.apply_gradients(learning_rate);
+        Ok(loss)
+    }
+
+    /// 结构、参数和状态都相同的副本，用于多线程训练。不支持 `replicate` 的层返回错误
+    pub(crate) fn replicate(&self) -> Result<NeuralNetwork<T>, NnError> {
+        let layers = self.layers.iter().enumerate()
+            .map(|(index, layer)| layer.replicate().ok_or(NnError::NotReplicable(index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut replica = NeuralNetwork::from_layers(layers);
+        replica.set_training(self.training);
+        Ok(replica)
+    }
+
+    /// 把另一个结构相同的网络的梯度加到本网络上
+    pub(crate) fn add_gradients_from(&mut self, other: &NeuralNetwork<T>) {
+        for (layer, source) in self.layers.iter_mut().zip(other.layers.iter()) {
+            let mut groups = source.gradients().into_iter();
+            layer.visit_parameters(&mut |_, grads| {
+                if let Some(group) = groups.next() {
+                    grads.iter_mut().zip(group).for_each(|(g, &s)| *g += s);
+                }
+            });
+        }
+    }
+
+    /// 各层 `visit_state` 给出的状态，按顺序排列
+    pub(crate) fn state(&mut self) -> Vec<Vec<T>> {
+        let mut state = Vec::new();
+        for layer in self.layers.iter_mut() {
+            layer.visit_state(&mut |values| state.push(values.to_vec()));
+        }
+        state
+    }
 
-        let layers = layer_sizes.windows(2)
-            .map(|w| Layer::new(w[0], w[1]))
-            .collect();
+    pub(crate) fn set_state(&mut self, state: &[Vec<T>]) {
+        let mut groups = state.iter();
+        for layer in self.layers.iter_mut() {
+            layer.visit_state(&mut |values| values.copy_from_slice(groups.next().expect("state of a different network")));
+        }
+    }
 
-        NeuralNetwork { layers }
+    pub fn layers(&self) -> &[Box<dyn Layer<T>>] {
+        &self.layers
     }
 
-    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
-        self.layers.iter().fold(input.to_vec(), |acc, layer| layer.forward(&acc))
+    pub(crate) fn layers_mut(&mut self) -> &mut [Box<dyn Layer<T>>] {
+        &mut self.layers
     }
+}
 
-    pub fn backward(&mut self, inputs: &[f64], target: &[f64], learning_rate: f64) {
-        let mut layer_inputs = vec![inputs.to_vec()];
-        let mut current_input = inputs.to_vec();
+/// 从输出层开始反向传播，`output` 是最近一次前向传播的输出
+fn backpropagate<T: Float>(layers: &mut [Box<dyn Layer<T>>], output: &Matrix<T>, loss: &dyn Loss<T>, targets: &Matrix<T>) -> Result<T, NnError> {
+    let Some((last, rest)) = layers.split_last_mut() else {
+        return Err(NnError::EmptyNetwork);
+    };
+    // 没有调用过 forward 时缓存的输出为 0 行
+    check_len("batch size", output.rows(), targets.rows())?;
+    check_len("target", output.cols(), targets.cols())?;
+    let pairs = || output.row_iter().zip(targets.row_iter());
+    let total = pairs().map(|(predicted, target)| loss.checked_value(predicted, target)).sum::<Result<T, _>>()?;
 
-        for layer in &self.layers {
-            current_input = layer.forward(&current_input);
-            layer_inputs.push(current_input.clone());
+    let mut fused_delta = |activation| {
+        let delta: Vec<Vec<T>> = pairs().map(|(predicted, target)| loss.fused_gradient(activation, predicted, target)).collect::<Option<_>>()?;
+        Some(Matrix::new(output.rows(), output.cols(), delta.concat()))
+    };
+    let mut current_grad = match last.fused_backward(&mut fused_delta) {
+        Some(grad) => grad,
+        None => {
+            let grads: Vec<T> = pairs().flat_map(|(predicted, target)| loss.gradient(predicted, target)).collect();
+            last.backward(&Matrix::new(output.rows(), output.cols(), grads))
         }
+    };
+    for layer in rest.iter_mut().rev() {
+        current_grad = layer.backward(&current_grad);
+    }
+    Ok(total)
+}
 
-        let error = layer_inputs.last().unwrap()
-            .iter()
-            .zip(target.iter())
-            .map(|(o, t)| o - t)
-            .collect::<Vec<_>>();
-        let mut current_error = error;
+/// 输出里出现 NaN 时返回错误
+fn check_nan<T: Float>(output: Matrix<T>) -> Result<Matrix<T>, NnError> {
+    if output.as_slice().iter().any(|x| x.is_nan()) {
+        return Err(NnError::NaN("network output"));
+    }
+    Ok(output)
+}
 
-        for (layer, inputs) in self.layers.iter_mut().rev().zip(layer_inputs.iter().rev().skip(1)) {
-            current_error = layer.backward(inputs, &current_error, learning_rate);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loss::CategoricalCrossEntropy;
+
+    #[test]
+    fn test_batch_matches_per_sample() {
+        let activations = [Activation::Tanh, Activation::Softmax];
+        let samples = [vec![0.5, -1.0, 2.0], vec![1.5, 0.0, -0.3], vec![-0.7, 0.2, 0.9]];
+        let targets = [vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0]];
+
+        let mut batched = NeuralNetwork::<f64>::seeded(&[3, 4, 2], &activations, 5).with_loss(CategoricalCrossEntropy);
+        let outputs = batched.forward_batch(&Matrix::from_rows(&samples)).unwrap();
+        let batch_loss = batched.compute_gradients_batch(&Matrix::from_rows(&targets)).unwrap();
+
+        let mut single = NeuralNetwork::<f64>::seeded(&[3, 4, 2], &activations, 5).with_loss(CategoricalCrossEntropy);
+        let mut loss = 0.0;
+        for (row, (input, target)) in samples.iter().zip(targets.iter()).enumerate() {
+            let output = single.forward(input).unwrap();
+            assert!(output.iter().zip(outputs.row(row)).all(|(a, b)| (a - b).abs() < 1e-12));
+            loss += single.compute_gradients(target).unwrap();
+        }
+        assert!((batch_loss - loss).abs() < 1e-12);
+        for (a, b) in batched.layers().iter().zip(single.layers()) {
+            let grads = a.gradients().into_iter().flatten().zip(b.gradients().into_iter().flatten());
+            assert!(grads.into_iter().all(|(x, y)| (x - y).abs() < 1e-12));
         }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_shape_errors() {
+        let mut network = NeuralNetwork::<f64>::new(&[3, 4, 2]);
+        assert_eq!(
+            network.forward(&[1.0, 2.0]),
+            Err(NnError::ShapeMismatch { what: "input", expected: 3, actual: 2 })
+        );
+        // 还没有成功的前向传播
+        assert_eq!(
+            network.compute_gradients(&[1.0, 0.0]),
+            Err(NnError::ShapeMismatch { what: "batch size", expected: 0, actual: 1 })
+        );
+        network.forward(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(
+            network.backward(&[1.0], 0.1),
+            Err(NnError::ShapeMismatch { what: "target", expected: 2, actual: 1 })
+        );
+        assert_eq!(network.predict(&[f64::NAN, 0.0, 0.0]), Err(NnError::NaN("network output")));
+        assert_eq!(NeuralNetwork::<f64>::new(&[3]).predict(&[1.0, 2.0, 3.0]), Err(NnError::EmptyNetwork));
+        assert_eq!(NeuralNetwork::<f32>::new(&[]).output_size(), 0);
+
+        let activations = [Activation::ReLU];
+        let result = NeuralNetwork::<f64>::try_with_activations(&[3, 4, 2], &activations);
+        assert_eq!(result.err(), Some(NnError::ShapeMismatch { what: "activations", expected: 2, actual: 1 }));
+        assert!(NeuralNetwork::<f64>::try_seeded(&[3, 4], &activations, 1).is_ok());
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::float::Float;
+
+/// 优化器：根据梯度更新参数，并自己保存每组参数的状态（动量、二阶矩等）。
+///
+/// 网络按固定顺序把参数分成若干组交给优化器，`id` 是组的编号，同一组参数每次更新时编号相同。
+/// 超参数用 f64 保存，状态和参数同为 `T` 精度。
+pub trait Optimizer<T: Float = f64>: Debug + Send {
+    /// 每次参数更新前调用一次，Adam 之类需要步数的优化器在这里推进计数
+    fn begin_step(&mut self) {}
+
+    fn update(&mut self, id: usize, params: &mut [T], grads: &[T]);
+
+    fn learning_rate(&self) -> f64;
+
+    /// 训练器设置了学习率调度时，每次参数更新前都会用调度的结果覆盖这里的值
+    fn set_learning_rate(&mut self, learning_rate: f64);
+}
+
+/// 取出（或初始化为 0）某组参数的状态
+fn state<T: Float>(states: &mut HashMap<usize, Vec<T>>, id: usize, len: usize) -> &mut Vec<T> {
+    states.entry(id).or_insert_with(|| vec![T::zero(); len])
+}
+
+/// 随机梯度下降，可选动量和 Nesterov 动量
+#[derive(Debug, Clone)]
+pub struct Sgd<T: Float = f64> {
+    learning_rate: f64,
+    momentum: f64,
+    nesterov: bool,
+    velocity: HashMap<usize, Vec<T>>,
+}
+
+impl<T: Float> Sgd<T> {
+    pub fn new(learning_rate: f64) -> Self {
+        Sgd { learning_rate, momentum: 0.0, nesterov: false, velocity: HashMap::new() }
+    }
+
+    pub fn with_momentum(mut self, momentum: f64) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    pub fn with_nesterov(mut self, momentum: f64) -> Self {
+        self.momentum = momentum;
+        self.nesterov = true;
+        self
+    }
+}
+
+impl<T: Float> Optimizer<T> for Sgd<T> {
+    fn update(&mut self, id: usize, params: &mut [T], grads: &[T]) {
+        let (learning_rate, momentum) = (T::from_f64(self.learning_rate), T::from_f64(self.momentum));
+        if self.momentum == 0.0 {
+            params.iter_mut().zip(grads).for_each(|(p, &g)| *p -= learning_rate * g);
+            return;
+        }
+        let velocity = state(&mut self.velocity, id, params.len());
+        for ((p, &g), v) in params.iter_mut().zip(grads).zip(velocity.iter_mut()) {
+            *v = momentum * *v + g;
+            let step = if self.nesterov { g + momentum * *v } else { *v };
+            *p -= learning_rate * step;
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// AdaGrad：按历史梯度平方和缩放步长，适合稀疏特征
+#[derive(Debug, Clone)]
+pub struct AdaGrad<T: Float = f64> {
+    learning_rate: f64,
+    epsilon: f64,
+    accumulated: HashMap<usize, Vec<T>>,
+}
+
+impl<T: Float> AdaGrad<T> {
+    pub fn new(learning_rate: f64) -> Self {
+        AdaGrad { learning_rate, epsilon: 1e-8, accumulated: HashMap::new() }
+    }
+}
+
+impl<T: Float> Optimizer<T> for AdaGrad<T> {
+    fn update(&mut self, id: usize, params: &mut [T], grads: &[T]) {
+        let (learning_rate, epsilon) = (T::from_f64(self.learning_rate), T::from_f64(self.epsilon));
+        let accumulated = state(&mut self.accumulated, id, params.len());
+        for ((p, &g), a) in params.iter_mut().zip(grads).zip(accumulated.iter_mut()) {
+            *a += g * g;
+            *p -= learning_rate * g / (a.sqrt() + epsilon);
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// RMSProp：梯度平方的指数滑动平均
+#[derive(Debug, Clone)]
+pub struct RmsProp<T: Float = f64> {
+    learning_rate: f64,
+    rho: f64,
+    epsilon: f64,
+    mean_square: HashMap<usize, Vec<T>>,
+}
+
+impl<T: Float> RmsProp<T> {
+    pub fn new(learning_rate: f64) -> Self {
+        RmsProp { learning_rate, rho: 0.9, epsilon: 1e-8, mean_square: HashMap::new() }
+    }
+
+    pub fn with_rho(mut self, rho: f64) -> Self {
+        self.rho = rho;
+        self
+    }
+}
+
+impl<T: Float> Optimizer<T> for RmsProp<T> {
+    fn update(&mut self, id: usize, params: &mut [T], grads: &[T]) {
+        let (learning_rate, epsilon) = (T::from_f64(self.learning_rate), T::from_f64(self.epsilon));
+        let (rho, one) = (T::from_f64(self.rho), T::one());
+        let mean_square = state(&mut self.mean_square, id, params.len());
+        for ((p, &g), s) in params.iter_mut().zip(grads).zip(mean_square.iter_mut()) {
+            *s = rho * *s + (one - rho) * g * g;
+            *p -= learning_rate * g / (s.sqrt() + epsilon);
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// Adam：一阶、二阶矩估计加偏差修正
+#[derive(Debug, Clone)]
+pub struct Adam<T: Float = f64> {
+    learning_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    step: i32,
+    first_moment: HashMap<usize, Vec<T>>,
+    second_moment: HashMap<usize, Vec<T>>,
+}
+
+impl<T: Float> Adam<T> {
+    pub fn new(learning_rate: f64) -> Self {
+        Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            step: 0,
+            first_moment: HashMap::new(),
+            second_moment: HashMap::new(),
+        }
+    }
+
+    pub fn with_betas(mut self, beta1: f64, beta2: f64) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+}
+
+impl<T: Float> Optimizer<T> for Adam<T> {
+    fn begin_step(&mut self) {
+        self.step += 1;
+    }
+
+    fn update(&mut self, id: usize, params: &mut [T], grads: &[T]) {
+        // 没调用 begin_step 时按第一步处理
+        let step = self.step.max(1);
+        let bias1 = T::from_f64(1.0 - self.beta1.powi(step));
+        let bias2 = T::from_f64(1.0 - self.beta2.powi(step));
+        let (beta1, beta2, one) = (T::from_f64(self.beta1), T::from_f64(self.beta2), T::one());
+        let (learning_rate, epsilon) = (T::from_f64(self.learning_rate), T::from_f64(self.epsilon));
+        let m = state(&mut self.first_moment, id, params.len());
+        let v = state(&mut self.second_moment, id, params.len());
+        for (((p, &g), m), v) in params.iter_mut().zip(grads).zip(m.iter_mut()).zip(v.iter_mut()) {
+            *m = beta1 * *m + (one - beta1) * g;
+            *v = beta2 * *v + (one - beta2) * g * g;
+            *p -= learning_rate * (*m / bias1) / ((*v / bias2).sqrt() + epsilon);
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+/// AdamW：权重衰减与梯度更新解耦的 Adam，`p -= lr * weight_decay * p` 不经过自适应缩放。
+/// 偏置同样会被衰减。
+#[derive(Debug, Clone)]
+pub struct AdamW<T: Float = f64> {
+    adam: Adam<T>,
+    weight_decay: f64,
+}
+
+impl<T: Float> AdamW<T> {
+    pub fn new(learning_rate: f64, weight_decay: f64) -> Self {
+        AdamW { adam: Adam::new(learning_rate), weight_decay }
+    }
+}
+
+impl<T: Float> Optimizer<T> for AdamW<T> {
+    fn begin_step(&mut self) {
+        self.adam.begin_step();
+    }
+
+    fn update(&mut self, id: usize, params: &mut [T], grads: &[T]) {
+        let decay = T::from_f64(self.adam.learning_rate * self.weight_decay);
+        params.iter_mut().for_each(|p| *p -= decay * *p);
+        self.adam.update(id, params, grads);
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.adam.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.adam.learning_rate = learning_rate;
+    }
+}
+
+/// 梯度裁剪，在优化器更新之前作用于整个网络的梯度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientClip {
+    /// 所有梯度拼成一个向量后的 L2 范数超过阈值时等比缩小
+    Norm(f64),
+    /// 每个梯度分量截断到 [-v, v]
+    Value(f64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Activation;
+    use crate::loss::CategoricalCrossEntropy;
+    use crate::neural_network::NeuralNetwork;
+
+    /// 在 f(x, y) = x^2 + 10 y^2 上跑若干步，返回最终的函数值
+    fn minimize(optimizer: &mut dyn Optimizer, steps: usize) -> f64 {
+        let mut params = [3.0, -2.0];
+        for _ in 0..steps {
+            let grads = [2.0 * params[0], 20.0 * params[1]];
+            optimizer.begin_step();
+            optimizer.update(0, &mut params, &grads);
+        }
+        params[0] * params[0] + 10.0 * params[1] * params[1]
+    }
+
+    #[test]
+    fn test_all_optimizers_descend() {
+        let optimizers: Vec<Box<dyn Optimizer>> = vec![
+            Box::new(Sgd::new(0.01)),
+            Box::new(Sgd::new(0.01).with_momentum(0.9)),
+            Box::new(Sgd::new(0.01).with_nesterov(0.9)),
+            Box::new(AdaGrad::new(0.5)),
+            Box::new(RmsProp::new(0.05)),
+            Box::new(Adam::new(0.1)),
+            Box::new(AdamW::new(0.1, 0.01)),
+        ];
+        for mut optimizer in optimizers {
+            let value = minimize(optimizer.as_mut(), 300);
+            assert!(value < 1e-2, "{:?} ended at {}", optimizer, value);
+        }
+    }
+
+    #[test]
+    fn test_momentum_is_faster_than_plain_sgd() {
+        let plain = minimize(&mut Sgd::new(0.005), 100);
+        let momentum = minimize(&mut Sgd::new(0.005).with_momentum(0.9), 100);
+        assert!(momentum < plain);
+    }
+
+    /// 用同一份初始参数训练两个网络，分别返回全量梯度下降若干轮之后的损失
+    fn compare(
+        build: impl Fn() -> NeuralNetwork,
+        data: &[(Vec<f64>, Vec<f64>)],
+        epochs: usize,
+        optimizers: [&mut dyn Optimizer; 2],
+    ) -> [f64; 2] {
+        let template = build();
+        optimizers.map(|optimizer| {
+            let mut network = build();
+            network.copy_parameters_from(&template);
+            let mut loss = 0.0;
+            for _ in 0..epochs {
+                network.zero_grad();
+                loss = data.iter().map(|(x, y)| {
+                    network.forward(x).unwrap();
+                    network.compute_gradients(y).unwrap()
+                }).sum::<f64>() / data.len() as f64;
+                network.scale_gradients(1.0 / data.len() as f64);
+                network.step(optimizer);
+            }
+            loss
+        })
+    }
+
+    #[test]
+    fn test_adam_converges_faster_on_xor() {
+        let data: Vec<(Vec<f64>, Vec<f64>)> = vec![
+            (vec![0.0, 0.0], vec![1.0, 0.0]),
+            (vec![0.0, 1.0], vec![0.0, 1.0]),
+            (vec![1.0, 0.0], vec![0.0, 1.0]),
+            (vec![1.0, 1.0], vec![1.0, 0.0]),
+        ];
+        let build = || {
+            NeuralNetwork::with_activations(&[2, 8, 2], &[Activation::Tanh, Activation::Softmax])
+                .with_loss(CategoricalCrossEntropy)
+        };
+        let [sgd, adam] = compare(build, &data, 300, [&mut Sgd::new(0.05), &mut Adam::new(0.05)]);
+        assert!(adam < sgd, "adam {} vs sgd {}", adam, sgd);
+        assert!(adam < 0.05, "adam {}", adam);
+    }
+
+    #[test]
+    fn test_adam_converges_faster_on_regression() {
+        // y = sin(x1) + 0.5 * x2，在 [-2, 2]^2 的网格上采样
+        let data: Vec<(Vec<f64>, Vec<f64>)> = (0..9)
+            .flat_map(|i| (0..9).map(move |j| (-2.0 + 0.5 * i as f64, -2.0 + 0.5 * j as f64)))
+            .map(|(x1, x2): (f64, f64)| (vec![x1, x2], vec![x1.sin() + 0.5 * x2]))
+            .collect();
+        let build = || NeuralNetwork::with_activations(&[2, 16, 1], &[Activation::Tanh, Activation::Identity]);
+        let [sgd, adam] = compare(build, &data, 300, [&mut Sgd::new(0.01), &mut Adam::new(0.01)]);
+        assert!(adam < sgd, "adam {} vs sgd {}", adam, sgd);
+    }
+
+    #[test]
+    fn test_gradient_clip() {
+        let mut network = NeuralNetwork::with_activations(&[3, 4, 2], &[Activation::Tanh, Activation::Identity]);
+        network.forward(&[10.0, -10.0, 5.0]).unwrap();
+        network.compute_gradients(&[100.0, -100.0]).unwrap();
+
+        network.clip_gradients(GradientClip::Norm(1.0));
+        assert!((network.gradient_norm() - 1.0).abs() < 1e-9);
+
+        network.clip_gradients(GradientClip::Value(0.01));
+        assert!(network.gradient_norm() <= 0.01 * ((3 * 4 + 4 + 4 * 2 + 2) as f64).sqrt());
+    }
+}
@@ -0,0 +1,901 @@
+//! 模型的保存和加载，支持 JSON 和紧凑的小端二进制两种格式。
+//!
+//! 二进制格式，层的类型、激活函数和形状都用定长的小端整数编码：
+//!
+//! ```text
+//! magic "RNNM" | version: u16 | layer_count: u32 | 每层一条记录
+//! 记录: kind: u8 | 按类型排列的字段
+//!   0 Dense:           activation | input_size: u32 | output_size: u32 | l1: f64 | l2: f64 | weights | biases
+//!   1 Dropout:         shape | rate: f64
+//!   2 BatchNorm:       shape | momentum: f64 | epsilon: f64 | gamma | beta | running_mean | running_var
+//!   3 Conv2D:          input_shape | filters: u32 | kernel_size: u32 | stride: u32 | padding: u32 | activation | weights | biases
+//!   4 Pool2D:          input_shape | mode: u8 (0 max, 1 average) | size: u32 | stride: u32
+//!   5 Flatten:         input_shape
+//!   6 Recurrent:       cell: u8 (0 rnn, 1 lstm, 2 gru) | steps: u32 | features: u32 | units: u32 | return_sequences: u8
+//!                      | bptt_steps: u32 (0 表示不截断) | stateful: u8 | input_weights | hidden_weights | biases
+//!   7 TimeDistributed: steps: u32 | 内层的记录
+//!   8 Rescale:         scale | offset
+//! activation: id: u8 | param: f64        shape: rank: u8 | dims: u32 * rank
+//! 参数数组: len: u32 | f64 * len，矩阵按行优先展开
+//! ```
+//!
+//! 只保存网络结构和参数，损失函数等训练配置不保存。参数一律按 f64 保存，f32 网络加载时再转换。
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::activation::Activation;
+use crate::batch_norm::BatchNorm;
+use crate::conv::{Conv2D, Conv2DSpec};
+use crate::data::Rescale;
+use crate::dense::Dense;
+use crate::dropout::Dropout;
+use crate::flatten::Flatten;
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use crate::pooling::{Pool2D, Pooling};
+use crate::recurrent::{CellKind, Recurrent, RecurrentSpec};
+use crate::time_distributed::TimeDistributed;
+use crate::regularizer::Regularizer;
+
+pub const MAGIC: &[u8; 4] = b"RNNM";
+/// 当前格式版本，结构不兼容时加一
+pub const FORMAT_VERSION: u16 = 1;
+
+#[derive(Error, Debug)]
+pub enum PersistError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("not a model file (bad magic header)")]
+    BadMagic,
+    #[error("unsupported model format version {found}, expected {expected}")]
+    UnsupportedVersion { found: u16, expected: u16 },
+    #[error("unknown activation id {0}")]
+    UnknownActivation(u8),
+    #[error("invalid {what} {value} in binary model")]
+    InvalidField { what: &'static str, value: u32 },
+    #[error("value {0} does not fit in the binary format")]
+    ValueTooLarge(usize),
+    #[error("model file is truncated")]
+    Truncated,
+    #[error("{0} trailing bytes after model data")]
+    TrailingBytes(usize),
+    #[error("layer {layer}: {reason}")]
+    Shape { layer: usize, reason: String },
+    #[error("layer {0} does not support saving")]
+    Unsupported(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ModelRecord {
+    format_version: u16,
+    layers: Vec<LayerRecord>,
+}
+
+/// 一层的结构和参数，参数统一用 f64 保存
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayerRecord {
+    Dense {
+        input_size: usize,
+        output_size: usize,
+        activation: Activation,
+        #[serde(default, skip_serializing_if = "Regularizer::is_none")]
+        regularizer: Regularizer,
+        /// 行优先展开
+        weights: Vec<f64>,
+        biases: Vec<f64>,
+    },
+    Dropout {
+        shape: Shape,
+        rate: f64,
+    },
+    BatchNorm {
+        shape: Shape,
+        momentum: f64,
+        epsilon: f64,
+        gamma: Vec<f64>,
+        beta: Vec<f64>,
+        running_mean: Vec<f64>,
+        running_var: Vec<f64>,
+    },
+    Conv2D {
+        input_shape: Shape,
+        filters: usize,
+        kernel_size: usize,
+        stride: usize,
+        padding: usize,
+        activation: Activation,
+        /// filters x (通道·k·k)，行优先展开
+        weights: Vec<f64>,
+        biases: Vec<f64>,
+    },
+    Pool2D {
+        input_shape: Shape,
+        mode: Pooling,
+        size: usize,
+        stride: usize,
+    },
+    Flatten {
+        input_shape: Shape,
+    },
+    Recurrent {
+        kind: CellKind,
+        steps: usize,
+        features: usize,
+        units: usize,
+        return_sequences: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bptt_steps: Option<usize>,
+        stateful: bool,
+        /// (门数·units) x features，行优先展开
+        input_weights: Vec<f64>,
+        /// (门数·units) x units
+        hidden_weights: Vec<f64>,
+        biases: Vec<f64>,
+    },
+    TimeDistributed {
+        steps: usize,
+        layer: Box<LayerRecord>,
+    },
+    /// 特征缩放 y = x * scale + offset
+    Rescale {
+        scale: Vec<f64>,
+        offset: Vec<f64>,
+    },
+}
+
+impl LayerRecord {
+    /// 检查参数个数后构造层，出错时返回原因
+    pub(crate) fn into_layer<T: Float>(self) -> Result<Box<dyn Layer<T>>, String> {
+        let layer = self.build()?;
+        // 层推算出的形状也来自文件中的尺寸，元素总数同样不能溢出
+        for shape in [layer.input_shape(), layer.output_shape()] {
+            checked_size(&shape)?;
+        }
+        Ok(layer)
+    }
+
+    /// 文件中的尺寸不可信，相乘、相加都要检查溢出
+    fn build<T: Float>(self) -> Result<Box<dyn Layer<T>>, String> {
+        match self {
+            LayerRecord::Dense { input_size, output_size, activation, regularizer, weights, biases } => {
+                if input_size == 0 || output_size == 0 {
+                    return Err("layer sizes must be positive".to_string());
+                }
+                expect_len("weights", input_size.checked_mul(output_size).ok_or_else(overflow)?, weights.len())?;
+                expect_len("biases", output_size, biases.len())?;
+                let weights = Matrix::new(output_size, input_size, convert(weights));
+                Ok(Box::new(Dense::from_parts(weights, convert(biases), activation).with_regularizer(regularizer)))
+            }
+            LayerRecord::Dropout { shape, rate } => {
+                if !(0.0..1.0).contains(&rate) {
+                    return Err(format!("dropout rate must be in [0, 1), found {}", rate));
+                }
+                checked_size(&shape)?;
+                Ok(Box::new(Dropout::new(shape, rate)))
+            }
+            LayerRecord::BatchNorm { shape, momentum, epsilon, gamma, beta, running_mean, running_var } => {
+                if !(0.0..1.0).contains(&momentum) || epsilon <= 0.0 {
+                    return Err(format!("invalid batch norm momentum {} or epsilon {}", momentum, epsilon));
+                }
+                let size = checked_size(&shape)?;
+                expect_len("gamma", size, gamma.len())?;
+                expect_len("beta", size, beta.len())?;
+                expect_len("running_mean", size, running_mean.len())?;
+                expect_len("running_var", size, running_var.len())?;
+                let layer = BatchNorm::from_parts(shape, convert(gamma), convert(beta), convert(running_mean), convert(running_var));
+                Ok(Box::new(layer.with_momentum(momentum).with_epsilon(epsilon)))
+            }
+            LayerRecord::Conv2D { input_shape, filters, kernel_size, stride, padding, activation, weights, biases } => {
+                let (channels, height, width) = input_shape.as_image().ok_or_else(|| format!("expected an image input, found {}", input_shape))?;
+                if [channels, height, width, filters, kernel_size, stride].contains(&0) {
+                    return Err("sizes must be positive".to_string());
+                }
+                checked_size(&input_shape)?;
+                let padded = |len: usize| padding.checked_mul(2).and_then(|pad| len.checked_add(pad)).ok_or_else(overflow);
+                if padded(height)? < kernel_size || padded(width)? < kernel_size {
+                    return Err(format!("kernel {} is larger than the padded input {}", kernel_size, input_shape));
+                }
+                let window = channels.checked_mul(kernel_size).and_then(|n| n.checked_mul(kernel_size)).ok_or_else(overflow)?;
+                expect_len("weights", filters.checked_mul(window).ok_or_else(overflow)?, weights.len())?;
+                expect_len("biases", filters, biases.len())?;
+                let spec = Conv2DSpec::new(filters, kernel_size, activation).with_stride(stride).with_padding(padding);
+                let weights = Matrix::new(filters, window, convert(weights));
+                Ok(Box::new(Conv2D::from_parts(input_shape, spec, weights, convert(biases))))
+            }
+            LayerRecord::Pool2D { input_shape, mode, size, stride } => {
+                let (_, height, width) = input_shape.as_image().ok_or_else(|| format!("expected an image input, found {}", input_shape))?;
+                if size == 0 || stride == 0 || height < size || width < size {
+                    return Err(format!("invalid pool size {} or stride {} for input {}", size, stride, input_shape));
+                }
+                Ok(Box::new(Pool2D::new(input_shape, mode, size, stride)))
+            }
+            LayerRecord::Flatten { input_shape } => {
+                checked_size(&input_shape)?;
+                Ok(Box::new(Flatten::new(input_shape)))
+            }
+            LayerRecord::Recurrent {
+                kind,
+                steps,
+                features,
+                units,
+                return_sequences,
+                bptt_steps,
+                stateful,
+                input_weights,
+                hidden_weights,
+                biases,
+            } => {
+                if [steps, features, units].contains(&0) || bptt_steps == Some(0) {
+                    return Err("sizes must be positive".to_string());
+                }
+                let rows = kind.gates().checked_mul(units).ok_or_else(overflow)?;
+                expect_len("input_weights", rows.checked_mul(features).ok_or_else(overflow)?, input_weights.len())?;
+                expect_len("hidden_weights", rows.checked_mul(units).ok_or_else(overflow)?, hidden_weights.len())?;
+                expect_len("biases", rows, biases.len())?;
+                let spec = RecurrentSpec { return_sequences, bptt_steps, stateful, ..RecurrentSpec::new(kind, units) };
+                let input_weights = Matrix::new(rows, features, convert(input_weights));
+                let hidden_weights = Matrix::new(rows, units, convert(hidden_weights));
+                Ok(Box::new(Recurrent::from_parts(Shape::sequence(steps, features), spec, input_weights, hidden_weights, convert(biases))))
+            }
+            LayerRecord::TimeDistributed { steps, layer } => {
+                if steps == 0 {
+                    return Err("steps must be positive".to_string());
+                }
+                Ok(Box::new(TimeDistributed::from_boxed(steps, layer.into_layer()?)))
+            }
+            LayerRecord::Rescale { scale, offset } => {
+                if scale.is_empty() {
+                    return Err("sizes must be positive".to_string());
+                }
+                expect_len("offset", scale.len(), offset.len())?;
+                if scale.iter().any(|&s| s == 0.0 || !s.is_finite()) {
+                    return Err("scale must be finite and non-zero".to_string());
+                }
+                if offset.iter().any(|o| !o.is_finite()) {
+                    return Err("offset must be finite".to_string());
+                }
+                Ok(Box::new(Rescale::new(scale, offset)))
+            }
+        }
+    }
+}
+
+fn expect_len(what: &str, expected: usize, found: usize) -> Result<(), String> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(format!("expected {} {}, found {}", expected, what, found))
+    }
+}
+
+fn overflow() -> String {
+    "dimensions overflow".to_string()
+}
+
+fn checked_size(shape: &Shape) -> Result<usize, String> {
+    shape.checked_size().ok_or_else(overflow)
+}
+
+fn convert<T: Float>(values: Vec<f64>) -> Vec<T> {
+    values.into_iter().map(T::from_f64).collect()
+}
+
+/// 先只读取版本号，版本不符时报告版本错误而不是结构错误
+#[derive(Deserialize)]
+struct VersionProbe {
+    format_version: u16,
+}
+
+impl ModelRecord {
+    fn from_network<T: Float>(network: &NeuralNetwork<T>) -> Result<Self, PersistError> {
+        let layers = network.layers().iter().enumerate()
+            .map(|(index, layer)| layer.to_record().ok_or(PersistError::Unsupported(index)))
+            .collect::<Result<_, _>>()?;
+        Ok(ModelRecord { format_version: FORMAT_VERSION, layers })
+    }
+
+    /// 检查每层的参数个数以及相邻层的形状，然后构造网络
+    fn into_network<T: Float>(self) -> Result<NeuralNetwork<T>, PersistError> {
+        check_version(self.format_version)?;
+        let mut layers: Vec<Box<dyn Layer<T>>> = Vec::with_capacity(self.layers.len());
+        for (index, record) in self.layers.into_iter().enumerate() {
+            let layer = record.into_layer().map_err(|reason| PersistError::Shape { layer: index, reason })?;
+            if let Some(previous) = layers.last().map(|previous| previous.output_shape()) {
+                if layer.input_shape() != previous {
+                    let reason = format!("input shape {} does not match previous output shape {}", layer.input_shape(), previous);
+                    return Err(PersistError::Shape { layer: index, reason });
+                }
+            }
+            layers.push(layer);
+        }
+        Ok(NeuralNetwork::from_layers(layers))
+    }
+}
+
+fn check_version(found: u16) -> Result<(), PersistError> {
+    if found == FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(PersistError::UnsupportedVersion { found, expected: FORMAT_VERSION })
+    }
+}
+
+fn activation_from_id(id: u8, param: f64) -> Result<Activation, PersistError> {
+    Ok(match id {
+        0 => Activation::Sigmoid,
+        1 => Activation::Tanh,
+        2 => Activation::ReLU,
+        3 => Activation::LeakyReLU(param),
+        4 => Activation::ELU(param),
+        5 => Activation::GELU,
+        6 => Activation::Softmax,
+        7 => Activation::Identity,
+        _ => return Err(PersistError::UnknownActivation(id)),
+    })
+}
+
+fn activation_to_id(activation: Activation) -> (u8, f64) {
+    match activation {
+        Activation::Sigmoid => (0, 0.0),
+        Activation::Tanh => (1, 0.0),
+        Activation::ReLU => (2, 0.0),
+        Activation::LeakyReLU(alpha) => (3, alpha),
+        Activation::ELU(alpha) => (4, alpha),
+        Activation::GELU => (5, 0.0),
+        Activation::Softmax => (6, 0.0),
+        Activation::Identity => (7, 0.0),
+    }
+}
+
+/// 层类型编号
+mod kind {
+    pub const DENSE: u8 = 0;
+    pub const DROPOUT: u8 = 1;
+    pub const BATCH_NORM: u8 = 2;
+    pub const CONV_2D: u8 = 3;
+    pub const POOL_2D: u8 = 4;
+    pub const FLATTEN: u8 = 5;
+    pub const RECURRENT: u8 = 6;
+    pub const TIME_DISTRIBUTED: u8 = 7;
+    pub const RESCALE: u8 = 8;
+}
+
+/// `TimeDistributed` 最多嵌套的层数，防止损坏的文件让读取时递归过深
+const MAX_NESTING: usize = 8;
+
+/// 按顺序写出小端二进制数据
+struct Writer {
+    out: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.out.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// 超出 u32 范围时返回错误，不截断
+    fn u32(&mut self, value: usize) -> Result<(), PersistError> {
+        let value = u32::try_from(value).map_err(|_| PersistError::ValueTooLarge(value))?;
+        self.out.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64_array(&mut self, values: &[f64]) -> Result<(), PersistError> {
+        self.u32(values.len())?;
+        values.iter().for_each(|&value| self.f64(value));
+        Ok(())
+    }
+
+    fn activation(&mut self, activation: Activation) {
+        let (id, param) = activation_to_id(activation);
+        self.u8(id);
+        self.f64(param);
+    }
+
+    fn shape(&mut self, shape: &Shape) -> Result<(), PersistError> {
+        let rank = shape.dims().len();
+        self.u8(u8::try_from(rank).map_err(|_| PersistError::ValueTooLarge(rank))?);
+        shape.dims().iter().try_for_each(|&dim| self.u32(dim))
+    }
+
+    fn layer(&mut self, record: &LayerRecord) -> Result<(), PersistError> {
+        match record {
+            LayerRecord::Dense { input_size, output_size, activation, regularizer, weights, biases } => {
+                self.u8(kind::DENSE);
+                self.activation(*activation);
+                self.u32(*input_size)?;
+                self.u32(*output_size)?;
+                self.f64(regularizer.l1);
+                self.f64(regularizer.l2);
+                self.f64_array(weights)?;
+                self.f64_array(biases)?;
+            }
+            LayerRecord::Dropout { shape, rate } => {
+                self.u8(kind::DROPOUT);
+                self.shape(shape)?;
+                self.f64(*rate);
+            }
+            LayerRecord::BatchNorm { shape, momentum, epsilon, gamma, beta, running_mean, running_var } => {
+                self.u8(kind::BATCH_NORM);
+                self.shape(shape)?;
+                self.f64(*momentum);
+                self.f64(*epsilon);
+                [gamma, beta, running_mean, running_var].into_iter().try_for_each(|array| self.f64_array(array))?;
+            }
+            LayerRecord::Conv2D { input_shape, filters, kernel_size, stride, padding, activation, weights, biases } => {
+                self.u8(kind::CONV_2D);
+                self.shape(input_shape)?;
+                [filters, kernel_size, stride, padding].into_iter().try_for_each(|&value| self.u32(value))?;
+                self.activation(*activation);
+                self.f64_array(weights)?;
+                self.f64_array(biases)?;
+            }
+            LayerRecord::Pool2D { input_shape, mode, size, stride } => {
+                self.u8(kind::POOL_2D);
+                self.shape(input_shape)?;
+                self.u8(match mode {
+                    Pooling::Max => 0,
+                    Pooling::Average => 1,
+                });
+                self.u32(*size)?;
+                self.u32(*stride)?;
+            }
+            LayerRecord::Flatten { input_shape } => {
+                self.u8(kind::FLATTEN);
+                self.shape(input_shape)?;
+            }
+            LayerRecord::Recurrent {
+                kind: cell,
+                steps,
+                features,
+                units,
+                return_sequences,
+                bptt_steps,
+                stateful,
+                input_weights,
+                hidden_weights,
+                biases,
+            } => {
+                self.u8(kind::RECURRENT);
+                self.u8(match cell {
+                    CellKind::Rnn => 0,
+                    CellKind::Lstm => 1,
+                    CellKind::Gru => 2,
+                });
+                [steps, features, units].into_iter().try_for_each(|&value| self.u32(value))?;
+                self.u8(*return_sequences as u8);
+                self.u32(bptt_steps.unwrap_or(0))?;
+                self.u8(*stateful as u8);
+                [input_weights, hidden_weights, biases].into_iter().try_for_each(|array| self.f64_array(array))?;
+            }
+            LayerRecord::TimeDistributed { steps, layer } => {
+                self.u8(kind::TIME_DISTRIBUTED);
+                self.u32(*steps)?;
+                self.layer(layer)?;
+            }
+            LayerRecord::Rescale { scale, offset } => {
+                self.u8(kind::RESCALE);
+                self.f64_array(scale)?;
+                self.f64_array(offset)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 按顺序读取二进制数据
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PersistError> {
+        if self.bytes.len() < len {
+            return Err(PersistError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, PersistError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, PersistError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, PersistError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, PersistError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64_vec(&mut self, len: usize) -> Result<Vec<f64>, PersistError> {
+        // 先检查长度，避免被损坏的文件骗去分配巨大的内存
+        let bytes = self.take(len.checked_mul(8).ok_or(PersistError::Truncated)?)?;
+        Ok(bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect())
+    }
+
+    fn size(&mut self) -> Result<usize, PersistError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn f64_array(&mut self) -> Result<Vec<f64>, PersistError> {
+        let len = self.size()?;
+        self.f64_vec(len)
+    }
+
+    fn bool(&mut self) -> Result<bool, PersistError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(PersistError::InvalidField { what: "flag", value: value as u32 }),
+        }
+    }
+
+    fn activation(&mut self) -> Result<Activation, PersistError> {
+        let id = self.u8()?;
+        activation_from_id(id, self.f64()?)
+    }
+
+    fn shape(&mut self) -> Result<Shape, PersistError> {
+        let rank = self.u8()?;
+        Ok(Shape::new((0..rank).map(|_| self.size()).collect::<Result<_, _>>()?))
+    }
+
+    /// 一条层记录，`depth` 是外面嵌套的层数
+    fn layer(&mut self, depth: usize) -> Result<LayerRecord, PersistError> {
+        Ok(match self.u8()? {
+            kind::DENSE => {
+                let activation = self.activation()?;
+                let (input_size, output_size) = (self.size()?, self.size()?);
+                let regularizer = Regularizer::l1_l2(self.f64()?, self.f64()?);
+                let (weights, biases) = (self.f64_array()?, self.f64_array()?);
+                LayerRecord::Dense { input_size, output_size, activation, regularizer, weights, biases }
+            }
+            kind::DROPOUT => LayerRecord::Dropout { shape: self.shape()?, rate: self.f64()? },
+            kind::BATCH_NORM => LayerRecord::BatchNorm {
+                shape: self.shape()?,
+                momentum: self.f64()?,
+                epsilon: self.f64()?,
+                gamma: self.f64_array()?,
+                beta: self.f64_array()?,
+                running_mean: self.f64_array()?,
+                running_var: self.f64_array()?,
+            },
+            kind::CONV_2D => LayerRecord::Conv2D {
+                input_shape: self.shape()?,
+                filters: self.size()?,
+                kernel_size: self.size()?,
+                stride: self.size()?,
+                padding: self.size()?,
+                activation: self.activation()?,
+                weights: self.f64_array()?,
+                biases: self.f64_array()?,
+            },
+            kind::POOL_2D => LayerRecord::Pool2D {
+                input_shape: self.shape()?,
+                mode: match self.u8()? {
+                    0 => Pooling::Max,
+                    1 => Pooling::Average,
+                    value => return Err(PersistError::InvalidField { what: "pooling mode", value: value as u32 }),
+                },
+                size: self.size()?,
+                stride: self.size()?,
+            },
+            kind::FLATTEN => LayerRecord::Flatten { input_shape: self.shape()? },
+            kind::RECURRENT => LayerRecord::Recurrent {
+                kind: match self.u8()? {
+                    0 => CellKind::Rnn,
+                    1 => CellKind::Lstm,
+                    2 => CellKind::Gru,
+                    value => return Err(PersistError::InvalidField { what: "recurrent cell", value: value as u32 }),
+                },
+                steps: self.size()?,
+                features: self.size()?,
+                units: self.size()?,
+                return_sequences: self.bool()?,
+                bptt_steps: Some(self.size()?).filter(|&steps| steps > 0),
+                stateful: self.bool()?,
+                input_weights: self.f64_array()?,
+                hidden_weights: self.f64_array()?,
+                biases: self.f64_array()?,
+            },
+            kind::TIME_DISTRIBUTED => {
+                if depth >= MAX_NESTING {
+                    return Err(PersistError::InvalidField { what: "nesting depth", value: depth as u32 + 1 });
+                }
+                let steps = self.size()?;
+                LayerRecord::TimeDistributed { steps, layer: Box::new(self.layer(depth + 1)?) }
+            }
+            kind::RESCALE => LayerRecord::Rescale { scale: self.f64_array()?, offset: self.f64_array()? },
+            value => return Err(PersistError::InvalidField { what: "layer kind", value: value as u32 }),
+        })
+    }
+}
+
+impl<T: Float> NeuralNetwork<T> {
+    /// 网络中有不支持保存的层时返回 `Unsupported`
+    pub fn to_json(&self) -> Result<String, PersistError> {
+        Ok(serde_json::to_string_pretty(&ModelRecord::from_network(self)?)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<NeuralNetwork<T>, PersistError> {
+        let VersionProbe { format_version } = serde_json::from_str(json)?;
+        check_version(format_version)?;
+        let record: ModelRecord = serde_json::from_str(json)?;
+        record.into_network()
+    }
+
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<(), PersistError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn load_json<P: AsRef<Path>>(path: P) -> Result<NeuralNetwork<T>, PersistError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PersistError> {
+        let record = ModelRecord::from_network(self)?;
+        let mut writer = Writer { out: MAGIC.to_vec() };
+        writer.u16(record.format_version);
+        writer.u32(record.layers.len())?;
+        record.layers.iter().try_for_each(|layer| writer.layer(layer))?;
+        Ok(writer.out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<NeuralNetwork<T>, PersistError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len()).map_err(|_| PersistError::BadMagic)? != MAGIC {
+            return Err(PersistError::BadMagic);
+        }
+        let format_version = reader.u16()?;
+        check_version(format_version)?;
+        let layer_count = reader.u32()?;
+        let layers = (0..layer_count).map(|_| reader.layer(0)).collect::<Result<_, _>>()?;
+        if !reader.bytes.is_empty() {
+            return Err(PersistError::TrailingBytes(reader.bytes.len()));
+        }
+        ModelRecord { format_version, layers }.into_network()
+    }
+
+    /// 以二进制格式保存
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PersistError> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<NeuralNetwork<T>, PersistError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conv::Conv2DSpec;
+    use crate::layer::Shape;
+    use crate::neural_network::LayerSpec;
+    use crate::recurrent::RecurrentSpec;
+    use crate::sequential::Sequential;
+    use tempfile::NamedTempFile;
+
+    fn network() -> NeuralNetwork {
+        NeuralNetwork::with_activations(
+            &[3, 5, 4, 2],
+            &[Activation::LeakyReLU(0.05), Activation::ELU(0.7), Activation::Softmax],
+        )
+    }
+
+    fn assert_same_predictions(a: &NeuralNetwork, b: &NeuralNetwork) {
+        for input in [[0.1, -0.4, 2.0], [0.0, 0.0, 0.0], [-3.0, 1.5, 0.25]] {
+            let (pa, pb) = (a.predict(&input).unwrap(), b.predict(&input).unwrap());
+            // 逐位相同
+            assert_eq!(pa.iter().map(|v| v.to_bits()).collect::<Vec<_>>(), pb.iter().map(|v| v.to_bits()).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let original = network();
+        let file = NamedTempFile::new().unwrap();
+        original.save_json(file.path()).unwrap();
+        let loaded = NeuralNetwork::load_json(file.path()).unwrap();
+        assert_same_predictions(&original, &loaded);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let original = network();
+        let file = NamedTempFile::new().unwrap();
+        original.save(file.path()).unwrap();
+        let loaded = NeuralNetwork::load(file.path()).unwrap();
+        assert_same_predictions(&original, &loaded);
+        let bytes = original.to_bytes().unwrap();
+        assert_eq!(bytes, loaded.to_bytes().unwrap());
+        // 固定 10 字节；每层 类型 1 + 激活 9 + 尺寸 8 + 正则化 16 + 两个数组的长度 8 字节；然后是参数
+        assert_eq!(bytes.len(), 10 + 3 * 42 + 8 * ((3 * 5 + 5) + (5 * 4 + 4) + (4 * 2 + 2)));
+        assert_eq!(bytes[10], kind::DENSE);
+        assert_eq!((bytes[11], f64::from_le_bytes(bytes[12..20].try_into().unwrap())), (3, 0.05));
+        assert_eq!(u32::from_le_bytes(bytes[20..24].try_into().unwrap()), 3);
+    }
+
+    #[test]
+    fn test_reject_invalid_files() {
+        let bytes = network().to_bytes().unwrap();
+
+        assert!(matches!(NeuralNetwork::<f64>::from_bytes(b"PNG\0abc"), Err(PersistError::BadMagic)));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            NeuralNetwork::<f64>::from_bytes(&wrong_version),
+            Err(PersistError::UnsupportedVersion { found, .. }) if found == FORMAT_VERSION + 1
+        ));
+
+        assert!(matches!(NeuralNetwork::<f64>::from_bytes(&bytes[..bytes.len() - 1]), Err(PersistError::Truncated)));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(NeuralNetwork::<f64>::from_bytes(&trailing), Err(PersistError::TrailingBytes(1))));
+
+        let mut unknown_kind = bytes.clone();
+        unknown_kind[10] = 42;
+        assert!(matches!(
+            NeuralNetwork::<f64>::from_bytes(&unknown_kind),
+            Err(PersistError::InvalidField { what: "layer kind", value: 42 })
+        ));
+
+        // 第二层的输入改成 6，与第一层的输出 5 对不上
+        let mut json: serde_json::Value = serde_json::from_str(&network().to_json().unwrap()).unwrap();
+        json["layers"][1]["input_size"] = 6.into();
+        assert!(matches!(NeuralNetwork::<f64>::from_json(&json.to_string()), Err(PersistError::Shape { layer: 1, .. })));
+
+        let mut json: serde_json::Value = serde_json::from_str(&network().to_json().unwrap()).unwrap();
+        json["layers"][0]["biases"].as_array_mut().unwrap().pop();
+        assert!(matches!(NeuralNetwork::<f64>::from_json(&json.to_string()), Err(PersistError::Shape { layer: 0, .. })));
+    }
+
+    #[test]
+    fn test_regularization_layers_round_trip() {
+        let mut original = Sequential::<f64>::seeded(3, 4)
+            .dense_with(LayerSpec::new(5, Activation::ReLU).with_regularizer(Regularizer::l2(1e-3)))
+            .batch_norm()
+            .dropout(0.3)
+            .dense(2, Activation::Softmax)
+            .build();
+        original.forward_batch(&Matrix::from_rows(&[[1.0, 2.0, 3.0], [-1.0, 0.5, 0.0]])).unwrap();
+
+        let from_json = NeuralNetwork::<f64>::from_json(&original.to_json().unwrap()).unwrap();
+        let from_bytes = NeuralNetwork::<f64>::from_bytes(&original.to_bytes().unwrap()).unwrap();
+        for loaded in [&from_json, &from_bytes] {
+            assert_same_predictions(&original, loaded);
+            let records = |network: &NeuralNetwork| network.layers().iter().map(|layer| layer.to_record()).collect::<Vec<_>>();
+            assert_eq!(records(&original), records(loaded));
+        }
+        original.set_training(false);
+        assert_eq!(original.forward(&[0.2, 0.1, -0.3]).unwrap(), from_json.predict(&[0.2, 0.1, -0.3]).unwrap());
+    }
+
+    #[test]
+    fn test_convolutional_round_trip() {
+        let original = Sequential::<f64>::seeded(Shape::image(1, 5, 5), 8)
+            .conv2d_with(Conv2DSpec::new(2, 3, Activation::ReLU).with_padding(1))
+            .max_pool(2)
+            .flatten()
+            .dense(3, Activation::Softmax)
+            .build();
+        let loaded = NeuralNetwork::<f64>::from_bytes(&original.to_bytes().unwrap()).unwrap();
+        let input: Vec<f64> = (0..25).map(|i| i as f64 / 25.0).collect();
+        assert_eq!(original.predict(&input).unwrap(), loaded.predict(&input).unwrap());
+
+        let mut json: serde_json::Value = serde_json::from_str(&original.to_json().unwrap()).unwrap();
+        json["layers"][0]["kernel_size"] = 7.into();
+        assert!(matches!(NeuralNetwork::<f64>::from_json(&json.to_string()), Err(PersistError::Shape { layer: 0, .. })));
+    }
+
+    #[test]
+    fn test_recurrent_round_trip() {
+        let original = Sequential::<f64>::seeded(Shape::sequence(4, 2), 5)
+            .recurrent(RecurrentSpec::lstm(3).with_return_sequences(true).with_bptt_steps(2))
+            .recurrent(RecurrentSpec::gru(3).with_return_sequences(true).with_stateful(true))
+            .time_distributed_dense(1, Activation::Identity)
+            .build();
+        let loaded = NeuralNetwork::<f64>::from_bytes(&original.to_bytes().unwrap()).unwrap();
+        let input = [0.1, 0.2, -0.3, 0.4, 0.5, -0.6, 0.7, 0.8];
+        assert_eq!(original.predict(&input).unwrap(), loaded.predict(&input).unwrap());
+        assert_eq!(original.to_json().unwrap(), loaded.to_json().unwrap());
+    }
+
+    #[test]
+    fn test_scaler_round_trip() {
+        use crate::data::{Dataset, Scaler, StandardScaler};
+
+        let data = Dataset::new(vec![(vec![1.0, 10.0], vec![0.0]), (vec![3.0, 30.0], vec![1.0])]);
+        let original = Sequential::<f64>::seeded(2, 4)
+            .layer(StandardScaler::fit(&data).unwrap().layer())
+            .dense(1, Activation::Sigmoid)
+            .build();
+        let loaded = NeuralNetwork::<f64>::from_bytes(&original.to_bytes().unwrap()).unwrap();
+        assert_eq!(original.predict(&[2.0, 40.0]).unwrap(), loaded.predict(&[2.0, 40.0]).unwrap());
+        assert_eq!(original.to_json().unwrap(), loaded.to_json().unwrap());
+
+        let nan_offset = LayerRecord::Rescale { scale: vec![1.0], offset: vec![f64::NAN] };
+        assert!(nan_offset.into_layer::<f64>().is_err());
+    }
+
+    #[test]
+    fn test_reject_overflowing_sizes() {
+        let overflow = |json: &serde_json::Value| match NeuralNetwork::<f64>::from_json(&json.to_string()) {
+            Err(PersistError::Shape { layer: 0, reason }) => reason == "dimensions overflow",
+            _ => false,
+        };
+        let mut json: serde_json::Value = serde_json::from_str(&network().to_json().unwrap()).unwrap();
+        json["layers"][0]["input_size"] = usize::MAX.into();
+        json["layers"][0]["output_size"] = usize::MAX.into();
+        assert!(overflow(&json));
+
+        let conv = Sequential::<f64>::seeded(Shape::image(1, 4, 4), 1).conv2d(1, 3, Activation::ReLU).build();
+        let mut json: serde_json::Value = serde_json::from_str(&conv.to_json().unwrap()).unwrap();
+        json["layers"][0]["padding"] = usize::MAX.into();
+        assert!(overflow(&json));
+        let mut json: serde_json::Value = serde_json::from_str(&conv.to_json().unwrap()).unwrap();
+        json["layers"][0]["input_shape"]["dims"] = serde_json::json!([1, usize::MAX, usize::MAX]);
+        assert!(overflow(&json));
+
+        let mut writer = Writer { out: Vec::new() };
+        assert!(matches!(writer.u32(u32::MAX as usize + 1), Err(PersistError::ValueTooLarge(_))));
+        assert!(writer.out.is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_layer() {
+        #[derive(Debug)]
+        struct Double;
+
+        impl Layer for Double {
+            fn input_shape(&self) -> Shape {
+                Shape::vector(2)
+            }
+            fn output_shape(&self) -> Shape {
+                Shape::vector(2)
+            }
+            fn forward(&mut self, input: &Matrix) -> Matrix {
+                self.predict(input)
+            }
+            fn predict(&self, input: &Matrix) -> Matrix {
+                input.map(|x| 2.0 * x)
+            }
+            fn backward(&mut self, output_grad: &Matrix) -> Matrix {
+                output_grad.map(|g| 2.0 * g)
+            }
+        }
+
+        let network = Sequential::<f64>::new(2).dense(2, Activation::Tanh).layer(Double).build();
+        assert!(matches!(network.to_json(), Err(PersistError::Unsupported(1))));
+        assert!(matches!(network.to_bytes(), Err(PersistError::Unsupported(1))));
+    }
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use crate::conv::image_dims;
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pooling {
+    Max,
+    Average,
+}
+
+/// 二维池化层，每个通道单独在 size x size 的窗口内取最大值或平均值，不填充，放不下完整窗口的边缘丢弃
+#[derive(Debug)]
+pub struct Pool2D<T: Float = f64> {
+    mode: Pooling,
+    size: usize,
+    stride: usize,
+    input: (usize, usize, usize),
+    output: (usize, usize, usize),
+    // 最大池化时每个输出元素取自输入样本的哪个下标，每行一个样本
+    argmax: Vec<Vec<usize>>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Float> Pool2D<T> {
+    pub fn new(input: Shape, mode: Pooling, size: usize, stride: usize) -> Pool2D<T> {
+        let (channels, height, width) = image_dims(&input, "Pool2D");
+        assert!(size > 0 && stride > 0, "pool size and stride must be positive");
+        assert!(height >= size && width >= size, "pool size {} is larger than the input {}", size, input);
+        let out_size = |len: usize| (len - size) / stride + 1;
+        Pool2D {
+            mode,
+            size,
+            stride,
+            input: (channels, height, width),
+            output: (channels, out_size(height), out_size(width)),
+            argmax: Vec::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// 步长等于窗口大小的最大池化
+    pub fn max(input: Shape, size: usize) -> Pool2D<T> {
+        Self::new(input, Pooling::Max, size, size)
+    }
+
+    /// 步长等于窗口大小的平均池化
+    pub fn average(input: Shape, size: usize) -> Pool2D<T> {
+        Self::new(input, Pooling::Average, size, size)
+    }
+
+    pub fn mode(&self) -> Pooling {
+        self.mode
+    }
+
+    /// 遍历每个输出元素 (下标, 窗口内各元素在输入样本中的下标)
+    fn for_each_window(&self, mut f: impl FnMut(usize, &[usize])) {
+        let (channels, height, width) = self.input;
+        let (_, out_h, out_w) = self.output;
+        let mut window = Vec::with_capacity(self.size * self.size);
+        for c in 0..channels {
+            for oy in 0..out_h {
+                for ox in 0..out_w {
+                    window.clear();
+                    for ky in 0..self.size {
+                        let row = (c * height + oy * self.stride + ky) * width + ox * self.stride;
+                        window.extend(row..row + self.size);
+                    }
+                    f((c * out_h + oy) * out_w + ox, &window);
+                }
+            }
+        }
+    }
+
+    /// 返回输出和最大池化的 argmax
+    fn pool(&self, input: &Matrix<T>) -> (Matrix<T>, Vec<Vec<usize>>) {
+        let (channels, out_h, out_w) = self.output;
+        let mut output = Matrix::zeros(input.rows(), channels * out_h * out_w);
+        let mut argmax = Vec::new();
+        let count = T::from_f64((self.size * self.size) as f64);
+        for n in 0..input.rows() {
+            let (sample, out) = (input.row(n), output.row_mut(n));
+            let mut indices = Vec::new();
+            self.for_each_window(|index, window| match self.mode {
+                Pooling::Max => {
+                    // 相等时取第一个
+                    let best = window.iter().copied().fold(window[0], |best, i| if sample[i] > sample[best] { i } else { best });
+                    out[index] = sample[best];
+                    indices.push(best);
+                }
+                Pooling::Average => out[index] = window.iter().map(|&i| sample[i]).sum::<T>() / count,
+            });
+            argmax.push(indices);
+        }
+        (output, argmax)
+    }
+}
+
+impl<T: Float> Layer<T> for Pool2D<T> {
+    fn input_shape(&self) -> Shape {
+        Shape::image(self.input.0, self.input.1, self.input.2)
+    }
+
+    fn output_shape(&self) -> Shape {
+        Shape::image(self.output.0, self.output.1, self.output.2)
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let (output, argmax) = self.pool(input);
+        self.argmax = argmax;
+        output
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        self.pool(input).0
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        // 最大池化的梯度只回到取到最大值的元素，平均池化平均分给窗口内的元素
+        let (channels, height, width) = self.input;
+        let mut input_grad = Matrix::zeros(output_grad.rows(), channels * height * width);
+        let count = T::from_f64((self.size * self.size) as f64);
+        for n in 0..output_grad.rows() {
+            let (grad, sample_grad) = (output_grad.row(n), input_grad.row_mut(n));
+            match self.mode {
+                Pooling::Max => {
+                    for (index, &source) in self.argmax[n].iter().enumerate() {
+                        sample_grad[source] += grad[index];
+                    }
+                }
+                Pooling::Average => self.for_each_window(|index, window| {
+                    window.iter().for_each(|&i| sample_grad[i] += grad[index] / count);
+                }),
+            }
+        }
+        input_grad
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        Some(LayerRecord::Pool2D { input_shape: self.input_shape(), mode: self.mode, size: self.size, stride: self.stride })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_and_average() {
+        // 1 x 4 x 4，值为 0..16
+        let input = Matrix::from_fn(1, 16, |_, i| i as f64);
+        let mut max = Pool2D::max(Shape::image(1, 4, 4), 2);
+        assert_eq!(max.forward(&input).as_slice(), &[5.0, 7.0, 13.0, 15.0]);
+        let grad = max.backward(&Matrix::new(1, 4, vec![1.0, 2.0, 3.0, 4.0]));
+        let mut expected = [0.0; 16];
+        expected[5] = 1.0;
+        expected[7] = 2.0;
+        expected[13] = 3.0;
+        expected[15] = 4.0;
+        assert_eq!(grad.as_slice(), &expected);
+
+        let mut average = Pool2D::new(Shape::image(1, 4, 4), Pooling::Average, 3, 1);
+        assert_eq!(average.output_shape(), Shape::image(1, 2, 2));
+        assert_eq!(average.forward(&input).as_slice(), &[5.0, 6.0, 9.0, 10.0]);
+        // 中间四个元素被四个窗口共享
+        let grad = average.backward(&Matrix::new(1, 4, vec![9.0; 4]));
+        assert_eq!(grad[(0, 5)], 4.0);
+        assert_eq!(grad[(0, 0)], 1.0);
+    }
+}
@@ -0,0 +1,478 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::activation::sigmoid;
+use crate::float::Float;
+use crate::init::Initializer;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// 循环单元的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellKind {
+    /// h = tanh(x W^T + h' U^T + b)
+    Rnn,
+    /// 输入门 i、遗忘门 f、候选值 g、输出门 o：c = f * c' + i * g，h = o * tanh(c)
+    Lstm,
+    /// 更新门 z、重置门 r、候选值 n = tanh(x Wn^T + (r * h') Un^T + bn)：h = (1 - z) * n + z * h'
+    Gru,
+}
+
+impl CellKind {
+    /// 门的个数，参数矩阵按门分块，顺序见各变体的说明
+    pub(crate) fn gates(self) -> usize {
+        match self {
+            CellKind::Rnn => 1,
+            CellKind::Lstm => 4,
+            CellKind::Gru => 3,
+        }
+    }
+}
+
+/// 循环层的配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecurrentSpec {
+    pub kind: CellKind,
+    pub units: usize,
+    /// 输出每个时间步的隐状态（多对多），默认只输出最后一步（多对一）
+    pub return_sequences: bool,
+    /// 截断 BPTT：梯度沿时间最多反向传播这么多步（从序列末尾起分段），默认不截断
+    pub bptt_steps: Option<usize>,
+    /// 每次 `forward` 从上一次结束时的状态继续，默认每个序列从 0 开始
+    pub stateful: bool,
+    /// 输入和循环权重的初始化方式，默认 Xavier 均匀分布
+    pub weight_init: Initializer,
+}
+
+impl RecurrentSpec {
+    pub fn new(kind: CellKind, units: usize) -> Self {
+        RecurrentSpec {
+            kind,
+            units,
+            return_sequences: false,
+            bptt_steps: None,
+            stateful: false,
+            weight_init: Initializer::XavierUniform,
+        }
+    }
+
+    pub fn rnn(units: usize) -> Self {
+        Self::new(CellKind::Rnn, units)
+    }
+
+    pub fn lstm(units: usize) -> Self {
+        Self::new(CellKind::Lstm, units)
+    }
+
+    pub fn gru(units: usize) -> Self {
+        Self::new(CellKind::Gru, units)
+    }
+
+    pub fn with_return_sequences(mut self, return_sequences: bool) -> Self {
+        self.return_sequences = return_sequences;
+        self
+    }
+
+    pub fn with_bptt_steps(mut self, steps: usize) -> Self {
+        assert!(steps > 0, "bptt steps must be positive");
+        self.bptt_steps = Some(steps);
+        self
+    }
+
+    pub fn with_stateful(mut self, stateful: bool) -> Self {
+        self.stateful = stateful;
+        self
+    }
+
+    pub fn with_weight_init(mut self, init: Initializer) -> Self {
+        self.weight_init = init;
+        self
+    }
+}
+
+// 一个时间步的缓存，每行一个样本
+#[derive(Debug, Clone)]
+struct Step<T: Float> {
+    x: Matrix<T>,
+    h_prev: Matrix<T>,
+    c_prev: Matrix<T>,
+    // 激活后的各门，按门分块
+    gates: Matrix<T>,
+    // LSTM 的细胞状态，其他单元与 h 相同
+    c: Matrix<T>,
+    h: Matrix<T>,
+    // GRU 的 r * h'
+    reset_hidden: Matrix<T>,
+}
+
+/// 循环层，输入是 时间步 x 特征 的序列。
+///
+/// 输出 `[时间步 x units]`（`return_sequences`）或最后一步的 `[units]`。
+/// 反向传播按时间展开（BPTT），可以用 `bptt_steps` 截断。stateful 模式下上一次 `forward`
+/// 结束时的状态作为下一次的初始状态（批大小不变时），`predict` 也从这个状态开始但不更新它，
+/// 梯度不会传到上一次调用。
+#[derive(Debug)]
+pub struct Recurrent<T: Float = f64> {
+    kind: CellKind,
+    steps: usize,
+    features: usize,
+    units: usize,
+    return_sequences: bool,
+    bptt_steps: Option<usize>,
+    stateful: bool,
+    // (门数·units) x features
+    input_weights: Matrix<T>,
+    // (门数·units) x units
+    hidden_weights: Matrix<T>,
+    biases: Vec<T>,
+    input_weight_grads: Matrix<T>,
+    hidden_weight_grads: Matrix<T>,
+    bias_grads: Vec<T>,
+    cache: Vec<Step<T>>,
+    // stateful 模式下保留的 (h, c)
+    state: Option<(Matrix<T>, Matrix<T>)>,
+}
+
+impl<T: Float> Recurrent<T> {
+    /// LSTM 的遗忘门偏置初始化为 1，其余偏置为 0
+    pub fn new<R: Rng + ?Sized>(input: Shape, spec: RecurrentSpec, rng: &mut R) -> Recurrent<T> {
+        let (_, features) = sequence_dims(&input);
+        let rows = spec.kind.gates() * spec.units;
+        let mut sample = |fan_in: usize, cols: usize| {
+            Matrix::from_fn(rows, cols, |_, _| T::from_f64(spec.weight_init.sample(fan_in, spec.units, rng)))
+        };
+        let input_weights = sample(features, features);
+        let hidden_weights = sample(spec.units, spec.units);
+        let biases = (0..rows).map(|i| {
+            let forget_gate = spec.kind == CellKind::Lstm && i / spec.units == 1;
+            if forget_gate { T::one() } else { T::zero() }
+        }).collect();
+        Self::from_parts(input, spec, input_weights, hidden_weights, biases)
+    }
+
+    /// 用已有的参数构造，`spec` 中的初始化方式不起作用
+    pub fn from_parts(input: Shape, spec: RecurrentSpec, input_weights: Matrix<T>, hidden_weights: Matrix<T>, biases: Vec<T>) -> Recurrent<T> {
+        let (steps, features) = sequence_dims(&input);
+        assert!(steps > 0 && spec.units > 0, "steps and units must be positive");
+        let rows = spec.kind.gates() * spec.units;
+        assert_eq!(input_weights.shape(), (rows, features), "input weights must be (gates * units) x features");
+        assert_eq!(hidden_weights.shape(), (rows, spec.units), "hidden weights must be (gates * units) x units");
+        assert_eq!(biases.len(), rows, "one bias per gate unit is required");
+        Recurrent {
+            kind: spec.kind,
+            steps,
+            features,
+            units: spec.units,
+            return_sequences: spec.return_sequences,
+            bptt_steps: spec.bptt_steps,
+            stateful: spec.stateful,
+            input_weight_grads: Matrix::zeros(rows, features),
+            hidden_weight_grads: Matrix::zeros(rows, spec.units),
+            bias_grads: vec![T::zero(); rows],
+            input_weights,
+            hidden_weights,
+            biases,
+            cache: Vec::new(),
+            state: None,
+        }
+    }
+
+    pub fn kind(&self) -> CellKind {
+        self.kind
+    }
+
+    pub fn units(&self) -> usize {
+        self.units
+    }
+
+    /// 初始状态：stateful 且批大小与上次相同时沿用上次的状态，否则全 0
+    fn initial_state(&self, rows: usize) -> (Matrix<T>, Matrix<T>) {
+        match &self.state {
+            Some((h, c)) if self.stateful && h.rows() == rows => (h.clone(), c.clone()),
+            _ => (Matrix::zeros(rows, self.units), Matrix::zeros(rows, self.units)),
+        }
+    }
+
+    /// 依次计算每个时间步，返回各步的缓存
+    fn run(&self, input: &Matrix<T>) -> Vec<Step<T>> {
+        let (mut h, mut c) = self.initial_state(input.rows());
+        let mut steps = Vec::with_capacity(self.steps);
+        for t in 0..self.steps {
+            let x = columns(input, t * self.features, self.features);
+            let step = self.step(x, h, c);
+            (h, c) = (step.h.clone(), step.c.clone());
+            steps.push(step);
+        }
+        steps
+    }
+
+    fn output(&self, steps: &[Step<T>]) -> Matrix<T> {
+        let h = self.units;
+        if self.return_sequences {
+            Matrix::from_fn(steps[0].h.rows(), self.steps * h, |n, col| steps[col / h].h[(n, col % h)])
+        } else {
+            steps[steps.len() - 1].h.clone()
+        }
+    }
+
+    fn step(&self, x: Matrix<T>, h_prev: Matrix<T>, c_prev: Matrix<T>) -> Step<T> {
+        let (rows, h) = (x.rows(), self.units);
+        let mut a = x.matmul(&self.input_weights.transpose());
+        a.add_row_vector(&self.biases);
+        let one = T::one();
+        match self.kind {
+            CellKind::Rnn => {
+                a.add_assign(&h_prev.matmul(&self.hidden_weights.transpose()));
+                let out = a.map(|v| v.tanh());
+                Step { x, h_prev, c_prev, gates: out.clone(), c: out.clone(), h: out, reset_hidden: Matrix::zeros(0, 0) }
+            }
+            CellKind::Lstm => {
+                a.add_assign(&h_prev.matmul(&self.hidden_weights.transpose()));
+                let gates = Matrix::from_fn(rows, 4 * h, |n, col| if col / h == 2 { a[(n, col)].tanh() } else { sigmoid(a[(n, col)]) });
+                let c = Matrix::from_fn(rows, h, |n, j| gates[(n, h + j)] * c_prev[(n, j)] + gates[(n, j)] * gates[(n, 2 * h + j)]);
+                let out = Matrix::from_fn(rows, h, |n, j| gates[(n, 3 * h + j)] * c[(n, j)].tanh());
+                Step { x, h_prev, c_prev, gates, c, h: out, reset_hidden: Matrix::zeros(0, 0) }
+            }
+            CellKind::Gru => {
+                let hidden = h_prev.matmul(&row_block(&self.hidden_weights, 0, 2 * h).transpose());
+                let update_reset = Matrix::from_fn(rows, 2 * h, |n, col| sigmoid(a[(n, col)] + hidden[(n, col)]));
+                let reset_hidden = Matrix::from_fn(rows, h, |n, j| update_reset[(n, h + j)] * h_prev[(n, j)]);
+                let candidate_hidden = reset_hidden.matmul(&row_block(&self.hidden_weights, 2 * h, h).transpose());
+                let gates = Matrix::from_fn(rows, 3 * h, |n, col| {
+                    if col < 2 * h { update_reset[(n, col)] } else { (a[(n, col)] + candidate_hidden[(n, col - 2 * h)]).tanh() }
+                });
+                let out = Matrix::from_fn(rows, h, |n, j| {
+                    let z = gates[(n, j)];
+                    (one - z) * gates[(n, 2 * h + j)] + z * h_prev[(n, j)]
+                });
+                Step { x, h_prev, c_prev, gates, c: out.clone(), h: out, reset_hidden }
+            }
+        }
+    }
+
+    /// 一个时间步的反向传播，`dh`、`dc` 是损失对本步 h、c 的梯度，返回 (dx, dh', dc')
+    fn backward_step(&mut self, step: &Step<T>, dh: &Matrix<T>, dc: &Matrix<T>) -> (Matrix<T>, Matrix<T>, Matrix<T>) {
+        let (rows, h) = (dh.rows(), self.units);
+        let one = T::one();
+        let gates = &step.gates;
+        // da 是损失对各门激活前值的梯度
+        let (da, dh_prev, dc_prev) = match self.kind {
+            CellKind::Rnn => {
+                let da = dh.zip_map(&step.h, |g, y| g * (one - y * y));
+                self.hidden_weight_grads.add_assign(&da.transpose().matmul(&step.h_prev));
+                let dh_prev = da.matmul(&self.hidden_weights);
+                (da, dh_prev, Matrix::zeros(rows, h))
+            }
+            CellKind::Lstm => {
+                // 对 c 的总梯度：来自下一步的 dc 加上经 h = o * tanh(c) 传来的部分
+                let dc_total = Matrix::from_fn(rows, h, |n, j| {
+                    let tc = step.c[(n, j)].tanh();
+                    dc[(n, j)] + dh[(n, j)] * gates[(n, 3 * h + j)] * (one - tc * tc)
+                });
+                let da = Matrix::from_fn(rows, 4 * h, |n, col| {
+                    let (gate, j) = (col / h, col % h);
+                    let y = gates[(n, col)];
+                    match gate {
+                        0 => dc_total[(n, j)] * gates[(n, 2 * h + j)] * y * (one - y),
+                        1 => dc_total[(n, j)] * step.c_prev[(n, j)] * y * (one - y),
+                        2 => dc_total[(n, j)] * gates[(n, j)] * (one - y * y),
+                        _ => dh[(n, j)] * step.c[(n, j)].tanh() * y * (one - y),
+                    }
+                });
+                let dc_prev = Matrix::from_fn(rows, h, |n, j| dc_total[(n, j)] * gates[(n, h + j)]);
+                self.hidden_weight_grads.add_assign(&da.transpose().matmul(&step.h_prev));
+                let dh_prev = da.matmul(&self.hidden_weights);
+                (da, dh_prev, dc_prev)
+            }
+            CellKind::Gru => {
+                let da_candidate = Matrix::from_fn(rows, h, |n, j| {
+                    let candidate = gates[(n, 2 * h + j)];
+                    dh[(n, j)] * (one - gates[(n, j)]) * (one - candidate * candidate)
+                });
+                let candidate_weights = row_block(&self.hidden_weights, 2 * h, h);
+                let d_reset_hidden = da_candidate.matmul(&candidate_weights);
+                let da_update_reset = Matrix::from_fn(rows, 2 * h, |n, col| {
+                    let (j, y) = (col % h, gates[(n, col)]);
+                    let grad = if col < h {
+                        dh[(n, j)] * (step.h_prev[(n, j)] - gates[(n, 2 * h + j)])
+                    } else {
+                        d_reset_hidden[(n, j)] * step.h_prev[(n, j)]
+                    };
+                    grad * y * (one - y)
+                });
+                add_row_block(&mut self.hidden_weight_grads, 0, &da_update_reset.transpose().matmul(&step.h_prev));
+                add_row_block(&mut self.hidden_weight_grads, 2 * h, &da_candidate.transpose().matmul(&step.reset_hidden));
+
+                let mut dh_prev = da_update_reset.matmul(&row_block(&self.hidden_weights, 0, 2 * h));
+                dh_prev.add_assign(&Matrix::from_fn(rows, h, |n, j| {
+                    dh[(n, j)] * gates[(n, j)] + d_reset_hidden[(n, j)] * gates[(n, h + j)]
+                }));
+                let da = Matrix::from_fn(rows, 3 * h, |n, col| {
+                    if col < 2 * h { da_update_reset[(n, col)] } else { da_candidate[(n, col - 2 * h)] }
+                });
+                (da, dh_prev, Matrix::zeros(rows, h))
+            }
+        };
+        self.input_weight_grads.add_assign(&da.transpose().matmul(&step.x));
+        self.bias_grads.iter_mut().zip(da.sum_rows()).for_each(|(g, d)| *g += d);
+        (da.matmul(&self.input_weights), dh_prev, dc_prev)
+    }
+}
+
+/// 二维形状的 (时间步, 特征)，否则 panic
+fn sequence_dims(shape: &Shape) -> (usize, usize) {
+    shape.as_sequence().unwrap_or_else(|| panic!("recurrent layers expect a steps x features input, got {}", shape))
+}
+
+/// 第 start 列起的 len 列
+fn columns<T: Float>(m: &Matrix<T>, start: usize, len: usize) -> Matrix<T> {
+    Matrix::from_fn(m.rows(), len, |row, col| m[(row, start + col)])
+}
+
+/// 第 start 行起的 len 行
+fn row_block<T: Float>(m: &Matrix<T>, start: usize, len: usize) -> Matrix<T> {
+    Matrix::new(len, m.cols(), m.as_slice()[start * m.cols()..(start + len) * m.cols()].to_vec())
+}
+
+fn add_row_block<T: Float>(m: &mut Matrix<T>, start: usize, block: &Matrix<T>) {
+    let cols = m.cols();
+    m.as_mut_slice()[start * cols..].iter_mut().zip(block.as_slice()).for_each(|(v, &b)| *v += b);
+}
+
+impl<T: Float> Layer<T> for Recurrent<T> {
+    fn input_shape(&self) -> Shape {
+        Shape::sequence(self.steps, self.features)
+    }
+
+    fn output_shape(&self) -> Shape {
+        if self.return_sequences {
+            Shape::sequence(self.steps, self.units)
+        } else {
+            Shape::vector(self.units)
+        }
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let steps = self.run(input);
+        let output = self.output(&steps);
+        if self.stateful {
+            let last = &steps[steps.len() - 1];
+            self.state = Some((last.h.clone(), last.c.clone()));
+        }
+        self.cache = steps;
+        output
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        self.output(&self.run(input))
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        let cache = std::mem::take(&mut self.cache);
+        let (rows, h) = (output_grad.rows(), self.units);
+        let mut input_grad = Matrix::zeros(rows, self.steps * self.features);
+        let (mut dh_next, mut dc_next) = (Matrix::zeros(rows, h), Matrix::zeros(rows, h));
+        for (t, step) in cache.iter().enumerate().rev() {
+            let mut dh = dh_next;
+            if self.return_sequences {
+                dh.add_assign(&columns(output_grad, t * h, h));
+            } else if t == self.steps - 1 {
+                dh.add_assign(output_grad);
+            }
+            let (dx, dh_prev, dc_prev) = self.backward_step(step, &dh, &dc_next);
+            for n in 0..rows {
+                input_grad.row_mut(n)[t * self.features..(t + 1) * self.features].copy_from_slice(dx.row(n));
+            }
+            // 截断：每 bptt_steps 步切断一次沿时间的梯度
+            let truncated = self.bptt_steps.is_some_and(|k| (self.steps - t).is_multiple_of(k));
+            (dh_next, dc_next) = if truncated { (Matrix::zeros(rows, h), Matrix::zeros(rows, h)) } else { (dh_prev, dc_prev) };
+        }
+        self.cache = cache;
+        input_grad
+    }
+
+    /// 三组参数：输入权重、循环权重和偏置，都按门分块
+    fn parameters(&self) -> Vec<&[T]> {
+        vec![self.input_weights.as_slice(), self.hidden_weights.as_slice(), &self.biases]
+    }
+
+    fn gradients(&self) -> Vec<&[T]> {
+        vec![self.input_weight_grads.as_slice(), self.hidden_weight_grads.as_slice(), &self.bias_grads]
+    }
+
+    fn visit_parameters(&mut self, f: &mut dyn FnMut(&mut [T], &mut [T])) {
+        f(self.input_weights.as_mut_slice(), self.input_weight_grads.as_mut_slice());
+        f(self.hidden_weights.as_mut_slice(), self.hidden_weight_grads.as_mut_slice());
+        f(&mut self.biases, &mut self.bias_grads);
+    }
+
+    fn reset_state(&mut self) {
+        self.state = None;
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        let to_f64 = |values: &[T]| values.iter().map(|v| v.to_f64()).collect();
+        Some(LayerRecord::Recurrent {
+            kind: self.kind,
+            steps: self.steps,
+            features: self.features,
+            units: self.units,
+            return_sequences: self.return_sequences,
+            bptt_steps: self.bptt_steps,
+            stateful: self.stateful,
+            input_weights: to_f64(self.input_weights.as_slice()),
+            hidden_weights: to_f64(self.hidden_weights.as_slice()),
+            biases: to_f64(&self.biases),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn sequence(rows: usize, steps: usize, features: usize) -> Matrix {
+        Matrix::from_fn(rows, steps * features, |n, i| ((n * 31 + i) as f64 * 0.53).sin())
+    }
+
+    #[test]
+    fn test_stateful_continues_sequence() {
+        for kind in [CellKind::Rnn, CellKind::Lstm, CellKind::Gru] {
+            let spec = RecurrentSpec::new(kind, 3);
+            let full = Recurrent::<f64>::new(Shape::sequence(4, 2), spec, &mut StdRng::seed_from_u64(1));
+            let parts = |m: &Recurrent| (m.input_weights.clone(), m.hidden_weights.clone(), m.biases.clone());
+            let (w, u, b) = parts(&full);
+            let fresh = Recurrent::from_parts(Shape::sequence(2, 2), spec, w.clone(), u.clone(), b.clone());
+            let mut half = Recurrent::from_parts(Shape::sequence(2, 2), spec.with_stateful(true), w, u, b);
+
+            let input = sequence(2, 4, 2);
+            half.forward(&columns(&input, 0, 4));
+            // predict 从保留的状态开始，但不更新它
+            let second = columns(&input, 4, 4);
+            let predicted = half.predict(&second);
+            assert_eq!(half.forward(&second), predicted);
+            assert!(full.predict(&input).zip_map(&predicted, |a, b| a - b).as_slice().iter().all(|d| d.abs() < 1e-12));
+
+            half.reset_state();
+            assert_eq!(half.forward(&second), fresh.predict(&second));
+        }
+    }
+
+    #[test]
+    fn test_truncated_bptt() {
+        let spec = RecurrentSpec::rnn(4).with_bptt_steps(2);
+        let mut layer = Recurrent::<f64>::new(Shape::sequence(5, 3), spec, &mut StdRng::seed_from_u64(2));
+        let input = sequence(2, 5, 3);
+        layer.forward(&input);
+        let grad = layer.backward(&Matrix::from_fn(2, 4, |_, _| 1.0));
+        // 多对一、截断为 2：只有最后两步的输入有梯度
+        for n in 0..2 {
+            assert!(grad.row(n)[..9].iter().all(|&g| g == 0.0));
+            assert!(grad.row(n)[9..].iter().any(|&g| g != 0.0));
+        }
+    }
+}
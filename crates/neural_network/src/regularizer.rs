@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::autodiff::Var;
+use crate::float::Float;
+
+/// 权重的 L1/L2 惩罚：penalty = l1 * Σ|w| + l2 * Σw²，只作用于权重，不作用于偏置
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Regularizer {
+    pub l1: f64,
+    pub l2: f64,
+}
+
+impl Regularizer {
+    pub fn l1(l1: f64) -> Regularizer {
+        Regularizer { l1, l2: 0.0 }
+    }
+
+    pub fn l2(l2: f64) -> Regularizer {
+        Regularizer { l1: 0.0, l2 }
+    }
+
+    pub fn l1_l2(l1: f64, l2: f64) -> Regularizer {
+        Regularizer { l1, l2 }
+    }
+
+    /// 两个系数都是 0
+    pub fn is_none(&self) -> bool {
+        self.l1 == 0.0 && self.l2 == 0.0
+    }
+
+    pub fn penalty<T: Float>(&self, weights: &[T]) -> f64 {
+        weights.iter().map(|w| {
+            let w = w.to_f64();
+            self.l1 * w.abs() + self.l2 * w * w
+        }).sum()
+    }
+
+    /// 在求导带上写出 `penalty`
+    pub fn penalty_on_tape<'t, T: Float>(&self, weights: Var<'t, T>) -> Var<'t, T> {
+        weights.abs().sum().scale(T::from_f64(self.l1)) + (weights * weights).sum().scale(T::from_f64(self.l2))
+    }
+
+    /// 把 `scale` 倍的惩罚梯度 l1 * sign(w) + 2 * l2 * w 加到 `grads` 上
+    pub fn add_gradient<T: Float>(&self, weights: &[T], grads: &mut [T], scale: f64) {
+        if self.is_none() {
+            return;
+        }
+        let (l1, l2) = (T::from_f64(self.l1 * scale), T::from_f64(2.0 * self.l2 * scale));
+        for (g, &w) in grads.iter_mut().zip(weights) {
+            // sign(0) 取 0
+            let sign = if w > T::zero() { T::one() } else if w < T::zero() { -T::one() } else { T::zero() };
+            *g += l1 * sign + l2 * w;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_penalty_and_gradient() {
+        let regularizer = Regularizer::l1_l2(0.1, 0.5);
+        let weights = [2.0, -1.0, 0.0];
+        assert!((regularizer.penalty(&weights) - (0.1 * 3.0 + 0.5 * 5.0)).abs() < 1e-12);
+
+        let mut grads = [1.0, 1.0, 1.0];
+        regularizer.add_gradient(&weights, &mut grads, 2.0);
+        let expected = [1.0 + 0.2 + 4.0, 1.0 - 0.2 - 2.0, 1.0];
+        assert!(grads.iter().zip(expected).all(|(g, e)| (g - e).abs() < 1e-12), "{:?}", grads);
+        assert!(Regularizer::default().is_none());
+    }
+}
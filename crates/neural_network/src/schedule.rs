@@ -0,0 +1,294 @@
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+/// 训练进度，交给学习率调度计算当前的学习率
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// 之前已经做过的参数更新次数
+    pub step: usize,
+    /// 以轮为单位的进度：当前轮号加上本轮已完成批次的比例
+    pub epoch: f64,
+}
+
+/// 学习率调度：训练器在每次参数更新之前调用 [`LrSchedule::learning_rate`]，
+/// 结果通过 [`Optimizer::set_learning_rate`](crate::optimizer::Optimizer::set_learning_rate) 交给优化器。
+///
+/// `base` 是开始训练时优化器的学习率。每轮结束时调用 [`LrSchedule::on_epoch_end`]，
+/// 依赖验证损失的调度（如 [`ReduceOnPlateau`]）在这里更新自己的状态，
+/// 每次 `fit` 开始时调用 [`LrSchedule::reset`] 清除上一次训练留下的状态。
+pub trait LrSchedule: Debug + Send {
+    fn learning_rate(&self, base: f64, progress: Progress) -> f64;
+
+    /// `monitored` 在有验证集时为验证损失，否则为训练损失
+    fn on_epoch_end(&mut self, _monitored: f64) {}
+
+    /// 回到刚创建时的状态
+    fn reset(&mut self) {}
+}
+
+impl<S: LrSchedule + ?Sized> LrSchedule for Box<S> {
+    fn learning_rate(&self, base: f64, progress: Progress) -> f64 {
+        (**self).learning_rate(base, progress)
+    }
+
+    fn on_epoch_end(&mut self, monitored: f64) {
+        (**self).on_epoch_end(monitored)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
+
+/// 学习率保持不变，一般作为 [`LinearWarmup`] 之后的调度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant;
+
+impl LrSchedule for Constant {
+    fn learning_rate(&self, base: f64, _progress: Progress) -> f64 {
+        base
+    }
+}
+
+/// 每 `step_size` 轮乘一次 `gamma`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepDecay {
+    pub step_size: usize,
+    pub gamma: f64,
+}
+
+impl StepDecay {
+    pub fn new(step_size: usize, gamma: f64) -> Self {
+        assert!(step_size > 0, "step size must be positive");
+        StepDecay { step_size, gamma }
+    }
+}
+
+impl LrSchedule for StepDecay {
+    fn learning_rate(&self, base: f64, progress: Progress) -> f64 {
+        let decays = (progress.epoch / self.step_size as f64).floor();
+        base * self.gamma.powi(decays as i32)
+    }
+}
+
+/// base * gamma^epoch，轮内按批次连续衰减
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialDecay {
+    pub gamma: f64,
+}
+
+impl ExponentialDecay {
+    pub fn new(gamma: f64) -> Self {
+        assert!(gamma > 0.0, "decay rate must be positive");
+        ExponentialDecay { gamma }
+    }
+}
+
+impl LrSchedule for ExponentialDecay {
+    fn learning_rate(&self, base: f64, progress: Progress) -> f64 {
+        base * self.gamma.powf(progress.epoch)
+    }
+}
+
+/// 带重启的余弦退火（SGDR）：每个周期内学习率沿余弦曲线从 base 降到 `min_lr`，然后重新开始。
+/// 第一个周期长 `period` 轮，之后每个周期的长度乘以 `period_mult`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CosineAnnealing {
+    pub period: f64,
+    pub period_mult: f64,
+    pub min_lr: f64,
+}
+
+impl CosineAnnealing {
+    /// 默认周期长度不变、最低降到 0
+    pub fn new(period: f64) -> Self {
+        assert!(period > 0.0, "period must be positive");
+        CosineAnnealing { period, period_mult: 1.0, min_lr: 0.0 }
+    }
+
+    pub fn with_period_mult(mut self, period_mult: f64) -> Self {
+        assert!(period_mult >= 1.0, "period multiplier must be at least 1");
+        self.period_mult = period_mult;
+        self
+    }
+
+    pub fn with_min_lr(mut self, min_lr: f64) -> Self {
+        self.min_lr = min_lr;
+        self
+    }
+}
+
+impl LrSchedule for CosineAnnealing {
+    fn learning_rate(&self, base: f64, progress: Progress) -> f64 {
+        // 找到当前所在的周期，以及在周期内的位置
+        let (mut position, mut period) = (progress.epoch, self.period);
+        if self.period_mult == 1.0 {
+            position %= period;
+        } else {
+            while position >= period {
+                position -= period;
+                period *= self.period_mult;
+            }
+        }
+        self.min_lr + (base - self.min_lr) * (1.0 + (PI * position / period).cos()) / 2.0
+    }
+}
+
+/// 前 `steps` 次更新把学习率从 base / steps 线性升到 base，之后交给 `after`。
+/// `after` 看到的进度不扣除预热的部分
+#[derive(Debug)]
+pub struct LinearWarmup {
+    steps: usize,
+    after: Box<dyn LrSchedule>,
+}
+
+impl LinearWarmup {
+    pub fn new(steps: usize, after: impl LrSchedule + 'static) -> Self {
+        LinearWarmup { steps, after: Box::new(after) }
+    }
+}
+
+impl LrSchedule for LinearWarmup {
+    fn learning_rate(&self, base: f64, progress: Progress) -> f64 {
+        let lr = self.after.learning_rate(base, progress);
+        if progress.step < self.steps {
+            lr * (progress.step + 1) as f64 / self.steps as f64
+        } else {
+            lr
+        }
+    }
+
+    fn on_epoch_end(&mut self, monitored: f64) {
+        self.after.on_epoch_end(monitored)
+    }
+
+    fn reset(&mut self) {
+        self.after.reset()
+    }
+}
+
+/// 监控的损失连续 `patience` 轮没有至少下降 `min_delta` 后，下一轮学习率乘以 `factor`，
+/// 但不低于 `min_lr`。降低之后重新计数
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub min_delta: f64,
+    pub min_lr: f64,
+    scale: f64,
+    best: f64,
+    epochs_without_improvement: usize,
+}
+
+impl ReduceOnPlateau {
+    pub fn new(factor: f64, patience: usize) -> Self {
+        assert!(factor > 0.0 && factor < 1.0, "factor must be within (0, 1)");
+        ReduceOnPlateau {
+            factor,
+            patience,
+            min_delta: 0.0,
+            min_lr: 0.0,
+            scale: 1.0,
+            best: f64::INFINITY,
+            epochs_without_improvement: 0,
+        }
+    }
+
+    pub fn with_min_delta(mut self, min_delta: f64) -> Self {
+        self.min_delta = min_delta;
+        self
+    }
+
+    pub fn with_min_lr(mut self, min_lr: f64) -> Self {
+        self.min_lr = min_lr;
+        self
+    }
+}
+
+impl LrSchedule for ReduceOnPlateau {
+    fn learning_rate(&self, base: f64, _progress: Progress) -> f64 {
+        (base * self.scale).max(self.min_lr.min(base))
+    }
+
+    fn on_epoch_end(&mut self, monitored: f64) {
+        if monitored < self.best - self.min_delta {
+            self.best = monitored;
+            self.epochs_without_improvement = 0;
+        } else {
+            self.epochs_without_improvement += 1;
+            if self.epochs_without_improvement > self.patience {
+                self.scale *= self.factor;
+                self.epochs_without_improvement = 0;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.scale = 1.0;
+        self.best = f64::INFINITY;
+        self.epochs_without_improvement = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(epoch: f64) -> Progress {
+        Progress { step: 0, epoch }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{} vs {}", actual, expected);
+    }
+
+    #[test]
+    fn test_decay() {
+        let step = StepDecay::new(2, 0.5);
+        let rates: Vec<f64> = [0.0, 1.9, 2.0, 5.5].iter().map(|&e| step.learning_rate(0.1, at(e))).collect();
+        assert_eq!(rates, [0.1, 0.1, 0.05, 0.025]);
+
+        let exponential = ExponentialDecay::new(0.9);
+        assert_close(exponential.learning_rate(1.0, at(2.0)), 0.81);
+        assert_close(exponential.learning_rate(1.0, at(0.5)), 0.9f64.sqrt());
+    }
+
+    #[test]
+    fn test_cosine_annealing() {
+        let cosine = CosineAnnealing::new(4.0).with_min_lr(0.1);
+        assert_close(cosine.learning_rate(1.0, at(0.0)), 1.0);
+        assert_close(cosine.learning_rate(1.0, at(2.0)), 0.55);
+        assert!(cosine.learning_rate(1.0, at(3.99)) < 0.11);
+        // 重启
+        assert_close(cosine.learning_rate(1.0, at(4.0)), 1.0);
+
+        // 周期 2, 4, 8 ...，第二个周期从第 2 轮开始，中点在第 4 轮
+        let growing = CosineAnnealing::new(2.0).with_period_mult(2.0);
+        assert_close(growing.learning_rate(1.0, at(4.0)), 0.5);
+        assert_close(growing.learning_rate(1.0, at(6.0)), 1.0);
+    }
+
+    #[test]
+    fn test_linear_warmup() {
+        let warmup = LinearWarmup::new(4, StepDecay::new(1, 0.5));
+        let rate = |step, epoch| warmup.learning_rate(0.2, Progress { step, epoch });
+        assert_close(rate(0, 0.0), 0.05);
+        assert_close(rate(3, 0.75), 0.2);
+        assert_close(rate(4, 1.0), 0.1);
+        assert_close(LinearWarmup::new(2, Constant).learning_rate(1.0, Progress { step: 10, epoch: 3.0 }), 1.0);
+    }
+
+    #[test]
+    fn test_reduce_on_plateau() {
+        let mut plateau = ReduceOnPlateau::new(0.5, 1).with_min_lr(0.3);
+        let mut rates = Vec::new();
+        for loss in [1.0, 0.8, 0.9, 0.85, 0.7, 0.7, 0.7, 0.7, 0.7] {
+            plateau.on_epoch_end(loss);
+            rates.push(plateau.learning_rate(1.0, at(0.0)));
+        }
+        assert_eq!(rates, [1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.3, 0.3, 0.3]);
+
+        plateau.reset();
+        assert_eq!(plateau, ReduceOnPlateau::new(0.5, 1).with_min_lr(0.3));
+    }
+}
@@ -0,0 +1,205 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::activation::Activation;
+use crate::batch_norm::BatchNorm;
+use crate::conv::{Conv2D, Conv2DSpec};
+use crate::dense::Dense;
+use crate::dropout::Dropout;
+use crate::flatten::Flatten;
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::loss::{Loss, MeanSquaredError};
+use crate::neural_network::{LayerSpec, NeuralNetwork};
+use crate::pooling::Pool2D;
+use crate::recurrent::{Recurrent, RecurrentSpec};
+use crate::time_distributed::TimeDistributed;
+
+/// 逐层搭建网络，每加一层都检查它的输入形状是否与上一层的输出一致
+///
+/// ```
+/// use neural_network::activation::Activation;
+/// use neural_network::loss::CategoricalCrossEntropy;
+/// use neural_network::sequential::Sequential;
+///
+/// let network = Sequential::<f64>::seeded(784, 42)
+///     .dense(64, Activation::ReLU)
+///     .dense(10, Activation::Softmax)
+///     .with_loss(CategoricalCrossEntropy)
+///     .build();
+/// assert_eq!(network.output_size(), 10);
+/// ```
+#[derive(Debug)]
+pub struct Sequential<T: Float = f64> {
+    input: Shape,
+    layers: Vec<Box<dyn Layer<T>>>,
+    loss: Box<dyn Loss<T>>,
+    // 初始化参数用，同一个种子按同样的顺序采样
+    rng: StdRng,
+}
+
+impl<T: Float> Sequential<T> {
+    pub fn new(input: impl Into<Shape>) -> Self {
+        Self::with_rng(input.into(), StdRng::from_entropy())
+    }
+
+    /// 用固定种子初始化参数，结果可复现
+    pub fn seeded(input: impl Into<Shape>, seed: u64) -> Self {
+        Self::with_rng(input.into(), StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(input: Shape, rng: StdRng) -> Self {
+        Sequential { input, layers: Vec::new(), loss: Box::new(MeanSquaredError), rng }
+    }
+
+    /// 当前最后一层的输出形状，还没有层时是网络的输入形状
+    pub fn output_shape(&self) -> Shape {
+        self.layers.last().map_or_else(|| self.input.clone(), |layer| layer.output_shape())
+    }
+
+    /// 全连接层，参数按激活函数的默认方式初始化
+    pub fn dense(self, units: usize, activation: Activation) -> Self {
+        self.dense_with(LayerSpec::new(units, activation))
+    }
+
+    /// 全连接层，可以指定初始化方式
+    pub fn dense_with(mut self, spec: LayerSpec) -> Self {
+        let input_size = self.output_shape().size();
+        let layer = Dense::new(input_size, spec.size, spec.activation, spec.weight_init, spec.bias_init, &mut self.rng)
+            .with_regularizer(spec.regularizer);
+        self.layer(layer)
+    }
+
+    /// 步长 1、不填充的卷积层，上一层的输出必须是 通道 x 高 x 宽
+    pub fn conv2d(self, filters: usize, kernel_size: usize, activation: Activation) -> Self {
+        self.conv2d_with(Conv2DSpec::new(filters, kernel_size, activation))
+    }
+
+    pub fn conv2d_with(mut self, spec: Conv2DSpec) -> Self {
+        let layer = Conv2D::new(self.output_shape(), spec, &mut self.rng);
+        self.layer(layer)
+    }
+
+    /// size x size 的最大池化，步长等于 size
+    pub fn max_pool(self, size: usize) -> Self {
+        let layer = Pool2D::max(self.output_shape(), size);
+        self.layer(layer)
+    }
+
+    /// size x size 的平均池化，步长等于 size
+    pub fn avg_pool(self, size: usize) -> Self {
+        let layer = Pool2D::average(self.output_shape(), size);
+        self.layer(layer)
+    }
+
+    pub fn flatten(self) -> Self {
+        let layer = Flatten::new(self.output_shape());
+        self.layer(layer)
+    }
+
+    /// 多对一的简单循环层，上一层的输出必须是 时间步 x 特征
+    pub fn rnn(self, units: usize) -> Self {
+        self.recurrent(RecurrentSpec::rnn(units))
+    }
+
+    /// 多对一的 LSTM 层
+    pub fn lstm(self, units: usize) -> Self {
+        self.recurrent(RecurrentSpec::lstm(units))
+    }
+
+    /// 多对一的 GRU 层
+    pub fn gru(self, units: usize) -> Self {
+        self.recurrent(RecurrentSpec::gru(units))
+    }
+
+    /// 循环层，多对多、截断 BPTT 和 stateful 模式在 `spec` 中指定
+    pub fn recurrent(mut self, spec: RecurrentSpec) -> Self {
+        let layer = Recurrent::new(self.output_shape(), spec, &mut self.rng);
+        self.layer(layer)
+    }
+
+    /// 对每个时间步分别应用同一个全连接层，上一层的输出必须是 时间步 x 特征
+    pub fn time_distributed_dense(mut self, units: usize, activation: Activation) -> Self {
+        let shape = self.output_shape();
+        let (steps, features) = shape.as_sequence()
+            .unwrap_or_else(|| panic!("time distributed layers expect a steps x features input, got {}", shape));
+        let spec = LayerSpec::new(units, activation);
+        let dense = Dense::new(features, units, activation, spec.weight_init, spec.bias_init, &mut self.rng);
+        self.layer(TimeDistributed::new(steps, dense))
+    }
+
+    /// 训练时以概率 `rate` 丢弃上一层的输出
+    pub fn dropout(mut self, rate: f64) -> Self {
+        let rng = StdRng::from_rng(&mut self.rng).expect("StdRng never fails to seed");
+        let layer = Dropout::with_rng(self.output_shape(), rate, rng);
+        self.layer(layer)
+    }
+
+    /// 对上一层的输出做批归一化
+    pub fn batch_norm(self) -> Self {
+        let layer = BatchNorm::new(self.output_shape());
+        self.layer(layer)
+    }
+
+    /// 添加任意层，输入形状与上一层输出不一致时 panic
+    pub fn layer(mut self, layer: impl Layer<T> + 'static) -> Self {
+        let expected = self.output_shape();
+        assert_eq!(
+            layer.input_shape(),
+            expected,
+            "layer {} expects input {}, but the previous output is {}",
+            self.layers.len(),
+            layer.input_shape(),
+            expected
+        );
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    pub fn with_loss(mut self, loss: impl Loss<T> + 'static) -> Self {
+        self.loss = Box::new(loss);
+        self
+    }
+
+    pub fn build(self) -> NeuralNetwork<T> {
+        let mut network = NeuralNetwork::from_layers(self.layers);
+        network.set_boxed_loss(self.loss);
+        network
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::Initializer;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn test_matches_layer_sizes_shortcut() {
+        let built = Sequential::<f64>::seeded(3, 9)
+            .dense(5, Activation::Tanh)
+            .dense(2, Activation::Sigmoid)
+            .build();
+        let shortcut = NeuralNetwork::<f64>::seeded(&[3, 5, 2], &[Activation::Tanh, Activation::Sigmoid], 9);
+        let input = [0.3, -1.0, 2.0];
+        assert_eq!(built.predict(&input).unwrap(), shortcut.predict(&input).unwrap());
+        assert_eq!(built.layers().len(), 2);
+        assert_eq!(built.layers()[0].param_count(), 3 * 5 + 5);
+    }
+
+    #[test]
+    fn test_add_custom_layer() {
+        let spec = LayerSpec::new(4, Activation::Identity).with_weight_init(Initializer::Constant(0.5));
+        let dense = Dense::<f32>::from_parts(Matrix::new(1, 4, vec![1.0; 4]), vec![0.0], Activation::Identity);
+        let network = Sequential::<f32>::new(2).dense_with(spec).layer(dense).build();
+        // 每个隐藏单元输出 0.5 * (1 + 2)，四个相加
+        assert_eq!(network.predict(&[1.0, 2.0]).unwrap(), vec![6.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expects input [3], but the previous output is [2]")]
+    fn test_shape_mismatch_panics() {
+        let dense = Dense::<f64>::from_parts(Matrix::zeros(1, 3), vec![0.0], Activation::Identity);
+        let _ = Sequential::<f64>::new(2).layer(dense);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::activation::Activation;
+use crate::float::Float;
+use crate::layer::{Layer, Shape};
+use crate::matrix::Matrix;
+use crate::persist::LayerRecord;
+
+/// 对序列的每个时间步分别应用同一个层（共享参数），例如在多对多的循环层之后接全连接输出。
+///
+/// 输入 `[时间步 x 内层输入]`，输出 `[时间步 x 内层输出]`。样本按时间步展开存放，
+/// 所以 N 个样本的批次就是 N·时间步 行的内层批次，只需改变矩阵的行列数。
+#[derive(Debug)]
+pub struct TimeDistributed<T: Float = f64> {
+    steps: usize,
+    layer: Box<dyn Layer<T>>,
+}
+
+impl<T: Float> TimeDistributed<T> {
+    pub fn new(steps: usize, layer: impl Layer<T> + 'static) -> TimeDistributed<T> {
+        Self::from_boxed(steps, Box::new(layer))
+    }
+
+    pub fn from_boxed(steps: usize, layer: Box<dyn Layer<T>>) -> TimeDistributed<T> {
+        assert!(steps > 0, "steps must be positive");
+        TimeDistributed { steps, layer }
+    }
+
+    pub fn inner(&self) -> &dyn Layer<T> {
+        self.layer.as_ref()
+    }
+
+    fn unfold(&self, m: &Matrix<T>) -> Matrix<T> {
+        unfold(self.steps, m)
+    }
+
+    fn fold(&self, m: Matrix<T>) -> Matrix<T> {
+        let (rows, cols) = (m.rows() / self.steps, m.cols() * self.steps);
+        Matrix::new(rows, cols, m.into_vec())
+    }
+
+    fn with_steps(&self, shape: Shape) -> Shape {
+        Shape::new([self.steps].into_iter().chain(shape.dims().iter().copied()).collect())
+    }
+}
+
+/// N x (时间步·k) 改为 (N·时间步) x k
+fn unfold<T: Float>(steps: usize, m: &Matrix<T>) -> Matrix<T> {
+    Matrix::new(m.rows() * steps, m.cols() / steps, m.as_slice().to_vec())
+}
+
+impl<T: Float> Layer<T> for TimeDistributed<T> {
+    fn input_shape(&self) -> Shape {
+        self.with_steps(self.layer.input_shape())
+    }
+
+    fn output_shape(&self) -> Shape {
+        self.with_steps(self.layer.output_shape())
+    }
+
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let output = self.layer.forward(&self.unfold(input));
+        self.fold(output)
+    }
+
+    fn predict(&self, input: &Matrix<T>) -> Matrix<T> {
+        self.fold(self.layer.predict(&self.unfold(input)))
+    }
+
+    fn backward(&mut self, output_grad: &Matrix<T>) -> Matrix<T> {
+        let grad = self.layer.backward(&self.unfold(output_grad));
+        self.fold(grad)
+    }
+
+    /// 内层的激活按行计算，也就是按时间步，可以照样与损失合并求导
+    fn fused_backward(&mut self, delta: &mut dyn FnMut(Activation) -> Option<Matrix<T>>) -> Option<Matrix<T>> {
+        let steps = self.steps;
+        let grad = self.layer.fused_backward(&mut |activation| delta(activation).map(|d| unfold(steps, &d)))?;
+        Some(self.fold(grad))
+    }
+
+    fn parameters(&self) -> Vec<&[T]> {
+        self.layer.parameters()
+    }
+
+    fn gradients(&self) -> Vec<&[T]> {
+        self.layer.gradients()
+    }
+
+    fn visit_parameters(&mut self, f: &mut dyn FnMut(&mut [T], &mut [T])) {
+        self.layer.visit_parameters(f);
+    }
+
+    fn zero_grad(&mut self) {
+        self.layer.zero_grad();
+    }
+
+    fn penalty(&self) -> f64 {
+        self.layer.penalty()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.layer.set_training(training);
+    }
+
+    fn reset_state(&mut self) {
+        self.layer.reset_state();
+    }
+
+    fn visit_state(&mut self, f: &mut dyn FnMut(&mut [T])) {
+        self.layer.visit_state(f);
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.layer.reseed(seed);
+    }
+
+    fn replicate(&self) -> Option<Box<dyn Layer<T>>> {
+        Some(Box::new(TimeDistributed::from_boxed(self.steps, self.layer.replicate()?)))
+    }
+
+    fn to_record(&self) -> Option<LayerRecord> {
+        let layer = Box::new(self.layer.to_record()?);
+        Some(LayerRecord::TimeDistributed { steps: self.steps, layer })
+    }
+}
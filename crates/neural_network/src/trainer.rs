@@ -0,0 +1,572 @@
+use std::thread;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::error::{check_len, NnError};
+use crate::float::Float;
+use crate::matrix::Matrix;
+use crate::neural_network::NeuralNetwork;
+use crate::optimizer::{GradientClip, Optimizer};
+use crate::schedule::{LrSchedule, Progress};
+
+/// 一个批次结束时的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStats {
+    pub epoch: usize,
+    pub batch: usize,
+    /// 本批次样本的平均损失
+    pub loss: f64,
+    /// 本批次更新使用的学习率
+    pub learning_rate: f64,
+}
+
+/// 一轮结束时的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochStats {
+    pub epoch: usize,
+    /// 本轮训练样本的平均损失（参数更新前计算）
+    pub train_loss: f64,
+    pub val_loss: Option<f64>,
+    /// 本轮最后一次更新使用的学习率
+    pub learning_rate: f64,
+}
+
+/// 训练过程的回调
+pub trait Callback {
+    fn on_batch_end(&mut self, _stats: &BatchStats) {}
+
+    /// 返回 `false` 时提前结束训练
+    fn on_epoch_end(&mut self, _stats: &EpochStats) -> bool {
+        true
+    }
+}
+
+/// 训练历史，每轮一条记录
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    pub train_loss: Vec<f64>,
+    /// 没有验证集时为空
+    pub val_loss: Vec<f64>,
+    /// 监控指标（有验证集时为验证损失，否则为训练损失）最好的一轮
+    pub best_epoch: usize,
+    /// 是否因为早停或回调提前结束
+    pub stopped_early: bool,
+    /// 每轮的训练吞吐量（样本数 / 秒），不包括验证的时间
+    pub samples_per_sec: Vec<f64>,
+    /// 每轮最后一次更新使用的学习率
+    pub learning_rate: Vec<f64>,
+}
+
+impl History {
+    pub fn epochs(&self) -> usize {
+        self.train_loss.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EarlyStopping {
+    patience: usize,
+    min_delta: f64,
+}
+
+/// 小批量训练器
+///
+/// ```no_run
+/// use neural_network::neural_network::NeuralNetwork;
+/// use neural_network::optimizer::Adam;
+/// use neural_network::trainer::Trainer;
+///
+/// let data = vec![(vec![0.0, 1.0], vec![1.0]), (vec![1.0, 1.0], vec![0.0])];
+/// let mut network = NeuralNetwork::new(&[2, 4, 1]);
+/// let history = Trainer::new(Adam::new(0.01))
+///     .with_batch_size(2)
+///     .with_epochs(500)
+///     .with_shuffle_seed(42)
+///     .fit(&mut network, &data)
+///     .expect("training failed");
+/// println!("final loss: {:?}", history.train_loss.last());
+/// ```
+pub struct Trainer<T: Float = f64> {
+    optimizer: Box<dyn Optimizer<T>>,
+    batch_size: usize,
+    epochs: usize,
+    shuffle_seed: Option<u64>,
+    validation_split: f64,
+    early_stopping: Option<EarlyStopping>,
+    gradient_clip: Option<GradientClip>,
+    threads: usize,
+    // 调度和它的基准学习率
+    lr_schedule: Option<(Box<dyn LrSchedule>, f64)>,
+    callbacks: Vec<Box<dyn Callback>>,
+}
+
+impl<T: Float> Trainer<T> {
+    /// 默认批大小 32、训练 100 轮、不打乱、不划分验证集、单线程
+    pub fn new(optimizer: impl Optimizer<T> + 'static) -> Self {
+        Trainer {
+            optimizer: Box::new(optimizer),
+            batch_size: 32,
+            epochs: 100,
+            shuffle_seed: None,
+            validation_split: 0.0,
+            early_stopping: None,
+            gradient_clip: None,
+            threads: 1,
+            lr_schedule: None,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// 批大小为 0 时 panic，需要处理错误时用 [`Trainer::try_with_batch_size`]
+    pub fn with_batch_size(self, batch_size: usize) -> Self {
+        self.try_with_batch_size(batch_size).unwrap()
+    }
+
+    pub fn try_with_batch_size(mut self, batch_size: usize) -> Result<Self, NnError> {
+        if batch_size == 0 {
+            return Err(NnError::InvalidConfig("batch size must be positive"));
+        }
+        self.batch_size = batch_size;
+        Ok(self)
+    }
+
+    pub fn with_epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    /// 每轮开始前用该种子的随机数打乱训练集
+    pub fn with_shuffle_seed(mut self, seed: u64) -> Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// 取数据末尾 `fraction` 比例的样本做验证集（在打乱之前划分）。
+    /// 不在 [0, 1) 内时 panic，需要处理错误时用 [`Trainer::try_with_validation_split`]
+    pub fn with_validation_split(self, fraction: f64) -> Self {
+        self.try_with_validation_split(fraction).unwrap()
+    }
+
+    pub fn try_with_validation_split(mut self, fraction: f64) -> Result<Self, NnError> {
+        if !(0.0..1.0).contains(&fraction) {
+            return Err(NnError::InvalidConfig("validation split must be within [0, 1)"));
+        }
+        self.validation_split = fraction;
+        Ok(self)
+    }
+
+    /// 监控指标连续 `patience` 轮没有至少下降 `min_delta` 时停止训练
+    pub fn with_early_stopping(mut self, patience: usize, min_delta: f64) -> Self {
+        self.early_stopping = Some(EarlyStopping { patience, min_delta });
+        self
+    }
+
+    pub fn with_gradient_clip(mut self, clip: GradientClip) -> Self {
+        self.gradient_clip = Some(clip);
+        self
+    }
+
+    /// 数据并行：每个批次按顺序切成 `threads` 段，分给同样数量的网络副本在各自的线程里计算梯度，
+    /// 副本在每个批次开始时从主网络复制参数，梯度按副本顺序求和后由主网络做一次优化器更新。
+    ///
+    /// 求和顺序固定，固定种子和线程数时结果可以复现；与单线程相比只有浮点舍入上的差别，
+    /// 但批归一化的批统计量按每段计算，滑动统计量取各段的加权平均。
+    /// 副本通过 [`Layer::replicate`](crate::layer::Layer::replicate) 创建，dropout 的种子由打乱种子派生。
+    /// 不适合 stateful 的循环层，因为各段的样本在批次间不连续。
+    ///
+    /// 线程数为 0 时 panic，需要处理错误时用 [`Trainer::try_with_threads`]
+    pub fn with_threads(self, threads: usize) -> Self {
+        self.try_with_threads(threads).unwrap()
+    }
+
+    pub fn try_with_threads(mut self, threads: usize) -> Result<Self, NnError> {
+        if threads == 0 {
+            return Err(NnError::InvalidConfig("at least one thread is required"));
+        }
+        self.threads = threads;
+        Ok(self)
+    }
+
+    /// 每次参数更新前按调度设置优化器的学习率，基准学习率是此时优化器的学习率，
+    /// 之后在训练中用 `Optimizer::set_learning_rate` 修改的值会被覆盖。
+    /// 每次 `fit` 开始时重置调度的状态，进度从 0 开始；训练结束后优化器保留最后一次使用的学习率
+    pub fn with_lr_schedule(mut self, schedule: impl LrSchedule + 'static) -> Self {
+        let base = self.optimizer.learning_rate();
+        self.lr_schedule = Some((Box::new(schedule), base));
+        self
+    }
+
+    pub fn with_callback(mut self, callback: impl Callback + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    pub fn optimizer(&self) -> &dyn Optimizer<T> {
+        self.optimizer.as_ref()
+    }
+
+    /// 训练网络，返回每轮的损失记录。
+    /// 开始前检查所有样本的维度；训练中损失出现 NaN 时停止并返回错误，此时网络参数停在出错前一次更新之后。
+    pub fn fit(&mut self, network: &mut NeuralNetwork<T>, data: &[(Vec<T>, Vec<T>)]) -> Result<History, NnError> {
+        if network.layers().is_empty() {
+            return Err(NnError::EmptyNetwork);
+        }
+        for (input, target) in data {
+            check_len("input", network.input_size(), input.len())?;
+            check_len("target", network.output_size(), target.len())?;
+        }
+        let val_len = (data.len() as f64 * self.validation_split).round() as usize;
+        let (train, validation) = data.split_at(data.len() - val_len);
+
+        let mut rng = self.shuffle_seed.map(StdRng::seed_from_u64);
+        let mut order: Vec<usize> = (0..train.len()).collect();
+        let mut history = History::default();
+        let mut best = f64::INFINITY;
+        let mut epochs_without_improvement = 0;
+        let batches = train.len().div_ceil(self.batch_size).max(1);
+        let mut step = 0;
+        if let Some((schedule, _)) = self.lr_schedule.as_mut() {
+            schedule.reset();
+        }
+
+        let mut workers = if self.threads > 1 {
+            (0..self.threads).map(|_| network.replicate()).collect::<Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+        let mut seeds = StdRng::seed_from_u64(self.shuffle_seed.unwrap_or(0));
+        for worker in workers.iter_mut() {
+            worker.layers_mut().iter_mut().for_each(|layer| layer.reseed(seeds.gen()));
+        }
+
+        for epoch in 0..self.epochs {
+            if let Some(rng) = rng.as_mut() {
+                order.shuffle(rng);
+            }
+
+            let started = Instant::now();
+            let mut epoch_loss = 0.0;
+            for (batch, indices) in order.chunks(self.batch_size).enumerate() {
+                let batch_loss = if workers.is_empty() {
+                    network.zero_grad();
+                    // 整个批次拼成矩阵一起前向、反向传播
+                    let (inputs, targets) = batch_matrices(train, indices);
+                    network.forward_batch(&inputs)?;
+                    network.compute_gradients_batch(&targets)?.to_f64()
+                } else {
+                    parallel_gradients(network, &mut workers, train, indices)?
+                };
+                // 批内梯度取平均
+                network.scale_gradients(1.0 / indices.len() as f64);
+                if let Some(clip) = self.gradient_clip {
+                    network.clip_gradients(clip);
+                }
+                if let Some((schedule, base)) = self.lr_schedule.as_ref() {
+                    let progress = Progress { step, epoch: epoch as f64 + batch as f64 / batches as f64 };
+                    self.optimizer.set_learning_rate(schedule.learning_rate(*base, progress));
+                }
+                network.step(self.optimizer.as_mut());
+                step += 1;
+
+                epoch_loss += batch_loss;
+                let learning_rate = self.optimizer.learning_rate();
+                let stats = BatchStats { epoch, batch, loss: batch_loss / indices.len() as f64, learning_rate };
+                self.callbacks.iter_mut().for_each(|callback| callback.on_batch_end(&stats));
+            }
+            history.samples_per_sec.push(train.len() as f64 / started.elapsed().as_secs_f64().max(1e-9));
+
+            let train_loss = epoch_loss / train.len().max(1) as f64;
+            let val_loss = if validation.is_empty() { None } else { Some(evaluate_loss(network, validation)?) };
+            history.train_loss.push(train_loss);
+            history.val_loss.extend(val_loss);
+            let learning_rate = self.optimizer.learning_rate();
+            history.learning_rate.push(learning_rate);
+
+            let monitored = val_loss.unwrap_or(train_loss);
+            let min_delta = self.early_stopping.map_or(0.0, |early| early.min_delta);
+            if monitored < best - min_delta {
+                best = monitored;
+                history.best_epoch = epoch;
+                epochs_without_improvement = 0;
+            } else {
+                epochs_without_improvement += 1;
+            }
+
+            if let Some((schedule, _)) = self.lr_schedule.as_mut() {
+                schedule.on_epoch_end(monitored);
+            }
+
+            let stats = EpochStats { epoch, train_loss, val_loss, learning_rate };
+            let mut keep_going = true;
+            for callback in self.callbacks.iter_mut() {
+                keep_going &= callback.on_epoch_end(&stats);
+            }
+            let patience_exhausted = self
+                .early_stopping
+                .is_some_and(|early| epochs_without_improvement > early.patience);
+            if !keep_going || patience_exhausted {
+                history.stopped_early = epoch + 1 < self.epochs;
+                break;
+            }
+        }
+        Ok(history)
+    }
+}
+
+fn batch_matrices<T: Float>(data: &[(Vec<T>, Vec<T>)], indices: &[usize]) -> (Matrix<T>, Matrix<T>) {
+    let inputs = Matrix::from_rows(&indices.iter().map(|&index| &data[index].0).collect::<Vec<_>>());
+    let targets = Matrix::from_rows(&indices.iter().map(|&index| &data[index].1).collect::<Vec<_>>());
+    (inputs, targets)
+}
+
+/// 每个副本在自己的线程里计算一段批次的梯度，再按副本顺序累加到主网络，返回各样本损失之和
+fn parallel_gradients<T: Float>(
+    network: &mut NeuralNetwork<T>,
+    workers: &mut [NeuralNetwork<T>],
+    data: &[(Vec<T>, Vec<T>)],
+    indices: &[usize],
+) -> Result<f64, NnError> {
+    let shards: Vec<&[usize]> = indices.chunks(indices.len().div_ceil(workers.len())).collect();
+    let workers = &mut workers[..shards.len()];
+    let state = network.state();
+    let (master, state) = (&*network, &state);
+    let losses: Vec<Result<T, NnError>> = thread::scope(|scope| {
+        let handles: Vec<_> = workers.iter_mut().zip(&shards).map(|(worker, shard)| {
+            scope.spawn(move || {
+                worker.copy_parameters_from(master);
+                worker.set_state(state);
+                worker.zero_grad();
+                let (inputs, targets) = batch_matrices(data, shard);
+                worker.forward_batch(&inputs)?;
+                worker.compute_gradients_with(master.loss(), &targets)
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().expect("training worker panicked")).collect()
+    });
+
+    network.zero_grad();
+    let mut total = 0.0;
+    for (worker, loss) in workers.iter().zip(losses) {
+        total += loss?.to_f64();
+        network.add_gradients_from(worker);
+    }
+    // 前向传播更新的状态按每段的样本数加权平均
+    if !state.is_empty() {
+        let mut average: Vec<Vec<T>> = state.iter().map(|group| vec![T::zero(); group.len()]).collect();
+        for (worker, shard) in workers.iter_mut().zip(&shards) {
+            let weight = T::from_f64(shard.len() as f64 / indices.len() as f64);
+            for (sum, group) in average.iter_mut().zip(worker.state()) {
+                sum.iter_mut().zip(group).for_each(|(s, v)| *s += weight * v);
+            }
+        }
+        network.set_state(&average);
+    }
+    Ok(total)
+}
+
+/// 数据集上的平均损失，只做推理
+pub fn evaluate_loss<T: Float>(network: &NeuralNetwork<T>, data: &[(Vec<T>, Vec<T>)]) -> Result<f64, NnError> {
+    let mut total = 0.0;
+    for (input, target) in data {
+        total += network.loss().checked_value(&network.predict(input)?, target)?.to_f64();
+    }
+    Ok(total / data.len().max(1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activation::Activation;
+    use crate::optimizer::{Adam, Sgd};
+    use crate::schedule::{LinearWarmup, ReduceOnPlateau, StepDecay};
+    use crate::sequential::Sequential;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn xor() -> Vec<(Vec<f64>, Vec<f64>)> {
+        vec![
+            (vec![0.0, 0.0], vec![0.0]),
+            (vec![0.0, 1.0], vec![1.0]),
+            (vec![1.0, 0.0], vec![1.0]),
+            (vec![1.0, 1.0], vec![0.0]),
+        ]
+    }
+
+    /// 记录回调次数
+    #[derive(Default)]
+    struct Counter {
+        batches: Rc<RefCell<Vec<(usize, usize)>>>,
+        epochs: Rc<RefCell<usize>>,
+        stop_after: Option<usize>,
+    }
+
+    impl Callback for Counter {
+        fn on_batch_end(&mut self, stats: &BatchStats) {
+            self.batches.borrow_mut().push((stats.epoch, stats.batch));
+        }
+
+        fn on_epoch_end(&mut self, stats: &EpochStats) -> bool {
+            *self.epochs.borrow_mut() += 1;
+            self.stop_after.is_none_or(|last| stats.epoch < last)
+        }
+    }
+
+    #[test]
+    fn test_fit_xor() {
+        let mut network = NeuralNetwork::with_activations(&[2, 8, 1], &[Activation::Tanh, Activation::Sigmoid]);
+        let history = Trainer::new(Adam::new(0.05))
+            .with_batch_size(4)
+            .with_epochs(500)
+            .with_shuffle_seed(7)
+            .fit(&mut network, &xor()).unwrap();
+        assert_eq!(history.epochs(), 500);
+        assert!(history.val_loss.is_empty());
+        assert!(history.train_loss[499] < history.train_loss[0]);
+        for (input, target) in xor() {
+            assert!((network.predict(&input).unwrap()[0] - target[0]).abs() < 0.2, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_batches_and_callbacks() {
+        let data: Vec<_> = (0..10).map(|i| (vec![i as f64 / 10.0], vec![0.0])).collect();
+        let counter = Counter { stop_after: Some(2), ..Default::default() };
+        let (batches, epochs) = (counter.batches.clone(), counter.epochs.clone());
+
+        let mut network = NeuralNetwork::new(&[1, 1]);
+        let history = Trainer::new(Sgd::new(0.1))
+            .with_batch_size(3)
+            .with_epochs(10)
+            .with_validation_split(0.2)
+            .with_callback(counter)
+            .fit(&mut network, &data).unwrap();
+
+        // 8 个训练样本、批大小 3：每轮 3 个批次；回调在第 2 轮结束时要求停止
+        assert_eq!(*epochs.borrow(), 3);
+        assert_eq!(batches.borrow().len(), 9);
+        assert_eq!(batches.borrow()[3..6], [(1, 0), (1, 1), (1, 2)]);
+        assert_eq!(history.epochs(), 3);
+        assert_eq!(history.val_loss.len(), 3);
+        assert!(history.stopped_early);
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        // 学习率为 0，损失不会下降
+        let mut network = NeuralNetwork::new(&[2, 3, 1]);
+        let history = Trainer::new(Sgd::new(0.0))
+            .with_epochs(100)
+            .with_early_stopping(3, 1e-6)
+            .fit(&mut network, &xor()).unwrap();
+        assert_eq!(history.best_epoch, 0);
+        assert_eq!(history.epochs(), 5);
+        assert!(history.stopped_early);
+    }
+
+    #[test]
+    fn test_lr_schedule() {
+        // 每轮 2 个批次：前 4 次更新预热，同时每轮减半
+        let mut network = NeuralNetwork::new(&[2, 3, 1]);
+        let mut trainer = Trainer::new(Sgd::new(0.4))
+            .with_batch_size(2)
+            .with_epochs(3)
+            .with_lr_schedule(LinearWarmup::new(4, StepDecay::new(1, 0.5)));
+        let history = trainer.fit(&mut network, &xor()).unwrap();
+        assert_eq!(history.learning_rate, [0.2, 0.2, 0.1]);
+        assert_eq!(trainer.optimizer().learning_rate(), 0.1);
+
+        // 学习率很小，验证损失几乎不变：每 2 轮没有改善就减半
+        let mut network = NeuralNetwork::new(&[2, 3, 1]);
+        let history = Trainer::new(Sgd::new(1e-9))
+            .with_epochs(6)
+            .with_validation_split(0.25)
+            .with_lr_schedule(ReduceOnPlateau::new(0.5, 1).with_min_delta(1e-6))
+            .fit(&mut network, &xor()).unwrap();
+        assert_eq!(history.learning_rate, [1e-9, 1e-9, 1e-9, 5e-10, 5e-10, 2.5e-10]);
+
+        // 再次训练时从基准学习率重新开始
+        let mut trainer = Trainer::new(Sgd::new(1e-9))
+            .with_epochs(4)
+            .with_validation_split(0.25)
+            .with_lr_schedule(ReduceOnPlateau::new(0.5, 1).with_min_delta(1e-6));
+        let first = trainer.fit(&mut network, &xor()).unwrap();
+        let second = trainer.fit(&mut network, &xor()).unwrap();
+        assert_eq!(second.learning_rate, first.learning_rate);
+    }
+
+    #[test]
+    fn test_same_seed_same_history() {
+        let template = NeuralNetwork::new(&[2, 4, 1]);
+        let run = || {
+            let mut network = NeuralNetwork::new(&[2, 4, 1]);
+            network.copy_parameters_from(&template);
+            Trainer::new(Sgd::new(0.5).with_momentum(0.9))
+                .with_batch_size(2)
+                .with_epochs(20)
+                .with_shuffle_seed(42)
+                .fit(&mut network, &xor()).unwrap()
+        };
+        // 吞吐量每次都不同
+        let losses = |history: History| History { samples_per_sec: Vec::new(), ..history };
+        assert_eq!(losses(run()), losses(run()));
+    }
+
+    #[test]
+    fn test_parallel_training() {
+        let data: Vec<_> = (0..40).map(|i| {
+            let x = i as f64 / 40.0;
+            (vec![x, 1.0 - x, (x * 6.0).sin()], vec![(x * 3.0).cos(), x * x])
+        }).collect();
+        let run = |threads: usize, regularized: bool| {
+            let mut builder = Sequential::<f64>::seeded(3, 5).dense(8, Activation::Tanh);
+            if regularized {
+                builder = builder.batch_norm().dropout(0.2);
+            }
+            let mut network = builder.dense(2, Activation::Identity).build();
+            let history = Trainer::new(Adam::new(0.01))
+                .with_batch_size(10)
+                .with_epochs(5)
+                .with_shuffle_seed(3)
+                .with_threads(threads)
+                .fit(&mut network, &data).unwrap();
+            (history, network.predict(&[0.3, 0.7, 0.5]).unwrap())
+        };
+
+        // 只有浮点舍入上的差别
+        let ((single, single_output), (parallel, parallel_output)) = (run(1, false), run(4, false));
+        assert_eq!(parallel.samples_per_sec.len(), 5);
+        assert!(parallel.samples_per_sec.iter().all(|&rate| rate > 0.0));
+        for (a, b) in single.train_loss.iter().zip(&parallel.train_loss).chain(single_output.iter().zip(&parallel_output)) {
+            assert!((a - b).abs() < 1e-9, "{} vs {}", a, b);
+        }
+        // 同样的种子和线程数，结果逐位相同
+        let ((first, first_output), (second, second_output)) = (run(3, true), run(3, true));
+        assert_eq!(first.train_loss, second.train_loss);
+        assert_eq!(first_output, second_output);
+    }
+
+    #[test]
+    fn test_fit_errors() {
+        let mut network = NeuralNetwork::new(&[2, 3, 1]);
+        let mut data = xor();
+        data[2].1.push(0.0);
+        assert_eq!(
+            Trainer::new(Sgd::new(0.1)).fit(&mut network, &data),
+            Err(NnError::ShapeMismatch { what: "target", expected: 1, actual: 2 })
+        );
+        // 学习率极大时参数发散成 NaN
+        let mut network = NeuralNetwork::with_activations(&[2, 3, 1], &[Activation::Identity, Activation::Identity]);
+        let result = Trainer::new(Sgd::new(1e300)).with_batch_size(1).with_epochs(10).fit(&mut network, &xor());
+        assert!(matches!(result, Err(NnError::NaN(_))), "{:?}", result);
+
+        let invalid = |result: Result<Trainer<f64>, NnError>| matches!(result, Err(NnError::InvalidConfig(_)));
+        assert!(invalid(Trainer::new(Sgd::new(0.1)).try_with_batch_size(0)));
+        assert!(invalid(Trainer::new(Sgd::new(0.1)).try_with_validation_split(1.0)));
+        assert!(invalid(Trainer::new(Sgd::new(0.1)).try_with_validation_split(f64::NAN)));
+        assert!(invalid(Trainer::new(Sgd::new(0.1)).try_with_threads(0)));
+        assert!(Trainer::<f64>::new(Sgd::new(0.1)).try_with_threads(2).is_ok());
+    }
+}